@@ -1,13 +1,31 @@
 use super::operations;
 use super::system;
+use super::workspaces;
 
 pub fn get_handlers() -> Box<dyn Fn(tauri::Invoke<tauri::Wry>) + Send + Sync> {
   Box::new(tauri::generate_handler![
     system::system_commands::get_system_theme,
     system::system_commands::set_system_theme,
+    system::system_commands::get_complex_mode,
+    system::system_commands::set_complex_mode,
+    system::system_commands::get_number_mode,
+    system::system_commands::set_number_mode,
+    system::system_commands::set_decimal_options,
+    system::system_commands::get_angle_mode,
+    system::system_commands::set_angle_mode,
+    system::system_commands::get_holidays,
+    system::system_commands::set_holidays,
+    system::system_commands::get_format_options,
+    system::system_commands::set_format_options,
     operations::operations_commands::store_operation_command,
     operations::operations_commands::get_operation_history_command,
     operations::operations_commands::clear_operation_history_command,
     operations::operations_commands::get_result_command,
+    operations::operations_commands::get_signatures_command,
+    workspaces::workspaces_commands::get_workspaces_command,
+    workspaces::workspaces_commands::create_workspace_command,
+    workspaces::workspaces_commands::rename_workspace_command,
+    workspaces::workspaces_commands::switch_workspace_command,
+    workspaces::workspaces_commands::delete_workspace_command,
   ])
 }
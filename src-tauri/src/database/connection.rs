@@ -16,14 +16,138 @@ pub fn get_connection() -> Result<Connection> {
   Ok(conn)
 }
 
+const CREATE_OPERATIONS: &str = "CREATE TABLE IF NOT EXISTS operations (id integer NOT NULL, workspace integer NOT NULL DEFAULT 1, operation text NOT NULL, result num DEFAULT NULL, representation text DEFAULT NULL, note text DEFAULT NULL, PRIMARY KEY (id));";
+
+const CREATE_VARIABLES: &str = "CREATE TABLE IF NOT EXISTS variables (workspace integer NOT NULL DEFAULT 1, name text NOT NULL,value num DEFAULT NULL, constant integer NOT NULL DEFAULT 0, formula text DEFAULT NULL, PRIMARY KEY (workspace, name));";
+
 fn create_initial_tables(conn: &Connection) -> Result<()> {
   conn.execute(
-    "CREATE TABLE IF NOT EXISTS operations (id integer NOT NULL, operation text NOT NULL, result num NOT NULL, PRIMARY KEY (id));",
-    params![]
+    "CREATE TABLE IF NOT EXISTS workspaces (id integer NOT NULL, name text NOT NULL UNIQUE, active integer NOT NULL DEFAULT 0, PRIMARY KEY (id));",
+    params![],
+  )?;
+  // The history of databases created before workspaces goes to the first one
+  conn.execute(
+    "INSERT INTO workspaces (id, name, active) SELECT 1, 'Default', 1 WHERE NOT EXISTS (SELECT id FROM workspaces);",
+    params![],
+  )?;
+  conn.execute(CREATE_OPERATIONS, params![])?;
+  // Databases created before `to hex`-like representations lack the column
+  if conn.prepare("SELECT representation FROM operations LIMIT 0;").is_err() {
+    conn.execute(
+      "ALTER TABLE operations ADD COLUMN representation text DEFAULT NULL;",
+      params![],
+    )?;
+  }
+  // Databases created before `help` entries lack the column
+  if conn.prepare("SELECT note FROM operations LIMIT 0;").is_err() {
+    conn.execute("ALTER TABLE operations ADD COLUMN note text DEFAULT NULL;", params![])?;
+  }
+  if conn.prepare("SELECT workspace FROM operations LIMIT 0;").is_err() {
+    conn.execute(
+      "ALTER TABLE operations ADD COLUMN workspace integer NOT NULL DEFAULT 1;",
+      params![],
+    )?;
+  }
+  // The notes written by `help` have no result, the table is copied to make it optional
+  let result_required: bool = conn.query_row(
+    "SELECT \"notnull\" FROM pragma_table_info('operations') WHERE name = 'result';",
+    params![],
+    |r| r.get(0),
   )?;
+  if result_required {
+    conn.execute_batch(&format!(
+      "BEGIN;
+      ALTER TABLE operations RENAME TO operations_old;
+      {}
+      INSERT INTO operations (id, workspace, operation, result, representation, note)
+        SELECT id, workspace, operation, CASE WHEN note IS NULL THEN result END,
+          representation, note
+        FROM operations_old;
+      DROP TABLE operations_old;
+      COMMIT;",
+      CREATE_OPERATIONS
+    ))?;
+  }
+  conn.execute(CREATE_VARIABLES, params![])?;
+  if conn.prepare("SELECT constant FROM variables LIMIT 0;").is_err() {
+    conn.execute(
+      "ALTER TABLE variables ADD COLUMN constant integer NOT NULL DEFAULT 0;",
+      params![],
+    )?;
+  }
+  // The workspace is part of the primary key, the table is copied to change it
+  if conn.prepare("SELECT workspace FROM variables LIMIT 0;").is_err() {
+    conn.execute_batch(&format!(
+      "BEGIN;
+      ALTER TABLE variables RENAME TO variables_old;
+      {}
+      INSERT INTO variables (name, value, constant) SELECT name, value, constant FROM variables_old;
+      DROP TABLE variables_old;
+      COMMIT;",
+      CREATE_VARIABLES
+    ))?;
+  }
+  // The expression of a variable defined with `:=`, it is recomputed when its inputs change
+  if conn.prepare("SELECT formula FROM variables LIMIT 0;").is_err() {
+    conn.execute("ALTER TABLE variables ADD COLUMN formula text DEFAULT NULL;", params![])?;
+  }
   conn.execute(
-    "CREATE TABLE IF NOT EXISTS variables (name text NOT NULL,value num DEFAULT NULL, PRIMARY KEY (name));",
+    "CREATE TABLE IF NOT EXISTS units (name text NOT NULL, value num NOT NULL, PRIMARY KEY (name));",
+    params![],
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS rates (code text NOT NULL, rate num NOT NULL, updated integer NOT NULL, PRIMARY KEY (code));",
     params![],
   )?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_migrations() {
+    let conn = Connection::open_in_memory().expect("Could not create in-memory database");
+    conn
+      .execute_batch(
+        "CREATE TABLE operations (id integer NOT NULL, operation text NOT NULL, result num NOT NULL, PRIMARY KEY (id));
+        CREATE TABLE variables (name text NOT NULL,value num DEFAULT NULL, PRIMARY KEY (name));
+        INSERT INTO operations (operation, result) VALUES ('x = 2', 2);
+        INSERT INTO variables (name, value) VALUES ('x', 2);",
+      )
+      .expect("Could not create the tables of an old database");
+
+    create_initial_tables(&conn).expect("Could not migrate the database");
+    // Running them again leaves the database as it is
+    create_initial_tables(&conn).expect("Could not migrate the database");
+    let variables: Vec<(i64, String, f64, bool, Option<String>)> = conn
+      .prepare("SELECT workspace, name, value, constant, formula FROM variables;")
+      .and_then(|mut stmt| {
+        stmt
+          .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))?
+          .collect()
+      })
+      .expect("Could not get variables");
+    assert_eq!(variables, vec![(1, "x".to_string(), 2., false, None)]);
+    let workspace: i64 = conn
+      .query_row("SELECT workspace FROM operations WHERE note IS NULL;", [], |r| r.get(0))
+      .expect("Could not get operation");
+    assert_eq!(workspace, 1);
+    // A note has no result
+    conn
+      .execute("INSERT INTO operations (operation, note) VALUES ('help', 'sin(x)');", params![])
+      .expect("Could not insert note");
+    let workspaces: Vec<(i64, String, bool)> = conn
+      .prepare("SELECT id, name, active FROM workspaces;")
+      .and_then(|mut stmt| {
+        stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?.collect()
+      })
+      .expect("Could not get workspaces");
+    assert_eq!(workspaces, vec![(1, "Default".to_string(), true)]);
+    // Two workspaces may have variables of the same name
+    conn
+      .execute("INSERT INTO variables (workspace, name, value) VALUES (2, 'x', 3);", params![])
+      .expect("Could not insert variable");
+  }
+}
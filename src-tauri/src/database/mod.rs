@@ -1,3 +1,4 @@
 mod connection;
+mod value;
 
 pub use connection::get_connection;
@@ -0,0 +1,83 @@
+use super::super::parser::{from_json, to_json, Value};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+/// Real results are stored as numbers, everything else (complex numbers, lists, decimals,
+/// fractions and big integers, which must not lose digits, and NaN, which SQLite would turn into
+/// NULL) is stored as text.
+impl ToSql for Value {
+  fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+    match *self {
+      Value::Real(x) if x.is_nan() => Ok(ToSqlOutput::from(x.to_string())),
+      Value::Real(x) => Ok(ToSqlOutput::from(x)),
+      _ => Ok(ToSqlOutput::from(to_json(self))),
+    }
+  }
+}
+
+impl FromSql for Value {
+  fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+    match value {
+      ValueRef::Real(x) => Ok(Value::Real(x)),
+      ValueRef::Integer(i) => Ok(Value::Real(i as f64)),
+      ValueRef::Text(_) => {
+        let text = value.as_str()?;
+        match text.parse::<f64>() {
+          Ok(x) => Ok(Value::Real(x)),
+          Err(_) => from_json(text).map_err(|e| FromSqlError::Other(Box::new(e))),
+        }
+      }
+      _ => Err(FromSqlError::InvalidType),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::super::parser::{builtin, eval_value_str_with_context, NumberMode};
+  use super::*;
+  use num_complex::Complex64;
+  use rusqlite::{params, Connection};
+
+  /// A result of the calculator in a number mode, e.g. an exact decimal.
+  fn eval(input: &str, number_mode: NumberMode) -> Value {
+    let mut context = builtin();
+    context.set_number_mode(number_mode);
+    eval_value_str_with_context(input, &context).expect("Could not evaluate").1
+  }
+
+  #[test]
+  fn test_value_roundtrip() {
+    let conn = Connection::open_in_memory().expect("Could not create in-memory database");
+    conn
+      .execute("CREATE TABLE t (id integer NOT NULL, v num NOT NULL, PRIMARY KEY (id));", params![])
+      .expect("Could not create table");
+
+    let values = [
+      Value::Real(2.5),
+      Value::Real(f64::INFINITY),
+      Value::Complex(Complex64::new(3., -4.)),
+      Value::List(vec![Value::Real(1.), Value::Real(2.)]),
+      eval("0.1000000000000000000000000000000001", NumberMode::Decimal),
+      eval("mixed(9/8)", NumberMode::Fraction),
+      Value::Integer("30414093201713378043612608166064768844377641568960512000000000000".parse().unwrap()),
+      eval("amortize(1000, 0.01, 2)", NumberMode::Float),
+    ];
+    for value in values.iter() {
+      conn
+        .execute("INSERT INTO t (v) VALUES (?1);", params![value])
+        .expect("Could not insert value");
+    }
+    conn
+      .execute("INSERT INTO t (v) VALUES (?1);", params![Value::Real(f64::NAN)])
+      .expect("Could not insert NaN");
+
+    let mut stmt = conn.prepare("SELECT v FROM t ORDER BY id ASC;").unwrap();
+    let stored: Vec<Value> = stmt
+      .query_map(params![], |row| row.get(0))
+      .unwrap()
+      .map(|v| v.unwrap())
+      .collect();
+    assert_eq!(&stored[..8], &values[..]);
+    assert!(matches!(stored[8], Value::Real(x) if x.is_nan()));
+  }
+}
@@ -14,15 +14,17 @@ mod menu;
 mod operations;
 mod parser;
 mod system;
+mod workspaces;
 
 fn main() {
   let config = system::get_config();
 
   let conn = get_connection().expect("Could not get connection");
-  let context = create_context_from_db(&conn);
+  let mut context = create_context_from_db(&conn);
+  config.configure_context(&mut context);
 
   tauri::Builder::default()
-    .menu(menu::init())
+    .menu(menu::init(&config))
     .on_menu_event(menu::on_menu_event)
     .manage(Mutex::new(config))
     .manage(Mutex::new(conn))
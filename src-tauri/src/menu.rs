@@ -1,10 +1,18 @@
 use super::operations::clear_operation_history;
-use super::parser::Context;
+use super::parser::{AngleMode, Context};
+use super::system::Config;
 use rusqlite::Connection;
 use std::sync::Mutex;
 use tauri::{CustomMenuItem, Manager, Menu, MenuItem, Submenu, WindowMenuEvent};
 
-pub fn init() -> Menu {
+/// The items of the Angle menu, only the one of the current mode is checked.
+const ANGLE_MODES: [(&str, &str, AngleMode); 3] = [
+  ("angle_radians", "Radians", AngleMode::Radians),
+  ("angle_degrees", "Degrees", AngleMode::Degrees),
+  ("angle_gradians", "Gradians", AngleMode::Gradians),
+];
+
+pub fn init(config: &Config) -> Menu {
   let root_submenu = Submenu::new("", Menu::new().add_native_item(MenuItem::Quit));
   let clear_history = CustomMenuItem::new("clear_history", "Clear History");
   let editmenu = Submenu::new(
@@ -16,7 +24,23 @@ pub fn init() -> Menu {
       .add_native_item(MenuItem::SelectAll)
       .add_item(clear_history),
   );
-  let menu = Menu::new().add_submenu(root_submenu).add_submenu(editmenu);
+  let anglemenu = Submenu::new(
+    "Angle",
+    ANGLE_MODES
+      .iter()
+      .fold(Menu::new(), |menu, &(id, title, angle_mode)| {
+        let item = CustomMenuItem::new(id, title);
+        menu.add_item(if angle_mode == config.angle_mode {
+          item.selected()
+        } else {
+          item
+        })
+      }),
+  );
+  let menu = Menu::new()
+    .add_submenu(root_submenu)
+    .add_submenu(editmenu)
+    .add_submenu(anglemenu);
   menu
 }
 
@@ -33,6 +57,33 @@ pub fn on_menu_event(event: WindowMenuEvent) {
         Err(e) => println!("Error: {}", e),
       }
     }
-    _ => {}
+    id => {
+      if let Some(&(_, _, angle_mode)) = ANGLE_MODES.iter().find(|&&(item, _, _)| item == id) {
+        set_angle_mode(&event, angle_mode);
+      }
+    }
+  }
+}
+
+/// Switches the angle mode in the preferences and in the parser context, and checks its menu item.
+fn set_angle_mode(event: &WindowMenuEvent, angle_mode: AngleMode) {
+  let window = event.window();
+  window
+    .state::<Mutex<Config>>()
+    .lock()
+    .unwrap()
+    .update_angle_mode(angle_mode);
+  window
+    .state::<Mutex<Context>>()
+    .lock()
+    .unwrap()
+    .set_angle_mode(angle_mode);
+  for &(id, _, mode) in ANGLE_MODES.iter() {
+    window
+      .menu_handle()
+      .get_item(id)
+      .set_selected(mode == angle_mode)
+      .expect("Could not update the menu");
   }
+  window.emit_all("angle_mode_changed", angle_mode).unwrap();
 }
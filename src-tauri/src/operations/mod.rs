@@ -1,4 +1,5 @@
 pub mod operations_commands;
 mod operations_service;
+mod rates_service;
 
 pub use operations_service::{clear_operation_history, create_context_from_db};
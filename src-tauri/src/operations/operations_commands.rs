@@ -1,7 +1,8 @@
-use super::super::parser::Context;
+use super::super::parser::{Context, Signature};
+use super::super::system::Config;
 use super::operations_service::{
-  calculate_result, check_if_command, clear_operation_history, get_operation_history, run_command,
-  store_operation, Operation,
+  calculate_result, check_if_command, clear_operation_history, get_operation_history,
+  representation_of, run_command, store_operation, FormattedValue, Operation,
 };
 use rusqlite::Connection;
 use std::result::Result;
@@ -13,6 +14,7 @@ use tauri::{State, Window};
 /// Arguments:
 ///
 /// * `conn`: This is the global sqlite connection (tauri passses it to the function for us).
+/// * `config`: The preferences, the result is formatted with them.
 /// * `operation`: String - This is the operation that will be stored.
 /// * `result`: f64 - This is the result of the operation.
 ///
@@ -22,19 +24,19 @@ use tauri::{State, Window};
 #[tauri::command]
 pub fn store_operation_command(
   conn: State<Mutex<Connection>>,
+  config: State<Mutex<Config>>,
   parser_context: State<Mutex<Context>>,
   window: Window,
   input: &str,
 ) -> Result<(), String> {
   match check_if_command(&input) {
     true => {
-      run_command(
+      return run_command(
         &input,
         &conn.lock().unwrap(),
         &mut parser_context.lock().unwrap(),
         &window,
       );
-      return Ok(());
     }
     false => {}
   };
@@ -42,6 +44,7 @@ pub fn store_operation_command(
     input,
     &mut conn.lock().unwrap(),
     &mut parser_context.lock().unwrap(),
+    &config.lock().unwrap().format,
     window,
   ) {
     Ok(_) => Ok(()),
@@ -51,17 +54,33 @@ pub fn store_operation_command(
   }
 }
 
+/// Evaluates an operation without storing it, for the live preview
+///
+/// Arguments:
+///
+/// * `input`: The operation to evaluate.
+/// * `config`: The preferences, the result is formatted with them.
+/// * `parser_context`: The math context used to evaluate operations.
+///
+/// Returns:
+///
+/// The result and its formatted display.
 #[tauri::command]
 pub fn get_result_command(
   input: &str,
+  config: State<Mutex<Config>>,
   parser_context: State<Mutex<Context>>,
-) -> Result<f64, String> {
+) -> Result<FormattedValue, String> {
   match check_if_command(&input) {
     true => return Err("command".to_string()),
     false => {}
   };
   match calculate_result(input, &parser_context.lock().unwrap()) {
-    Ok(result) => Ok(result),
+    Ok(result) => Ok(FormattedValue::new(
+      result,
+      representation_of(input),
+      &config.lock().unwrap().format,
+    )),
     Err(err) => Err(err.to_string()),
   }
 }
@@ -91,13 +110,17 @@ pub fn clear_operation_history_command(
 /// Arguments:
 ///
 /// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+/// * `config`: The preferences, the results are formatted with them.
 ///
 /// Returns:
 ///
 /// A vector of operations.
 #[tauri::command]
-pub fn get_operation_history_command(conn: State<Mutex<Connection>>) -> Vec<Operation> {
-  match get_operation_history(&conn.lock().unwrap()) {
+pub fn get_operation_history_command(
+  conn: State<Mutex<Connection>>,
+  config: State<Mutex<Config>>,
+) -> Vec<Operation> {
+  match get_operation_history(&conn.lock().unwrap(), &config.lock().unwrap().format) {
     Ok(operations) => operations,
     Err(e) => {
       println!("Error: {}", e);
@@ -105,3 +128,17 @@ pub fn get_operation_history_command(conn: State<Mutex<Connection>>) -> Vec<Oper
     }
   }
 }
+
+/// Returns the signatures of the builtin and user functions, for the tooltips of the input
+///
+/// Arguments:
+///
+/// * `parser_context`: The math context used to evaluate operations.
+///
+/// Returns:
+///
+/// The parameters, domain, description and example of the functions, sorted by name.
+#[tauri::command]
+pub fn get_signatures_command(parser_context: State<Mutex<Context>>) -> Vec<Signature> {
+  parser_context.lock().unwrap().all_signatures()
+}
@@ -1,35 +1,152 @@
-use super::super::parser::{builtin, eval_str_with_context, Context, Error as ParserError};
+use super::super::parser::parsers::{
+  ends_with_representation, starts_with_assignment, starts_with_constant_definition,
+  starts_with_formula, starts_with_unit_definition,
+};
+use super::super::parser::{
+  builtin, eval_value_str_with_context, format_represented, Context, Error as ParserError,
+  FormatOptions, Representation, Signature, Token, Unit, Value,
+};
+use super::super::workspaces::current_workspace;
+use super::rates_service::{is_rate_command, load_rates, run_rate_command};
 use rusqlite::{params, Connection, Result};
 use serde::{Deserialize, Serialize};
 use tauri::Window;
 
 pub fn create_context_from_db<'a>(conn: &Connection) -> Context<'a> {
+  // The rates are a library below the layer of the workspace
   let mut context = builtin();
+  load_rates(conn, &mut context);
+  context.push_layer();
 
   let mut stmt = conn
-    .prepare("SELECT name, value FROM variables")
+    .prepare("SELECT name, value, constant, formula FROM variables WHERE workspace = ?1")
     .expect("Could not prepare statement");
 
+  let mut rows = stmt
+    .query_map(params![current_workspace(conn)], |row| {
+      let name: String = row.get(0).expect("Could not get name");
+      // A formula that failed has no value
+      let value: Option<Value> = row.get(1).expect("Could not get value");
+      let constant: bool = row.get(2).expect("Could not get constant");
+      let formula: Option<String> = row.get(3).expect("Could not get formula");
+      Ok((name, value, constant, formula))
+    })
+    .expect("Could not query variables");
+  while let Some(Ok((name, value, constant, formula))) = rows.next() {
+    match (formula, value) {
+      (Some(formula), _) => {
+        context.set_formula(name, formula);
+      }
+      (None, Some(value)) if constant => {
+        context.define_constant(name, value);
+      }
+      (None, Some(value)) => {
+        context.value_var(name, value);
+      }
+      (None, None) => {}
+    }
+  }
+
+  let mut stmt = conn
+    .prepare("SELECT name, value FROM units ORDER BY rowid ASC")
+    .expect("Could not prepare statement");
   let mut rows = stmt
     .query_map(params![], |row| {
       let name: String = row.get(0).expect("Could not get name");
-      let value: f64 = row.get(1).expect("Could not get value");
+      let value: Value = row.get(1).expect("Could not get value");
       Ok((name, value))
     })
-    .expect("Could not query variables");
+    .expect("Could not query units");
   while let Some(Ok((name, value))) = rows.next() {
-    context.var(name, value);
+    if let Ok(unit) = Unit::define(&name, &value) {
+      context.unit(name, unit);
+    }
   }
 
+  // The formulas may read the variables and units loaded in any order
+  context.recompute_all();
   context
 }
 
 pub fn check_if_command(input: &str) -> bool {
   let commands = vec!["clear", "exit"];
   commands.contains(&input)
+    || is_rate_command(input)
+    || use_command(input).is_some()
+    || seed_command(input).is_some()
+    || help_command(input).is_some()
+}
+
+/// The namespace of a `use phys` command, which brings its constants into scope for the session.
+fn use_command(input: &str) -> Option<&str> {
+  match input.split_whitespace().collect::<Vec<&str>>().as_slice() {
+    ["use", namespace] => Some(*namespace),
+    _ => None,
+  }
+}
+
+/// The argument of a `seed 42` command, which restarts the random generator for the session.
+fn seed_command(input: &str) -> Option<&str> {
+  match input.split_whitespace().collect::<Vec<&str>>().as_slice() {
+    ["seed", seed] => Some(*seed),
+    _ => None,
+  }
+}
+
+/// The function of a `help sin` command, `Some(None)` for `help` alone which lists the functions.
+fn help_command(input: &str) -> Option<Option<&str>> {
+  match input.split_whitespace().collect::<Vec<&str>>().as_slice() {
+    ["help"] => Some(None),
+    ["help", name] => Some(Some(*name)),
+    _ => None,
+  }
+}
+
+/// The text of a `help` entry, the signatures of a function, the constants of a namespace or the
+/// names of all the functions
+///
+/// Arguments:
+///
+/// * `name`: The function or namespace of constants to document, all the functions are listed
+///   when it is `None`.
+/// * `context`: The math context, which has the signatures of the builtin and user functions.
+///
+/// Returns:
+///
+/// The text of the entry or an error if the function is unknown
+pub fn help_text(name: Option<&str>, context: &Context) -> std::result::Result<String, String> {
+  let name = match name {
+    Some(name) => name,
+    None => {
+      let mut names: Vec<String> =
+        context.all_signatures().into_iter().map(|signature| signature.name).collect();
+      names.dedup();
+      return Ok(format!(
+        "Functions: {}\nType `help name` for the description of a function",
+        names.join(", ")
+      ));
+    }
+  };
+  let signatures = context.signatures(name);
+  if signatures.is_empty() {
+    // `help phys` lists the constants of the namespace
+    let constants = context.namespace_constants(name);
+    if constants.is_empty() {
+      return Err(format!("Unknown function `{}`", name));
+    }
+    let entries: Vec<String> = constants.iter().map(|c| c.to_string()).collect();
+    return Ok(entries.join("\n"));
+  }
+  let entries: Vec<String> = signatures.iter().map(Signature::to_string).collect();
+  Ok(entries.join("\n\n"))
 }
 
-pub fn run_command(input: &str, conn: &Connection, context: &mut Context, window: &Window) {
+pub fn run_command(
+  input: &str,
+  conn: &Connection,
+  context: &mut Context,
+  window: &Window,
+) -> std::result::Result<(), String> {
   match input {
     "clear" => {
       match clear_operation_history(conn, context) {
@@ -42,14 +159,43 @@ pub fn run_command(input: &str, conn: &Connection, context: &mut Context, window
       };
     }
     "exit" => {}
+    _ if use_command(input).is_some() => {
+      let namespace = use_command(input).expect("The input is a use command");
+      if !context.use_namespace(namespace) {
+        return Err(format!("Unknown namespace `{}`", namespace));
+      }
+    }
+    _ if seed_command(input).is_some() => {
+      let seed = seed_command(input).expect("The input is a seed command");
+      match seed.parse::<i64>() {
+        Ok(seed) => context.seed(seed as u64),
+        Err(_) => return Err(format!("The seed must be an integer, got `{}`", seed)),
+      };
+    }
+    _ if help_command(input).is_some() => {
+      let name = help_command(input).expect("The input is a help command");
+      let text = help_text(name, context)?;
+      let op = store_note(input, &text, conn).map_err(|e| e.to_string())?;
+      window
+        .emit("add_to_history", &op)
+        .expect("Could not emit add_to_history");
+    }
+    _ if is_rate_command(input) => {
+      let count = run_rate_command(input, conn, context).map_err(|e| e.to_string())?;
+      window
+        .emit("rates_updated", count)
+        .expect("Could not emit event");
+    }
     _ => {}
   };
+  Ok(())
 }
 
 // pub fn run_command(input: &str) -> bool {
 
 // }
-/// It takes a string and a context, and returns a result
+/// It takes a string and a context, and returns a result without advancing the random generator,
+/// so that the live preview of `rand()` is the number the operation will store
 ///
 /// Arguments:
 ///
@@ -59,10 +205,34 @@ pub fn run_command(input: &str, conn: &Connection, context: &mut Context, window
 /// Returns:
 ///
 /// The operation result or an error
-pub fn calculate_result<S: AsRef<str>>(input: S, context: &Context) -> Result<f64, ParserError> {
-  match eval_str_with_context(input, context) {
-    Ok((_, result)) => Ok(result),
-    Err(e) => Err(e),
+pub fn calculate_result<S: AsRef<str>>(input: S, context: &Context) -> Result<Value, ParserError> {
+  if let Ok((formula, name)) = starts_with_formula(input.as_ref()) {
+    check_assignable(name, context)?;
+    context.check_formula(name, formula)?;
+    let (_, result) = context.preview(|context| eval_value_str_with_context(formula, context))?;
+    return Ok(result);
+  }
+  // Preview the value of a unit or constant definition
+  let (input, defined) = match starts_with_unit_definition(input.as_ref()) {
+    Ok((definition, name)) => (definition, Some(name.to_string())),
+    Err(_) => match starts_with_constant_definition(input.as_ref()) {
+      Ok((definition, name)) => (definition, Some(name.to_string())),
+      Err(_) => (input.as_ref(), None),
+    },
+  };
+  let (variable, result) =
+    context.preview(|context| eval_value_str_with_context(input, context))?;
+  match defined.or(variable) {
+    Some(name) => check_assignable(&name, context).map(|_| result),
+    None => Ok(result),
+  }
+}
+
+/// Rejects an assignment to a constant, a function or a reserved name such as `today`.
+fn check_assignable(name: &str, context: &Context) -> Result<(), ParserError> {
+  match context.reserved(name) {
+    Some(reserved) => Err(ParserError::Reserved(name.to_string(), reserved)),
+    None => Ok(()),
   }
 }
 
@@ -81,16 +251,18 @@ pub fn save_variable<S: AsRef<str>>(
   input: S,
   context: &mut Context,
   conn: &Connection,
-) -> Result<f64, ParserError> {
-  match eval_str_with_context(input, &context) {
+) -> Result<Value, ParserError> {
+  let workspace = current_workspace(conn);
+  match eval_value_str_with_context(input, &context) {
     Ok((variable, result)) => {
       match variable {
         Some(name) => {
-          context.var(&name, result);
+          check_assignable(&name, context)?;
+          context.value_var(&name, result.clone());
           conn
             .execute(
-              "INSERT OR REPLACE INTO variables (name, value) VALUES (?1, ?2)",
-              params![name, result],
+              "INSERT OR REPLACE INTO variables (workspace, name, value) VALUES (?1, ?2, ?3)",
+              params![workspace, name, result],
             )
             .expect("Could not insert variable");
         }
@@ -98,18 +270,161 @@ pub fn save_variable<S: AsRef<str>>(
       };
       conn
         .execute(
-          "INSERT OR REPLACE INTO variables (name, value) VALUES (?1, ?2)",
-          params!["ans", result],
+          "INSERT OR REPLACE INTO variables (workspace, name, value) VALUES (?1, ?2, ?3)",
+          params![workspace, "ans", result],
         )
         .expect("Could not save ans");
-      context.var("ans", result);
+      context.value_var("ans", result.clone());
       Ok(result)
     }
     Err(e) => Err(e),
   }
 }
 
-/// Delete all operations from the database
+/// It takes the name and definition of a unit, e.g. `furlong` and `201.168 m`, and saves the unit
+/// in the context and the database
+///
+/// Arguments:
+///
+/// * `name`: The name of the new unit.
+/// * `definition`: The value of one unit, the input string after `unit name =`.
+/// * `context`: The context to use for the evaluation.
+///
+/// Returns:
+///
+/// The value of one unit or an error
+pub fn save_unit(
+  name: &str,
+  definition: &str,
+  context: &mut Context,
+  conn: &Connection,
+) -> Result<Value, ParserError> {
+  check_assignable(name, context)?;
+  let (_, value) = eval_value_str_with_context(definition, &context)?;
+  let unit = Unit::define(name, &value).map_err(ParserError::Unit)?;
+  context.unit(name, unit);
+  conn
+    .execute(
+      "INSERT OR REPLACE INTO units (name, value) VALUES (?1, ?2)",
+      params![name, value],
+    )
+    .expect("Could not insert unit");
+  Ok(value)
+}
+
+/// It takes the name and definition of a constant, e.g. `g` and `9.81`, and saves the constant in
+/// the context and the database, it cannot be reassigned afterwards
+///
+/// Arguments:
+///
+/// * `name`: The name of the new constant.
+/// * `definition`: The value of the constant, the input string after `const name =`.
+/// * `context`: The context to use for the evaluation.
+///
+/// Returns:
+///
+/// The value of the constant or an error
+pub fn save_constant(
+  name: &str,
+  definition: &str,
+  context: &mut Context,
+  conn: &Connection,
+) -> Result<Value, ParserError> {
+  check_assignable(name, context)?;
+  let (_, value) = eval_value_str_with_context(definition, &context)?;
+  context.define_constant(name, value.clone());
+  conn
+    .execute(
+      "INSERT OR REPLACE INTO variables (workspace, name, value, constant) VALUES (?1, ?2, ?3, 1)",
+      params![current_workspace(conn), name, value],
+    )
+    .expect("Could not insert constant");
+  Ok(value)
+}
+
+/// It takes the name and formula of a variable, e.g. `b` and `a * 2`, and saves the variable in
+/// the context and the database with its formula, the variable is computed again when a variable
+/// read by the formula changes
+///
+/// Arguments:
+///
+/// * `name`: The name of the variable.
+/// * `formula`: The expression of the variable, the input string after `name :=`.
+/// * `context`: The context to use for the evaluation.
+///
+/// Returns:
+///
+/// The value of the variable or an error, e.g. when the formula reads the variable itself
+pub fn save_formula(
+  name: &str,
+  formula: &str,
+  context: &mut Context,
+  conn: &Connection,
+) -> Result<Value, ParserError> {
+  check_assignable(name, context)?;
+  let value = context.define_formula(name, formula)?;
+  conn
+    .execute(
+      "INSERT OR REPLACE INTO variables (workspace, name, value, formula) VALUES (?1, ?2, ?3, ?4)",
+      params![current_workspace(conn), name, value, formula.trim()],
+    )
+    .expect("Could not insert formula");
+  Ok(value)
+}
+
+/// A variable computed again from its formula, with its new value or the error of the formula.
+#[derive(Serialize, Deserialize)]
+pub struct ChangedVariable {
+  pub name: String,
+  pub value: Option<FormattedValue>,
+  pub error: Option<String>,
+}
+
+/// Computes again the formulas reading some variables after they changed and saves their values,
+/// a formula that fails leaves its variable without a value
+///
+/// Arguments:
+///
+/// * `names`: The variables that changed.
+/// * `context`: The context holding the formulas.
+/// * `conn`: The connection to the database.
+/// * `options`: How the new values are formatted.
+///
+/// Returns:
+///
+/// The variables computed again, in the order they were computed.
+pub fn update_formulas(
+  names: &[&str],
+  context: &mut Context,
+  conn: &Connection,
+  options: &FormatOptions,
+) -> Vec<ChangedVariable> {
+  let workspace = current_workspace(conn);
+  context
+    .recompute(names)
+    .into_iter()
+    .map(|(name, result)| {
+      conn
+        .execute(
+          "UPDATE variables SET value = ?1 WHERE workspace = ?2 AND name = ?3",
+          params![result.as_ref().ok(), workspace, name],
+        )
+        .expect("Could not update variable");
+      let representation = context.formula(&name).and_then(representation_of);
+      match result {
+        Ok(value) => ChangedVariable {
+          name,
+          value: Some(FormattedValue::new(value, representation, options)),
+          error: None,
+        },
+        Err(e) => ChangedVariable { name, value: None, error: Some(e.to_string()) },
+      }
+    })
+    .collect()
+}
+
+/// Delete the operations and variables of the current workspace from the database, the units and
+/// constants defined by the user are kept
 ///
 /// Arguments:
 ///
@@ -119,8 +434,12 @@ pub fn save_variable<S: AsRef<str>>(
 ///
 /// Nothing.
 pub fn clear_operation_history(conn: &Connection, context: &mut Context) -> Result<()> {
-  conn.execute("DELETE FROM operations;", params![])?;
-  conn.execute("DELETE FROM variables;", params![])?;
+  let workspace = current_workspace(conn);
+  conn.execute("DELETE FROM operations WHERE workspace = ?1;", params![workspace])?;
+  conn.execute(
+    "DELETE FROM variables WHERE workspace = ?1 AND constant = 0;",
+    params![workspace],
+  )?;
   context.clear();
   Ok(())
 }
@@ -128,7 +447,43 @@ pub fn clear_operation_history(conn: &Connection, context: &mut Context) -> Resu
 #[derive(Serialize, Deserialize)]
 pub struct Operation {
   operation: String,
-  result: f64,
+  /// The result, none for a note.
+  result: Option<Value>,
+  /// The representation requested with a `to hex`-like suffix.
+  #[serde(default)]
+  representation: Option<Representation>,
+  /// The result as displayed with the format preferences.
+  #[serde(default)]
+  formatted: String,
+  /// The text written by a command such as `help`, shown instead of the result.
+  #[serde(default)]
+  note: Option<String>,
+}
+
+/// A result next to its display with the format preferences, so that the live preview and the
+/// history agree.
+#[derive(Serialize, Deserialize)]
+pub struct FormattedValue {
+  pub result: Value,
+  pub formatted: String,
+}
+
+impl FormattedValue {
+  pub fn new(
+    result: Value,
+    representation: Option<Representation>,
+    options: &FormatOptions,
+  ) -> FormattedValue {
+    FormattedValue {
+      formatted: format_represented(&result, representation, options),
+      result,
+    }
+  }
+}
+
+/// The representation requested with a `to hex`-like suffix at the end of the operation.
+pub fn representation_of(operation: &str) -> Option<Representation> {
+  ends_with_representation(operation).map(|(_, representation)| representation)
 }
 
 /// Returns a list of past operations
@@ -136,16 +491,30 @@ pub struct Operation {
 /// Arguments:
 ///
 /// * `conn`: &Connection - This is the connection to the database.
+/// * `options`: How the results are formatted.
 ///
 /// Returns:
 ///
 /// The list of operations.
-pub fn get_operation_history(conn: &Connection) -> Result<Vec<Operation>> {
-  let mut stmt = conn.prepare("SELECT operation, result FROM operations ORDER BY id ASC;")?;
-  let operation_iter = stmt.query_map(params![], |row| {
+pub fn get_operation_history(conn: &Connection, options: &FormatOptions) -> Result<Vec<Operation>> {
+  let mut stmt = conn.prepare(
+    "SELECT operation, result, representation, note FROM operations WHERE workspace = ?1 ORDER BY id ASC;",
+  )?;
+  let operation_iter = stmt.query_map(params![current_workspace(conn)], |row| {
+    let result: Option<Value> = row.get(1)?;
+    let representation: Option<String> = row.get(2)?;
+    let representation = representation.and_then(|r| r.parse().ok());
+    let note: Option<String> = row.get(3)?;
     Ok(Operation {
       operation: row.get(0)?,
-      result: row.get(1)?,
+      formatted: match (&note, &result) {
+        (Some(note), _) => note.clone(),
+        (None, Some(result)) => format_represented(result, representation, options),
+        (None, None) => String::new(),
+      },
+      result,
+      representation,
+      note,
     })
   })?;
   let mut operations = Vec::new();
@@ -162,6 +531,9 @@ pub fn get_operation_history(conn: &Connection) -> Result<Vec<Operation>> {
 /// * `operation`: The operation to store.
 /// * `conn`: this is the connection to the database.
 /// * `context`: The math context
+/// * `options`: How the result is formatted.
+/// * `window`: The window notified with `add_to_history`, and with `variables_changed` when
+///   formulas read the variables assigned by the operation.
 ///
 /// Returns:
 ///
@@ -170,29 +542,90 @@ pub fn store_operation(
   operation: &str,
   conn: &mut Connection,
   context: &mut Context,
+  options: &FormatOptions,
   window: Window,
 ) -> Result<Operation, ParserError> {
-  match save_variable(operation, context, conn) {
+  // The variables assigned, the formulas reading them are computed again
+  let (saved, assigned) = match starts_with_unit_definition(operation) {
+    Ok((definition, name)) => (save_unit(name, definition, context, conn), vec![name.to_string()]),
+    Err(_) => match starts_with_constant_definition(operation) {
+      Ok((definition, name)) => {
+        (save_constant(name, definition, context, conn), vec![name.to_string()])
+      }
+      Err(_) => match starts_with_formula(operation) {
+        Ok((formula, name)) => (save_formula(name, formula, context, conn), vec![name.to_string()]),
+        Err(_) => match starts_with_assignment(operation) {
+          Ok((_, Token::Var(name))) => {
+            (save_variable(operation, context, conn), vec![name, "ans".to_string()])
+          }
+          _ => (save_variable(operation, context, conn), vec!["ans".to_string()]),
+        },
+      },
+    },
+  };
+  match saved {
     Ok(result) => {
+      let representation = representation_of(operation);
       conn
         .execute(
-          "INSERT INTO operations (operation, result) VALUES (?1, ?2);",
-          params![operation, result],
+          "INSERT INTO operations (workspace, operation, result, representation) VALUES (?1, ?2, ?3, ?4);",
+          params![
+            current_workspace(conn),
+            operation,
+            result,
+            representation.map(|r| r.to_string())
+          ],
         )
         .expect("Could not store in database");
       let op = Operation {
         operation: operation.to_string(),
-        result: result,
+        formatted: format_represented(&result, representation, options),
+        result: Some(result),
+        representation,
+        note: None,
       };
       window
         .emit("add_to_history", &op)
         .expect("Could not emit add_to_history");
+      let assigned: Vec<&str> = assigned.iter().map(String::as_str).collect();
+      let changed = update_formulas(&assigned, context, conn, options);
+      if !changed.is_empty() {
+        window
+          .emit("variables_changed", &changed)
+          .expect("Could not emit variables_changed");
+      }
       Ok(op)
     }
     Err(e) => Err(e),
   }
 }
 
+/// Stores the text written by a command, e.g. the description of a function for `help sin`, in
+/// the history. It has no result.
+///
+/// Arguments:
+///
+/// * `operation`: The command.
+/// * `note`: The text shown instead of a result.
+/// * `conn`: this is the connection to the database.
+///
+/// Returns:
+///
+/// The entry of the history
+pub fn store_note(operation: &str, note: &str, conn: &Connection) -> Result<Operation> {
+  conn.execute(
+    "INSERT INTO operations (workspace, operation, note) VALUES (?1, ?2, ?3);",
+    params![current_workspace(conn), operation, note],
+  )?;
+  Ok(Operation {
+    operation: operation.to_string(),
+    result: None,
+    representation: None,
+    formatted: note.to_string(),
+    note: Some(note.to_string()),
+  })
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -200,16 +633,31 @@ mod tests {
 
   fn create_db() -> Connection {
     let conn = Connection::open_in_memory().expect("Could not create in-memory database");
+    conn.execute_batch(
+      "CREATE TABLE IF NOT EXISTS workspaces (id integer NOT NULL, name text NOT NULL UNIQUE, active integer NOT NULL DEFAULT 0, PRIMARY KEY (id));
+      INSERT INTO workspaces (id, name, active) VALUES (1, 'Default', 1), (2, 'Taxes 2026', 0);",
+    ).expect("Could not create table workspaces");
+
     conn.execute(
-      "CREATE TABLE IF NOT EXISTS operations (id integer NOT NULL, operation text NOT NULL, result num NOT NULL, PRIMARY KEY (id));",
+      "CREATE TABLE IF NOT EXISTS operations (id integer NOT NULL, workspace integer NOT NULL DEFAULT 1, operation text NOT NULL, result num DEFAULT NULL, representation text DEFAULT NULL, note text DEFAULT NULL, PRIMARY KEY (id));",
       params![]
     ).expect("Could not create table operations");
 
     conn.execute(
-      "CREATE TABLE IF NOT EXISTS variables (name text NOT NULL,value num DEFAULT NULL, PRIMARY KEY (name));",
+      "CREATE TABLE IF NOT EXISTS variables (workspace integer NOT NULL DEFAULT 1, name text NOT NULL,value num DEFAULT NULL, constant integer NOT NULL DEFAULT 0, formula text DEFAULT NULL, PRIMARY KEY (workspace, name));",
       params![],
     ).expect("Could not create table variables");
 
+    conn.execute(
+      "CREATE TABLE IF NOT EXISTS units (name text NOT NULL, value num NOT NULL, PRIMARY KEY (name));",
+      params![],
+    ).expect("Could not create table units");
+
+    conn.execute(
+      "CREATE TABLE IF NOT EXISTS rates (code text NOT NULL, rate num NOT NULL, updated integer NOT NULL, PRIMARY KEY (code));",
+      params![],
+    ).expect("Could not create table rates");
+
     // Iniitialize the db with some data
     conn
       .execute(
@@ -250,25 +698,255 @@ mod tests {
     let conn = create_db();
 
     let mut context = create_context_from_db(&conn);
+    conn
+      .execute_batch(
+        "INSERT INTO operations (workspace, operation, result) VALUES (2, '3', 3);
+        INSERT INTO variables (workspace, name, value) VALUES (2, 'a', 3);",
+      )
+      .expect("Could not insert into the other workspace");
 
     // Clear the history
     clear_operation_history(&conn, &mut context).expect("Could not clear history");
 
-    // Assert that the context and db is empty
+    // Assert that the context and db is empty, only the other workspace is left
     let operation_count: usize = conn
       .query_row("SELECT COUNT(*) FROM operations;", [], |r| r.get(0))
       .expect("Could not get operation count");
-    assert_eq!(operation_count, 0);
+    assert_eq!(operation_count, 1);
     let variable_count: usize = conn
-      .query_row("SELECT COUNT(*) FROM variables;", [], |r| r.get(0))
+      .query_row("SELECT COUNT(*) FROM variables WHERE workspace = 1;", [], |r| r.get(0))
       .expect("Could not get variable count");
     assert_eq!(variable_count, 0);
+    assert_eq!(get_operation_history(&conn, &FormatOptions::default()).map(|h| h.len()), Ok(0));
 
     assert_eq!(context.get_var("a"), None);
     assert_eq!(context.get_var("var1"), None);
     assert_eq!(context.get_var("c"), None);
   }
 
+  #[test]
+  fn test_constants() {
+    use super::super::super::parser::{ContextProvider, Reserved};
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+
+    let reserved = |name: &str, kind| Err(ParserError::Reserved(name.into(), kind));
+    assert_eq!(save_variable("pi = 3", &mut context, &conn), reserved("pi", Reserved::Constant));
+    assert_eq!(save_variable("sin = 2", &mut context, &conn), reserved("sin", Reserved::Function));
+    assert_eq!(
+      save_variable("today = 1", &mut context, &conn),
+      reserved("today", Reserved::Keyword)
+    );
+    assert_eq!(calculate_result("e = 2", &context), reserved("e", Reserved::Constant));
+    assert_eq!(
+      reserved("pi", Reserved::Constant).unwrap_err().to_string(),
+      "Evaluation error: `pi` is a constant and cannot be assigned."
+    );
+
+    assert_eq!(calculate_result("const g = 9.81", &context), Ok(Value::Real(9.81)));
+    assert_eq!(save_constant("g", " 9.81", &mut context, &conn), Ok(Value::Real(9.81)));
+    assert_eq!(save_variable("g = 10", &mut context, &conn), reserved("g", Reserved::Constant));
+    assert_eq!(save_constant("g", " 10", &mut context, &conn), reserved("g", Reserved::Constant));
+    assert_eq!(calculate_result("const a = 2", &context), Ok(Value::Real(2.)));
+    assert_eq!(save_constant("a", " 2", &mut context, &conn), Ok(Value::Real(2.)));
+    assert_eq!(context.get_var("a"), Some(2.));
+
+    // Constants survive `clear` and a restart, the variables do not
+    clear_operation_history(&conn, &mut context).expect("Could not clear history");
+    let names: Vec<String> = conn
+      .prepare("SELECT name FROM variables ORDER BY name;")
+      .and_then(|mut stmt| stmt.query_map([], |r| r.get(0))?.collect())
+      .expect("Could not get variables");
+    assert_eq!(names, ["a", "g"]);
+    for context in [context, create_context_from_db(&conn)] {
+      assert_eq!(context.get_var("pi"), Some(std::f64::consts::PI));
+      assert_eq!(context.get_var("g"), Some(9.81));
+      assert_eq!(context.get_var("a"), Some(2.));
+      assert_eq!(context.get_var("var1"), None);
+    }
+  }
+
+  #[test]
+  fn test_formulas() {
+    use super::super::super::parser::ContextProvider;
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+    let options = FormatOptions::default();
+
+    assert_eq!(calculate_result("b := a * 2", &context), Ok(Value::Real(2.)));
+    assert_eq!(save_formula("b", " a * 2", &mut context, &conn), Ok(Value::Real(2.)));
+    assert_eq!(
+      save_formula("d", " b + round(c) to hex", &mut context, &conn),
+      Ok(Value::Real(27.))
+    );
+    assert_eq!(
+      calculate_result("a := d", &context).map_err(|e| e.to_string()),
+      Err("Evaluation error: circular formula `a -> d -> b -> a`.".into())
+    );
+
+    // Assigning a variable computes again the formulas reading it, in order
+    save_variable("a = 5", &mut context, &conn).unwrap();
+    let changed = update_formulas(&["a", "ans"], &mut context, &conn, &options);
+    let changed: Vec<(&str, Option<&str>)> = changed
+      .iter()
+      .map(|c| (c.name.as_str(), c.value.as_ref().map(|v| v.formatted.as_str())))
+      .collect();
+    assert_eq!(changed, [("b", Some("10")), ("d", Some("0x23"))]);
+    let stored: (Value, String) = conn
+      .query_row("SELECT value, formula FROM variables WHERE name = 'd';", [], |r| {
+        Ok((r.get(0)?, r.get(1)?))
+      })
+      .expect("Could not get formula");
+    assert_eq!(stored, (Value::Real(35.), "b + round(c) to hex".into()));
+
+    // A failing formula has no value until its inputs are back, also after a restart
+    save_variable("c = unknown", &mut context, &conn).unwrap_err();
+    conn.execute("DELETE FROM variables WHERE name = 'c';", []).expect("Could not delete c");
+    let mut context = create_context_from_db(&conn);
+    assert_eq!(context.get_var("b"), Some(10.));
+    assert_eq!(context.get_var("d"), None);
+    save_variable("c = 1", &mut context, &conn).unwrap();
+    let changed = update_formulas(&["c", "ans"], &mut context, &conn, &options);
+    assert_eq!(changed.len(), 1);
+    assert_eq!(create_context_from_db(&conn).get_var("d"), Some(11.));
+
+    // A plain assignment replaces the formula
+    save_variable("b = 1", &mut context, &conn).unwrap();
+    assert_eq!(context.formula("b"), None);
+    assert_eq!(create_context_from_db(&conn).formula("d"), Some("b + round(c) to hex"));
+    assert_eq!(create_context_from_db(&conn).formula("b"), None);
+  }
+
+  #[test]
+  fn test_save_complex_variable() {
+    use super::super::super::parser::ContextProvider;
+    use num_complex::Complex64;
+
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+    context.set_complex_mode(true);
+
+    let z = Value::Complex(Complex64::new(3., 4.));
+    assert_eq!(save_variable("z = 3+4i", &mut context, &conn), Ok(z.clone()));
+
+    let stored: Value = conn
+      .query_row("SELECT value FROM variables WHERE name = 'z';", [], |r| r.get(0))
+      .expect("Could not get variable");
+    assert_eq!(stored, z);
+
+    let context = create_context_from_db(&conn);
+    assert_eq!(context.get_value("z"), Some(z.clone()));
+    assert_eq!(context.get_value("ans"), Some(z));
+  }
+
+  #[test]
+  fn test_save_units() {
+    use super::super::super::parser::{ContextProvider, Reserved};
+
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+
+    let distance = save_variable("d = 5 km + 300 m", &mut context, &conn).unwrap();
+    assert_eq!(distance.to_string(), "5.3 km");
+    let stored: Value = conn
+      .query_row("SELECT value FROM variables WHERE name = 'd';", [], |r| r.get(0))
+      .expect("Could not get variable");
+    assert_eq!(stored, distance);
+
+    let furlong = save_unit("furlong", "201.168 m", &mut context, &conn).unwrap();
+    assert_eq!(furlong.to_string(), "201.168 m");
+
+    let context = create_context_from_db(&conn);
+    assert_eq!(context.get_value("d"), Some(distance));
+    assert_eq!(
+      calculate_result("d to furlong", &context).map(|v| v.to_string()),
+      Ok("26.34613855086296 furlong".into())
+    );
+    assert_eq!(
+      calculate_result("unit fortnight = 14 day", &context).map(|v| v.to_string()),
+      Ok("14 day".into())
+    );
+
+    // A unit cannot hide a constant or a function
+    let reserved = |name: &str, kind| Err(ParserError::Reserved(name.into(), kind));
+    let mut context = create_context_from_db(&conn);
+    assert_eq!(save_unit("pi", "3 m", &mut context, &conn), reserved("pi", Reserved::Constant));
+    assert_eq!(calculate_result("unit sin = 2 m", &context), reserved("sin", Reserved::Function));
+
+    // Redefining a unit computes again the formulas using it
+    save_formula("laps", " d to furlong", &mut context, &conn).unwrap();
+    save_unit("furlong", "265 m", &mut context, &conn).unwrap();
+    let changed = update_formulas(&["furlong"], &mut context, &conn, &FormatOptions::default());
+    assert_eq!(changed.len(), 1);
+    assert_eq!(context.get_value("laps").map(|v| v.to_string()), Some("20 furlong".into()));
+  }
+
+  #[test]
+  fn test_history_representations() {
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+    let operation = "x = 255.5 to hex";
+    let result = save_variable(operation, &mut context, &conn).unwrap();
+    conn
+      .execute(
+        "INSERT INTO operations (operation, result, representation) VALUES (?1, ?2, ?3);",
+        params![operation, result, representation_of(operation).map(|r| r.to_string())],
+      )
+      .expect("Could not insert into operations");
+
+    let history = get_operation_history(&conn, &FormatOptions::default()).unwrap();
+    let formatted: Vec<&str> = history.iter().map(|op| op.formatted.as_str()).collect();
+    assert_eq!(formatted, ["1", "2", "0xff.8"]);
+    assert_eq!(history[2].representation, Some(Representation::Radix(16)));
+    // `ans` keeps the number
+    assert_eq!(calculate_result("ans + 0.5", &context), Ok(Value::Real(256.)));
+  }
+
+  #[test]
+  fn test_help() {
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+    assert!(check_if_command("help"));
+    assert!(check_if_command("help round"));
+    assert!(!check_if_command("help + 1"));
+
+    let text = help_text(Some("round"), &context).expect("round is documented");
+    assert!(text.starts_with("round(x, digits?): Rounds half away from zero"));
+    assert!(text.contains("\nExample: round(3.14159, 2) = 3.14"));
+    assert!(help_text(None, &context).expect("The functions are listed").contains("sqrt, stdev, stdevp, sum"));
+    assert_eq!(help_text(Some("nope"), &context), Err("Unknown function `nope`".into()));
+    let constants = help_text(Some("math"), &context).expect("The constants are listed");
+    assert!(constants.contains("math.phi = 1.618033988749895 (golden ratio)\n"));
+    context.func("double", |x| 2. * x);
+    assert_eq!(help_text(Some("double"), &context), Ok("double(x)".into()));
+
+    store_note("help round", &text, &conn).expect("Could not store the note");
+    let history = get_operation_history(&conn, &FormatOptions::default()).unwrap();
+    let entry = history.last().expect("The note is in the history");
+    assert_eq!(entry.operation, "help round");
+    assert_eq!(entry.note.as_deref(), Some(text.as_str()));
+    assert_eq!(entry.formatted, text);
+    assert_eq!(entry.result, None);
+    assert_eq!(history[0].note, None);
+  }
+
+  #[test]
+  fn test_random_preview() {
+    use super::super::super::parser::ContextProvider;
+
+    let conn = create_db();
+    let mut context = create_context_from_db(&conn);
+    assert!(check_if_command("seed 42"));
+    assert!(!check_if_command("seed"));
+
+    context.seed(42);
+    let preview = calculate_result("x = randint(1, 1000)", &context).unwrap();
+    assert_eq!(calculate_result("x = randint(1, 1000)", &context), Ok(preview.clone()));
+    let stored = save_variable("x = randint(1, 1000)", &mut context, &conn).unwrap();
+    assert_eq!(stored, preview);
+    assert_eq!(context.get_value("x"), Some(stored));
+  }
+
   #[test]
   fn test_create_context_from_db() {
     use super::super::super::parser::ContextProvider;
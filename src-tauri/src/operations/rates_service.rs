@@ -0,0 +1,209 @@
+//! Exchange rates of the currency units, kept by the user in the `rates` table since conversions
+//! must work offline. A rate is the value of one unit of the currency in US dollars.
+use super::super::parser::{Context, Unit};
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection};
+use std::fmt;
+use std::fs;
+
+#[derive(Debug)]
+pub enum RateError {
+  /// A rate that is not a positive number, or an invalid currency code.
+  InvalidRate(String),
+  /// A line of an imported CSV file that is not `code,rate` or `code,rate,YYYY-MM-DD`.
+  InvalidLine(usize, String),
+  Io(std::io::Error),
+  Database(rusqlite::Error),
+}
+
+impl fmt::Display for RateError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      RateError::InvalidRate(ref rate) => write!(f, "Invalid rate `{}`", rate),
+      RateError::InvalidLine(n, ref line) => write!(f, "Invalid rate on line {}: `{}`", n, line),
+      RateError::Io(ref e) => write!(f, "Could not read the rates: {}", e),
+      RateError::Database(ref e) => write!(f, "Could not save the rates: {}", e),
+    }
+  }
+}
+
+impl From<rusqlite::Error> for RateError {
+  fn from(err: rusqlite::Error) -> RateError {
+    RateError::Database(err)
+  }
+}
+
+/// Whether the input is `rate EUR 1.08` or `import rates path/to/rates.csv`.
+pub fn is_rate_command(input: &str) -> bool {
+  let words: Vec<&str> = input.split_whitespace().collect();
+  matches!(words.as_slice(), ["rate", _, _] | ["import", "rates", _, ..])
+}
+
+/// Runs a rate command, returns the number of rates that were set.
+pub fn run_rate_command(
+  input: &str,
+  conn: &Connection,
+  context: &mut Context,
+) -> Result<usize, RateError> {
+  let now = Utc::now().timestamp();
+  match input.split_whitespace().collect::<Vec<&str>>().as_slice() {
+    ["rate", code, rate] => {
+      let rate = parse_rate(code, rate).ok_or_else(|| RateError::InvalidRate(input.into()))?;
+      set_rate(conn, context, code, rate, now)?;
+      Ok(1)
+    }
+    _ => {
+      let path = input.trim_start()["import".len()..].trim_start()["rates".len()..].trim();
+      let csv = fs::read_to_string(path).map_err(RateError::Io)?;
+      import_rates(conn, context, &csv, now)
+    }
+  }
+}
+
+/// A currency code is made of letters, the rate must be a positive number.
+fn parse_rate(code: &str, rate: &str) -> Option<f64> {
+  let rate = rate.parse::<f64>().ok()?;
+  if code.chars().all(|c| c.is_ascii_alphabetic()) && rate.is_finite() && rate > 0. {
+    Some(rate)
+  } else {
+    None
+  }
+}
+
+/// Saves the rate of a currency and makes it available in the context.
+pub fn set_rate(
+  conn: &Connection,
+  context: &mut Context,
+  code: &str,
+  rate: f64,
+  updated: i64,
+) -> Result<(), RateError> {
+  conn.execute(
+    "INSERT OR REPLACE INTO rates (code, rate, updated) VALUES (?1, ?2, ?3)",
+    params![code, rate, updated],
+  )?;
+  context.unit(code, Unit::currency(code, rate, updated));
+  Ok(())
+}
+
+/// Imports the rates of a CSV file with `code,rate` lines and an optional date of the rate in a
+/// third column, rates without a date are dated `now`. A header line is skipped.
+pub fn import_rates(
+  conn: &Connection,
+  context: &mut Context,
+  csv: &str,
+  now: i64,
+) -> Result<usize, RateError> {
+  let mut rates = vec![];
+  for (i, line) in csv.lines().enumerate() {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let invalid = || RateError::InvalidLine(i + 1, line.to_string());
+    let (code, rate, date) = match fields.as_slice() {
+      [""] => continue,
+      [code, rate] => (*code, *rate, None),
+      [code, rate, date] => (*code, *rate, Some(*date)),
+      _ => return Err(invalid()),
+    };
+    let rate = match parse_rate(code, rate) {
+      Some(rate) => rate,
+      None if i == 0 => continue,
+      None => return Err(invalid()),
+    };
+    let updated = match date {
+      Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| invalid())?
+        .and_hms_opt(0, 0, 0)
+        .expect("Midnight is a valid time")
+        .and_utc()
+        .timestamp(),
+      None => now,
+    };
+    rates.push((code, rate, updated));
+  }
+  // Only save the rates once the whole file is valid
+  for &(code, rate, updated) in rates.iter() {
+    set_rate(conn, context, code, rate, updated)?;
+  }
+  Ok(rates.len())
+}
+
+/// Adds the saved rates to the context.
+pub fn load_rates(conn: &Connection, context: &mut Context) {
+  let mut stmt = conn
+    .prepare("SELECT code, rate, updated FROM rates")
+    .expect("Could not prepare statement");
+  let mut rows = stmt
+    .query_map(params![], |row| {
+      Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+    })
+    .expect("Could not query rates");
+  while let Some(Ok((code, rate, updated))) = rows.next() {
+    context.unit(&code, Unit::currency(&code, rate, updated));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::super::parser::{builtin, eval_value_str_with_context};
+  use super::*;
+
+  // 2026-10-18
+  const NOW: i64 = 1_792_281_600;
+
+  fn create_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("Could not create in-memory database");
+    conn
+      .execute(
+        "CREATE TABLE IF NOT EXISTS rates (code text NOT NULL, rate num NOT NULL, updated integer NOT NULL, PRIMARY KEY (code));",
+        params![],
+      )
+      .expect("Could not create table rates");
+    conn
+  }
+
+  fn eval(input: &str, context: &Context) -> String {
+    eval_value_str_with_context(input, context).unwrap().1.to_string()
+  }
+
+  #[test]
+  fn test_rate_command() {
+    let conn = create_db();
+    let mut context = builtin();
+
+    assert!(is_rate_command("rate EUR 1.08"));
+    assert!(is_rate_command("import rates /tmp/rates.csv"));
+    assert!(!is_rate_command("rate(360, -1199.1, 200000)"));
+    assert!(matches!(
+      run_rate_command("rate EUR -1", &conn, &mut context),
+      Err(RateError::InvalidRate(_))
+    ));
+
+    set_rate(&conn, &mut context, "EUR", 1.08, NOW).unwrap();
+    set_rate(&conn, &mut context, "GBP", 1.25, NOW).unwrap();
+    assert_eq!(eval("108 USD in EUR", &context), "100 EUR (rates of 2026-10-18)");
+    assert_eq!(eval("$45 * 3 in GBP", &context), "108 GBP (rates of 2026-10-18)");
+
+    let mut context = builtin();
+    load_rates(&conn, &mut context);
+    assert_eq!(eval("€100 to $", &context), "108 USD (rates of 2026-10-18)");
+  }
+
+  #[test]
+  fn test_import_rates() {
+    let conn = create_db();
+    let mut context = builtin();
+
+    let csv = "currency,rate,date\nEUR,1.08\nJPY, 0.0067, 2026-09-01\n\n";
+    assert_eq!(import_rates(&conn, &mut context, csv, NOW).unwrap(), 2);
+    assert_eq!(eval("1 EUR + ¥1000", &context), "7.203703703703703 EUR (rates of 2026-09-01)");
+
+    assert!(matches!(
+      import_rates(&conn, &mut context, "CHF,1.1\nCAD,abc", NOW),
+      Err(RateError::InvalidLine(2, _))
+    ));
+    let rates: usize = conn
+      .query_row("SELECT COUNT(*) FROM rates;", [], |r| r.get(0))
+      .expect("Could not count rates");
+    assert_eq!(rates, 2);
+  }
+}
@@ -0,0 +1,183 @@
+//! Named constants grouped in namespaces, `phys.c` or `math.tau`, with the CODATA 2018 values of
+//! the physical constants. `use phys` brings the names of a namespace into scope unqualified.
+use super::units::{self, Unit, UnitError};
+use super::value::Value;
+use std::f64::consts;
+use std::fmt;
+
+/// A constant with its unit and standard uncertainty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant {
+  pub namespace: &'static str,
+  pub name: &'static str,
+  pub value: f64,
+  /// The symbols and powers of the unit, empty for dimensionless constants.
+  pub unit: &'static [(&'static str, i32)],
+  /// The standard uncertainty in the same unit, 0 for exact values.
+  pub uncertainty: f64,
+  pub description: &'static str,
+}
+
+impl Constant {
+  pub fn qualified_name(&self) -> String {
+    format!("{}.{}", self.namespace, self.name)
+  }
+
+  pub fn unit(&self) -> Result<Option<Unit>, UnitError> {
+    if self.unit.is_empty() {
+      return Ok(None);
+    }
+    let factors: Vec<(String, i32)> = self.unit.iter().map(|&(s, p)| (s.to_string(), p)).collect();
+    units::compound(&factors, units::lookup).map(Some)
+  }
+
+  /// The value with its unit, a real number for dimensionless constants.
+  pub fn to_value(&self) -> Value {
+    match self.unit() {
+      Ok(Some(unit)) => units::apply(&Value::Real(self.value), unit).expect("Units apply to reals"),
+      _ => Value::Real(self.value),
+    }
+  }
+}
+
+/// `phys.c = 299792458 m/s (speed of light in vacuum)`, with `± uncertainty` when it is not exact.
+impl fmt::Display for Constant {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} = {}", self.qualified_name(), self.to_value())?;
+    if self.uncertainty != 0. {
+      write!(f, " ± {}", self.uncertainty)?;
+    }
+    write!(f, " ({})", self.description)
+  }
+}
+
+const fn constant(
+  namespace: &'static str,
+  name: &'static str,
+  value: f64,
+  unit: &'static [(&'static str, i32)],
+  uncertainty: f64,
+  description: &'static str,
+) -> Constant {
+  Constant {
+    namespace,
+    name,
+    value,
+    unit,
+    uncertainty,
+    description,
+  }
+}
+
+const CONSTANTS: [Constant; 29] = [
+  constant("math", "pi", consts::PI, &[], 0., "ratio of a circle's circumference to its diameter"),
+  constant("math", "tau", consts::TAU, &[], 0., "ratio of a circle's circumference to its radius"),
+  constant("math", "e", consts::E, &[], 0., "base of the natural logarithm"),
+  constant("math", "phi", 1.618033988749895, &[], 0., "golden ratio"),
+  constant("math", "gamma", 0.5772156649015329, &[], 0., "Euler–Mascheroni constant"),
+  constant("math", "sqrt2", consts::SQRT_2, &[], 0., "square root of 2"),
+  constant("math", "ln2", consts::LN_2, &[], 0., "natural logarithm of 2"),
+  constant("math", "ln10", consts::LN_10, &[], 0., "natural logarithm of 10"),
+  constant("phys", "c", 299792458., &[("m", 1), ("s", -1)], 0., "speed of light in vacuum"),
+  constant("phys", "h", 6.62607015e-34, &[("J", 1), ("s", 1)], 0., "Planck constant"),
+  constant("phys", "hbar", 1.054571817e-34, &[("J", 1), ("s", 1)], 0., "reduced Planck constant"),
+  constant("phys", "k_B", 1.380649e-23, &[("J", 1), ("K", -1)], 0., "Boltzmann constant"),
+  constant("phys", "N_A", 6.02214076e23, &[("mol", -1)], 0., "Avogadro constant"),
+  constant("phys", "R", 8.314462618, &[("J", 1), ("mol", -1), ("K", -1)], 0., "molar gas constant"),
+  constant(
+    "phys",
+    "G",
+    6.6743e-11,
+    &[("m", 3), ("kg", -1), ("s", -2)],
+    1.5e-15,
+    "Newtonian constant of gravitation",
+  ),
+  constant("phys", "g_n", 9.80665, &[("m", 1), ("s", -2)], 0., "standard acceleration of gravity"),
+  constant("phys", "e", 1.602176634e-19, &[("C", 1)], 0., "elementary charge"),
+  constant("phys", "m_e", 9.1093837015e-31, &[("kg", 1)], 2.8e-40, "electron mass"),
+  constant("phys", "m_p", 1.67262192369e-27, &[("kg", 1)], 5.1e-37, "proton mass"),
+  constant("phys", "m_n", 1.67492749804e-27, &[("kg", 1)], 9.5e-37, "neutron mass"),
+  constant("phys", "m_u", 1.6605390666e-27, &[("kg", 1)], 5e-37, "atomic mass constant"),
+  constant(
+    "phys",
+    "eps_0",
+    8.8541878128e-12,
+    &[("C", 1), ("V", -1), ("m", -1)],
+    1.3e-21,
+    "vacuum electric permittivity",
+  ),
+  constant(
+    "phys",
+    "mu_0",
+    1.25663706212e-6,
+    &[("N", 1), ("A", -2)],
+    1.9e-16,
+    "vacuum magnetic permeability",
+  ),
+  constant("phys", "alpha", 7.2973525693e-3, &[], 1.1e-12, "fine-structure constant"),
+  constant("phys", "R_inf", 10973731.56816, &[("m", -1)], 2.1e-5, "Rydberg constant"),
+  constant("phys", "a_0", 5.29177210903e-11, &[("m", 1)], 8e-21, "Bohr radius"),
+  constant(
+    "phys",
+    "sigma",
+    5.670374419e-8,
+    &[("W", 1), ("m", -2), ("K", -4)],
+    0.,
+    "Stefan–Boltzmann constant",
+  ),
+  constant("phys", "F", 96485.33212, &[("C", 1), ("mol", -1)], 0., "Faraday constant"),
+  constant("phys", "atm", 101325., &[("Pa", 1)], 0., "standard atmosphere"),
+];
+
+/// Whether constants are defined in the namespace.
+pub fn is_namespace(namespace: &str) -> bool {
+  CONSTANTS.iter().any(|c| c.namespace == namespace)
+}
+
+/// Looks up a constant by its qualified name, e.g. `phys.c`.
+pub fn lookup(name: &str) -> Option<&'static Constant> {
+  let (namespace, name) = name.split_once('.')?;
+  find(namespace, name)
+}
+
+/// Looks up a constant by its name in a namespace.
+pub fn find(namespace: &str, name: &str) -> Option<&'static Constant> {
+  CONSTANTS.iter().find(|c| c.namespace == namespace && c.name == name)
+}
+
+/// The constants of a namespace.
+pub fn in_namespace(namespace: &str) -> impl Iterator<Item = &'static Constant> + '_ {
+  CONSTANTS.iter().filter(move |c| c.namespace == namespace)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_lookup() {
+    assert_eq!(lookup("phys.c").map(|c| c.value), Some(299792458.));
+    assert_eq!(lookup("math.tau").map(|c| c.value), Some(consts::TAU));
+    assert_eq!(lookup("phys.tau"), None);
+    assert_eq!(lookup("c"), None);
+    assert!(is_namespace("phys"));
+    assert!(!is_namespace("chem"));
+    assert_eq!(in_namespace("math").count(), 8);
+    for c in CONSTANTS.iter() {
+      assert!(c.unit().is_ok(), "{}", c.qualified_name());
+    }
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(lookup("phys.c").unwrap().to_value().to_string(), "299792458 m/s");
+    assert_eq!(
+      lookup("phys.G").unwrap().to_string(),
+      "phys.G = 0.000000000066743 m^3/(kg s^2) ± 0.0000000000000015 (Newtonian constant of gravitation)"
+    );
+    assert_eq!(
+      lookup("math.phi").unwrap().to_string(),
+      "math.phi = 1.618033988749895 (golden ratio)"
+    );
+  }
+}
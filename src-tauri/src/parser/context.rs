@@ -1,4 +1,29 @@
+use super::constants::{self, Constant};
+use super::dates::{self, Date};
+use super::decimal::{self, Decimal, DecimalOptions};
+use super::distributions;
+use super::extra_math::{self, DomainError};
+use super::expr::eval_value_str_with_context;
+use super::finance;
+use super::formulas::{self, Graph};
+use super::integer;
+use super::number_theory;
+use super::random::Rng;
+use super::rounding;
+use super::scope::Scope;
+use super::signatures::{self, Signature};
+use super::rational::Fraction;
+use super::statistics;
+use super::units::{self, Unit};
+use super::value::Value;
+use super::Error;
+use bigdecimal::{BigDecimal, RoundingMode, Signed};
+use chrono::NaiveDate;
+use num_rational::BigRational;
 use fnv::FnvHashMap;
+use serde::{Deserialize, Serialize};
+use num_complex::Complex64;
+use std::cell::Cell;
 use std::f64::consts;
 use std::fmt;
 use std::sync::Arc;
@@ -22,18 +47,167 @@ pub trait ContextProvider {
     Err(FuncEvalError::UnknownFunction)
   }
 
+  /// Looks up a variable that may hold a non-real value.
+  fn get_value(&self, name: &str) -> Option<Value> {
+    self.get_var(name).map(Value::Real)
+  }
+
+  /// Looks up a unit, by default only the built-in units are known.
+  fn get_unit(&self, name: &str) -> Option<Unit> {
+    units::lookup(name)
+  }
+
+  /// Evaluates a function on arbitrary values, by default only real arguments are accepted.
+  fn eval_value_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    match args.iter().map(Value::as_real).collect::<Option<Vec<f64>>>() {
+      Some(args) => self.eval_func(name, &args).map(Value::Real),
+      None => Err(FuncEvalError::ComplexArgument),
+    }
+  }
+
+  /// Whether operations without a real result should return a complex number instead of NaN.
+  fn complex_mode(&self) -> bool {
+    false
+  }
+
+  /// How number literals and arithmetic are evaluated.
+  fn number_mode(&self) -> NumberMode {
+    NumberMode::Float
+  }
+
+  /// Precision and rounding of decimal results in `NumberMode::Decimal`.
+  fn decimal_options(&self) -> DecimalOptions {
+    DecimalOptions::default()
+  }
+
   fn var<S: Into<String>>(&mut self, _var: S, _value: f64) -> &mut Self {
     self
   }
 }
 
+/// The arithmetic backend used to evaluate expressions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberMode {
+  /// Double precision floating point numbers.
+  #[default]
+  Float,
+  /// Arbitrary-precision decimals, so that `0.1 + 0.2 == 0.3`.
+  Decimal,
+  /// Exact fractions, so that `1/3 + 1/6 == 1/2`.
+  Fraction,
+}
+
+/// The unit of the angles taken by the trigonometric functions and returned by their inverses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AngleMode {
+  #[default]
+  Radians,
+  Degrees,
+  Gradians,
+}
+
+impl AngleMode {
+  /// The value of one unit of the mode in radians.
+  fn radians(self) -> f64 {
+    match self {
+      AngleMode::Radians => 1.,
+      AngleMode::Degrees => consts::PI / 180.,
+      AngleMode::Gradians => consts::PI / 200.,
+    }
+  }
+
+  /// A full turn, `None` in radians where it is not exactly representable.
+  fn turn(self) -> Option<f64> {
+    match self {
+      AngleMode::Radians => None,
+      AngleMode::Degrees => Some(360.),
+      AngleMode::Gradians => Some(400.),
+    }
+  }
+}
+
+/// Trigonometric functions taking an angle.
+const ANGLE_FUNCS: [&str; 3] = ["sin", "cos", "tan"];
+/// Inverse trigonometric functions returning an angle.
+const INVERSE_ANGLE_FUNCS: [&str; 4] = ["asin", "acos", "atan", "atan2"];
+
+/// Largest result snapped to 0, the bound relative to the argument would reach 1 around 1e15 and
+/// swallow `sin(1e16)`.
+const MAX_SNAP_ERROR: f64 = 1e-9;
+
+/// Functions drawing from the random generator of the context.
+const RANDOM_FUNCS: [&str; 5] = ["rand", "randn", "randint", "choose", "shuffle"];
+
+/// Names the calculator sets itself, the result of the last operation and the current date.
+const RESERVED_NAMES: [&str; 3] = ["ans", "today", "now"];
+
+/// Why a name cannot be assigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reserved {
+  Constant,
+  Function,
+  Keyword,
+}
+
+impl fmt::Display for Reserved {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Reserved::Constant => write!(f, "a constant"),
+      Reserved::Function => write!(f, "a function"),
+      Reserved::Keyword => write!(f, "a reserved name"),
+    }
+  }
+}
+
+/// Rounds away the error of the conversion to radians, so that `sin(30)` is 0.5 in degrees.
+fn snap(x: f64, targets: &[f64]) -> f64 {
+  targets
+    .iter()
+    .copied()
+    .find(|t| (x - t).abs() <= 4. * f64::EPSILON * t.abs().max(1.))
+    .unwrap_or(x)
+}
+
+/// A uniform integer between `a` and `b` included.
+fn randint(rng: &mut Rng, a: &Value, b: &Value) -> Result<i64, FuncEvalError> {
+  let (a, b) = match (a.as_real(), b.as_real()) {
+    (Some(a), Some(b)) => (a, b),
+    _ => return Err(FuncEvalError::ComplexArgument),
+  };
+  for x in [a, b] {
+    if x.fract() != 0. || x.abs() > 9007199254740992. {
+      return Err(DomainError::NotInteger(x).into());
+    }
+  }
+  if b < a {
+    return Err(DomainError::OutOfRange(b, a, f64::INFINITY).into());
+  }
+  let (a, b) = (a as i64, b as i64);
+  let n = rng.below((b - a) as u64 + 1).expect("The range has at least one integer");
+  Ok(a + n as i64)
+}
+
 /// Function evaluation error.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FuncEvalError {
   TooFewArguments,
   TooManyArguments,
   NumberArgs(usize),
+  /// None of the implementations of an overloaded function takes this number of arguments.
+  Arities(Vec<Arity>),
   UnknownFunction,
+  ComplexArgument,
+  ListArgument,
+  /// Functions only take plain numbers, not quantities with a unit.
+  UnitArgument,
+  /// Only dates are accepted, e.g. `weekday(3)`.
+  NotADate,
+  /// Numeric functions do not take dates, e.g. `sqrt(today)`.
+  DateArgument,
+  /// The function is undefined for the arguments, e.g. `gamma(0)`.
+  Domain(DomainError),
 }
 
 impl fmt::Display for FuncEvalError {
@@ -41,19 +215,47 @@ impl fmt::Display for FuncEvalError {
     match *self {
       FuncEvalError::UnknownFunction => write!(f, "Unknown function"),
       FuncEvalError::NumberArgs(i) => write!(f, "Expected {} arguments", i),
+      FuncEvalError::Arities(ref arities) => {
+        let arities: Vec<String> = arities.iter().map(Arity::to_string).collect();
+        match arities.split_last() {
+          Some((last, [])) => write!(f, "Expected {} arguments", last),
+          Some((last, rest)) => write!(f, "Expected {} or {} arguments", rest.join(", "), last),
+          None => write!(f, "Unknown function"),
+        }
+      }
       FuncEvalError::TooFewArguments => write!(f, "Too few arguments"),
       FuncEvalError::TooManyArguments => write!(f, "Too many arguments"),
+      FuncEvalError::ComplexArgument => write!(f, "Complex arguments are not supported"),
+      FuncEvalError::ListArgument => write!(f, "List arguments are not supported"),
+      FuncEvalError::UnitArgument => write!(f, "Arguments with units are not supported"),
+      FuncEvalError::NotADate => write!(f, "Expected a date argument"),
+      FuncEvalError::DateArgument => write!(f, "Date arguments are not supported"),
+      FuncEvalError::Domain(ref e) => e.fmt(f),
     }
   }
 }
 
+impl From<DomainError> for FuncEvalError {
+  fn from(err: DomainError) -> FuncEvalError {
+    FuncEvalError::Domain(err)
+  }
+}
+
 impl std::error::Error for FuncEvalError {
   fn description(&self) -> &str {
     match *self {
       FuncEvalError::UnknownFunction => "unknown function",
-      FuncEvalError::NumberArgs(_) => "wrong number of function arguments",
+      FuncEvalError::NumberArgs(_) | FuncEvalError::Arities(_) => {
+        "wrong number of function arguments"
+      }
       FuncEvalError::TooFewArguments => "too few function arguments",
       FuncEvalError::TooManyArguments => "too many function arguments",
+      FuncEvalError::ComplexArgument => "complex function argument",
+      FuncEvalError::ListArgument => "list function argument",
+      FuncEvalError::UnitArgument => "function argument with a unit",
+      FuncEvalError::NotADate => "function argument that is not a date",
+      FuncEvalError::DateArgument => "date function argument",
+      FuncEvalError::Domain(_) => "argument outside of the function domain",
     }
   }
 }
@@ -69,17 +271,19 @@ pub fn min_array(xs: &[f64]) -> f64 {
 }
 
 #[doc(hidden)]
-pub fn avg_array(xs: &[f64]) -> f64 {
-  xs.iter().fold(0., |m, &x| m + x) / xs.len() as f64
+pub fn avg_array(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  if xs.is_empty() {
+    return Err(FuncEvalError::TooFewArguments);
+  }
+  Ok(xs.iter().fold(0., |m, &x| m + x) / xs.len() as f64)
 }
 
 #[doc(hidden)]
 pub fn builtin<'a>() -> Context<'a> {
-  // TODO: cache this (lazy_static)
   Context::new()
 }
 
-type ContextHashMap<K, V> = FnvHashMap<K, V>;
+pub(super) type ContextHashMap<K, V> = FnvHashMap<K, V>;
 
 /// A structure for storing variables/constants and functions to be used in an expression.
 ///
@@ -98,17 +302,28 @@ type ContextHashMap<K, V> = FnvHashMap<K, V>;
 /// ```
 #[derive(Clone)]
 pub struct Context<'a> {
-  vars: ContextHashMap<String, f64>,
-  funcs: ContextHashMap<String, GuardedFunc<'a>>,
+  /// The layers of names from the bottom: the builtins, shared by the contexts of a thread, the
+  /// loaded libraries and the workspace. Definitions go to the top layer.
+  layers: Vec<Arc<Scope<'a>>>,
+  /// Namespaces of constants brought into scope with `use`.
+  namespaces: Vec<String>,
+  angle_mode: AngleMode,
+  /// Whether float noise is rounded away from trigonometric results, `sin(pi)` is 0.
+  snap: bool,
+  /// The generator of `rand()` and the other random functions, seeded with `seed`.
+  rng: Cell<Rng>,
+  complex: bool,
+  number_mode: NumberMode,
+  decimal: DecimalOptions,
 }
 
 impl<'a> Context<'a> {
   /// Creates a context with built-in constants and functions.
   pub fn new() -> Context<'a> {
-    thread_local!(static DEFAULT_CONTEXT: Context<'static> = {
+    thread_local!(static BUILTINS: Arc<Scope<'static>> = {
         let mut ctx = Context::empty();
-        ctx.var("pi", consts::PI);
-        ctx.var("e", consts::E);
+        ctx.define_constant("pi", Value::Real(consts::PI));
+        ctx.define_constant("e", Value::Real(consts::E));
 
         ctx.func("sqrt", f64::sqrt);
         ctx.func("exp", f64::exp);
@@ -127,55 +342,458 @@ impl<'a> Context<'a> {
         ctx.func("asinh", f64::asinh);
         ctx.func("acosh", f64::acosh);
         ctx.func("atanh", f64::atanh);
-        ctx.func("floor", f64::floor);
-        ctx.func("ceil", f64::ceil);
-        ctx.func("round", f64::round);
         ctx.func("signum", f64::signum);
+        ctx.func("fract", f64::fract);
+        ctx.func("cbrt", f64::cbrt);
+        ctx.func("log2", f64::log2);
+        for (name, mode) in rounding::MODES {
+          ctx.try_funcn(name, rounding::rounder(mode), 1..3);
+          ctx.decimal_func(name, move |xs, _| rounding::round_decimal(xs, mode));
+          ctx.fraction_func(name, move |xs| rounding::round_fraction(xs, mode));
+        }
+        ctx.try_funcn("roundto", rounding::roundto, 2);
+        ctx.try_funcn("mround", rounding::mround, 2);
+        ctx.try_funcn("clamp", rounding::clamp, 3);
+        ctx.funcn("lerp", rounding::lerp, 3);
+        ctx.funcn("hypot", rounding::hypot, 1..);
+        ctx.try_funcn("nthroot", rounding::nthroot, 2);
+        ctx.func("erf", extra_math::erf);
+        ctx.func("erfc", extra_math::erfc);
+        ctx.try_func("gamma", extra_math::gamma);
+        ctx.try_func("lgamma", extra_math::lgamma);
+        ctx.try_func("digamma", extra_math::digamma);
+        ctx.try_func("zeta", extra_math::zeta);
+        ctx.try_func2("beta", extra_math::beta);
         ctx.func2("atan2", f64::atan2);
         ctx.func2("logn", |base, num| f64::log(num, base));
         ctx.funcn("max", max_array, 1..);
         ctx.funcn("min", min_array, 1..);
-        ctx.funcn("avg", avg_array, 1..);
-        ctx
+        ctx.try_funcn("avg", avg_array, 1..);
+        ctx.funcn("sum", statistics::sum, 1..);
+        ctx.funcn("sumsq", statistics::sumsq, 1..);
+        ctx.try_funcn("median", statistics::median, 1..);
+        ctx.try_funcn("mode", statistics::mode, 1..);
+        ctx.try_funcn("range", statistics::range, 1..);
+        ctx.try_funcn("var", statistics::var, 2..);
+        ctx.try_funcn("varp", statistics::varp, 1..);
+        ctx.try_funcn("stdev", statistics::stdev, 2..);
+        ctx.try_funcn("stdevp", statistics::stdevp, 1..);
+        ctx.try_funcn("percentile", statistics::percentile, 2..);
+        ctx.try_funcn("iqr", statistics::iqr, 1..);
+        ctx.try_funcn("geomean", statistics::geomean, 1..);
+        ctx.try_funcn("harmmean", statistics::harmmean, 1..);
+        ctx.try_funcn("skew", statistics::skew, 1..);
+        ctx.try_funcn("kurtosis", statistics::kurtosis, 1..);
+        ctx.try_funcn("normpdf", distributions::normpdf, 1..4);
+        ctx.try_funcn("normcdf", distributions::normcdf, 1..4);
+        ctx.try_funcn("invnorm", distributions::invnorm, 1..4);
+        ctx.try_funcn("binompdf", distributions::binompdf, 3..4);
+        ctx.try_funcn("binomcdf", distributions::binomcdf, 3..4);
+        ctx.try_func2("poissonpdf", distributions::poissonpdf);
+        ctx.try_func2("poissoncdf", distributions::poissoncdf);
+        ctx.try_func2("tpdf", distributions::tpdf);
+        ctx.try_func2("tcdf", distributions::tcdf);
+        ctx.try_func2("invt", distributions::invt);
+        ctx.try_func2("chi2pdf", distributions::chi2pdf);
+        ctx.try_func2("chi2cdf", distributions::chi2cdf);
+        ctx.try_func2("invchi2", distributions::invchi2);
+        ctx.try_func2("exppdf", distributions::exppdf);
+        ctx.try_func2("expcdf", distributions::expcdf);
+        ctx.try_func2("invexp", distributions::invexp);
+        ctx.try_funcn("fv", finance::fv, 3..6);
+        ctx.try_funcn("pv", finance::pv, 3..6);
+        ctx.try_funcn("pmt", finance::pmt, 3..6);
+        ctx.try_funcn("nper", finance::nper, 3..6);
+        ctx.try_funcn("rate", finance::rate, 3..7);
+        ctx.try_funcn("npv", finance::npv, 2..);
+        ctx.try_funcn("irr", finance::irr, 2..);
+        ctx.try_funcn("gcd", number_theory::gcd, 1..);
+        ctx.try_funcn("lcm", number_theory::lcm, 1..);
+
+        ctx.complex_func("sqrt", Complex64::sqrt);
+        ctx.complex_func("exp", Complex64::exp);
+        ctx.complex_func("ln", Complex64::ln);
+        ctx.complex_func("log10", |z| z.ln() / consts::LN_10);
+        ctx.complex_func("log2", |z| z.ln() / consts::LN_2);
+        ctx.complex_func("sin", Complex64::sin);
+        ctx.complex_func("cos", Complex64::cos);
+        ctx.complex_func("tan", Complex64::tan);
+        ctx.complex_func("asin", Complex64::asin);
+        ctx.complex_func("acos", Complex64::acos);
+        ctx.complex_func("atan", Complex64::atan);
+        ctx.complex_func("sinh", Complex64::sinh);
+        ctx.complex_func("cosh", Complex64::cosh);
+        ctx.complex_func("tanh", Complex64::tanh);
+        ctx.complex_func("asinh", Complex64::asinh);
+        ctx.complex_func("acosh", Complex64::acosh);
+        ctx.complex_func("atanh", Complex64::atanh);
+
+        ctx.value_func("abs", 1, |args| complex_arg(&args[0]).map(|z| Value::Real(z.norm())));
+        ctx.value_func("re", 1, |args| complex_arg(&args[0]).map(|z| Value::Real(z.re)));
+        ctx.value_func("im", 1, |args| complex_arg(&args[0]).map(|z| Value::Real(z.im)));
+        ctx.value_func("arg", 1, |args| complex_arg(&args[0]).map(|z| Value::Real(z.arg())));
+        ctx.value_func("conj", 1, |args| {
+          complex_arg(&args[0]).map(|z| Value::from_complex(z.conj()))
+        });
+        ctx.value_func("polar", 1, |args| {
+          let (r, theta) = complex_arg(&args[0])?.to_polar();
+          Ok(Value::List(vec![Value::Real(r), Value::Real(theta)]))
+        });
+        ctx.value_func("nCr", 2, number_theory::ncr);
+        ctx.value_func("nPr", 2, number_theory::npr);
+        ctx.value_func("isprime", 1, number_theory::isprime);
+        ctx.value_func("nextprime", 1, number_theory::nextprime);
+        ctx.value_func("factor", 1, number_theory::factor);
+        ctx.value_func("totient", 1, number_theory::totient);
+        ctx.value_func("modpow", 3, number_theory::modpow);
+        ctx.value_func("modinv", 2, number_theory::modinv);
+        ctx.value_func("emod", 2, number_theory::emod);
+        ctx.value_funcn("quartiles", statistics::quartiles, 1..);
+        ctx.value_func("amortize", 3, finance::amortize);
+        ctx.value_funcn("date", dates::date, 3..7);
+        ctx.value_func("weekday", 1, dates::weekday);
+        ctx.value_func("weeknum", 1, dates::weeknum);
+        ctx.value_funcn("eomonth", dates::eomonth, 1..3);
+        ctx.set_holidays(vec![]);
+        ctx.value_func("mixed", 1, |args| match Fraction::from_value(&args[0]) {
+          Some(f) => Ok(Value::Fraction(f.to_mixed())),
+          None => Ok(args[0].clone()),
+        });
+        ctx.value_func("rect", 2, |args| {
+          match (args[0].as_real(), args[1].as_real()) {
+            (Some(r), Some(theta)) => Ok(Value::from_complex(Complex64::from_polar(r, theta))),
+            _ => Err(FuncEvalError::ComplexArgument),
+          }
+        });
+
+        ctx.decimal_func("abs", |xs, _| unary_decimal(xs, BigDecimal::abs));
+        ctx.decimal_func("signum", |xs, _| unary_decimal(xs, BigDecimal::signum));
+        ctx.decimal_func("fract", |xs, _| {
+          unary_decimal(xs, |x| x - decimal::round(x, RoundingMode::Down))
+        });
+        ctx.decimal_func("clamp", |xs, _| match xs {
+          [x, lo, hi] if lo <= hi => Some(x.clone().clamp(lo.clone(), hi.clone())),
+          _ => None,
+        });
+        ctx.decimal_func("lerp", |xs, _| match xs {
+          [a, b, t] => Some(a + (b - a) * t),
+          _ => None,
+        });
+        ctx.decimal_func("sqrt", |xs, ctx| match xs {
+          [x] => x.sqrt_with_context(ctx),
+          _ => None,
+        });
+        ctx.decimal_func("exp", |xs, ctx| unary_decimal(xs, |x| x.exp_with_context(ctx)));
+        ctx.decimal_func("max", |xs, _| xs.iter().max().cloned());
+        ctx.decimal_func("min", |xs, _| xs.iter().min().cloned());
+        ctx.decimal_func("avg", |xs, ctx| {
+          if xs.is_empty() {
+            return None;
+          }
+          let sum: BigDecimal = xs.iter().sum();
+          Some(ctx.round_decimal(sum / BigDecimal::from(xs.len() as u64)))
+        });
+
+        ctx.fraction_func("abs", |xs| unary_fraction(xs, BigRational::abs));
+        ctx.fraction_func("signum", |xs| unary_fraction(xs, BigRational::signum));
+        ctx.fraction_func("fract", |xs| unary_fraction(xs, BigRational::fract));
+        ctx.fraction_func("clamp", |xs| match xs {
+          [x, lo, hi] if lo <= hi => Some(x.clone().clamp(lo.clone(), hi.clone())),
+          _ => None,
+        });
+        ctx.fraction_func("lerp", |xs| match xs {
+          [a, b, t] => Some(a + (b - a) * t),
+          _ => None,
+        });
+        ctx.fraction_func("max", |xs| xs.iter().max().cloned());
+        ctx.fraction_func("min", |xs| xs.iter().min().cloned());
+        ctx.fraction_func("avg", |xs| {
+          if xs.is_empty() {
+            return None;
+          }
+          let sum: BigRational = xs.iter().sum();
+          Some(sum / BigRational::from_integer(xs.len().into()))
+        });
+
+        for signature in signatures::builtins() {
+          ctx.describe(signature);
+        }
+        ctx.layers.remove(0)
     });
 
-    DEFAULT_CONTEXT.with(|ctx| ctx.clone())
+    let mut ctx = Context::empty();
+    ctx.layers.insert(0, BUILTINS.with(Arc::clone));
+    ctx
   }
 
   /// Creates an empty contexts.
   pub fn empty() -> Context<'a> {
     Context {
-      vars: ContextHashMap::default(),
-      funcs: ContextHashMap::default(),
+      layers: vec![Arc::default()],
+      namespaces: vec![],
+      angle_mode: AngleMode::default(),
+      snap: false,
+      rng: Cell::new(Rng::from_time()),
+      complex: false,
+      number_mode: NumberMode::default(),
+      decimal: DecimalOptions::default(),
     }
   }
 
+  /// Removes the variables and formulas of the top layer, the constants are kept.
   pub fn clear(&mut self) {
-    self.vars.clear();
+    let top = self.top();
+    top.vars.clear();
+    top.formulas.clear();
+  }
+
+  /// Adds an empty layer on top, the new definitions go to it.
+  pub fn push_layer(&mut self) -> &mut Self {
+    self.layers.push(Arc::default());
+    self
+  }
+
+  /// The layer receiving the definitions, copied first if it is shared with other contexts.
+  fn top(&mut self) -> &mut Scope<'a> {
+    Arc::make_mut(self.layers.last_mut().expect("A context has at least one layer"))
+  }
+
+  /// Looks a name up in the layers from the top, the first layer defining it wins.
+  fn find<'s, T>(&'s self, lookup: impl Fn(&'s Scope<'a>) -> Option<&'s T>) -> Option<&'s T> {
+    self.layers.iter().rev().find_map(|scope| lookup(scope))
+  }
+
+  /// The overloads of a name in the top layer, starting from those of the layer below defining
+  /// it, so that adding an implementation keeps the others.
+  fn top_overloads<F: Clone>(
+    &mut self,
+    name: String,
+    map: for<'s> fn(&'s Scope<'a>) -> &'s ContextHashMap<String, Overloads<F>>,
+    map_mut: for<'s> fn(&'s mut Scope<'a>) -> &'s mut ContextHashMap<String, Overloads<F>>,
+  ) -> &mut Overloads<F> {
+    let inherited = self.find(|scope| map(scope).get(&name)).cloned().unwrap_or_default();
+    map_mut(self.top()).entry(name).or_insert(inherited)
+  }
+
+  /// A variable or constant, the constants of all the layers come before the variables.
+  fn lookup_value(&self, name: &str) -> Option<&Value> {
+    self.find(|scope| scope.constants.get(name)).or_else(|| self.find(|scope| scope.vars.get(name)))
+  }
+
+  /// Why a name cannot be assigned, if it is a constant, a function or a reserved name.
+  pub fn reserved(&self, name: &str) -> Option<Reserved> {
+    if self.find(|scope| scope.constants.get(name)).is_some() {
+      Some(Reserved::Constant)
+    } else if self
+      .layers
+      .iter()
+      .any(|scope| scope.defines_func(name) || scope.complex_funcs.contains_key(name))
+      || RANDOM_FUNCS.contains(&name)
+    {
+      Some(Reserved::Function)
+    } else if RESERVED_NAMES.contains(&name) || (self.complex && (name == "i" || name == "j")) {
+      Some(Reserved::Keyword)
+    } else {
+      None
+    }
+  }
+
+  /// Brings the constants of a namespace into scope unqualified, `c` for `phys.c` after
+  /// `use phys`. Returns `false` when no constants are defined in the namespace.
+  pub fn use_namespace(&mut self, namespace: &str) -> bool {
+    if !constants::is_namespace(namespace) {
+      return false;
+    }
+    if !self.namespaces.iter().any(|n| n == namespace) {
+      self.namespaces.push(namespace.to_string());
+    }
+    true
+  }
+
+  /// The constants of a namespace, listed by `help phys`.
+  pub fn namespace_constants(&self, namespace: &str) -> Vec<&'static Constant> {
+    constants::in_namespace(namespace).collect()
+  }
+
+  /// Looks up a constant by its qualified name, or its name in a namespace in scope.
+  pub fn constant(&self, name: &str) -> Option<&'static Constant> {
+    constants::lookup(name).or_else(|| {
+      self
+        .namespaces
+        .iter()
+        .find_map(|namespace| constants::find(namespace, name))
+    })
+  }
+
+  /// Enables or disables complex results, and the `i`/`j` imaginary unit.
+  pub fn set_complex_mode(&mut self, complex: bool) -> &mut Self {
+    self.complex = complex;
+    self
+  }
+
+  /// Selects the arithmetic backend used for literals, operators and builtins.
+  pub fn set_number_mode(&mut self, number_mode: NumberMode) -> &mut Self {
+    self.number_mode = number_mode;
+    self
+  }
+
+  /// Selects the unit of the angles of the trigonometric functions and of `deg`-like suffixes.
+  pub fn set_angle_mode(&mut self, angle_mode: AngleMode) -> &mut Self {
+    self.angle_mode = angle_mode;
+    self
+  }
+
+  /// Enables or disables the removal of float noise from trigonometric results.
+  pub fn set_snap(&mut self, snap: bool) -> &mut Self {
+    self.snap = snap;
+    self
+  }
+
+  /// Restarts the random generator from a seed, for reproducible random numbers.
+  pub fn seed(&mut self, seed: u64) -> &mut Self {
+    self.rng.set(Rng::new(seed));
+    self
+  }
+
+  /// Runs an evaluation without advancing the random generator, so that the live preview of
+  /// `rand()` is stable and shows the number the operation will store.
+  pub fn preview<T>(&self, eval: impl FnOnce(&Self) -> T) -> T {
+    let rng = self.rng.get();
+    let result = eval(self);
+    self.rng.set(rng);
+    result
+  }
+
+  /// Sets the precision and rounding of decimal results.
+  pub fn set_decimal_options(&mut self, options: DecimalOptions) -> &mut Self {
+    self.decimal = options;
+    self
   }
 
   /// Adds a new variable/constant.
   pub fn var<S: Into<String>>(&mut self, var: S, value: f64) -> &mut Self {
-    self.vars.insert(var.into(), value);
+    self.value_var(var, Value::Real(value))
+  }
+
+  /// Adds a user defined unit, it takes precedence over a built-in unit of the same name.
+  pub fn unit<S: Into<String>>(&mut self, name: S, unit: Unit) -> &mut Self {
+    self.top().units.insert(name.into(), unit);
     self
   }
 
+  /// Sets the holidays skipped by `workdays`, on top of the weekends.
+  pub fn set_holidays(&mut self, holidays: Vec<NaiveDate>) -> &mut Self {
+    self.value_funcn("workdays", move |args: &[Value]| dates::workdays(args, &holidays), 2..3)
+  }
+
+  /// Adds a read-only value, it takes precedence over a variable of the same name.
+  pub fn define_constant<S: Into<String>>(&mut self, name: S, value: Value) -> &mut Self {
+    let name = name.into();
+    let top = self.top();
+    top.vars.remove(&name);
+    top.formulas.remove(&name);
+    top.constants.insert(name, value);
+    self
+  }
+
+  /// Adds a new variable holding any kind of value, it replaces a formula of the same name.
+  pub fn value_var<S: Into<String>>(&mut self, var: S, value: Value) -> &mut Self {
+    let var = var.into();
+    let top = self.top();
+    top.formulas.remove(&var);
+    top.vars.insert(var, value);
+    self
+  }
+
+  /// The expression of a variable defined with `:=`.
+  pub fn formula(&self, name: &str) -> Option<&str> {
+    self.find(|scope| scope.formulas.get(name)).map(String::as_str)
+  }
+
+  /// The formulas of all the layers, those of the upper layers hide those below.
+  fn formula_graph(&self) -> Graph {
+    let mut formulas = std::collections::BTreeMap::new();
+    for scope in &self.layers {
+      formulas.extend(scope.formulas.iter());
+    }
+    Graph::new(formulas)
+  }
+
+  /// Rejects a formula reading its own variable, directly or through other formulas.
+  pub fn check_formula(&self, name: &str, formula: &str) -> Result<(), Error> {
+    let reads = formulas::dependencies(formula)?;
+    match self.formula_graph().cycle(name, &reads) {
+      Some(cycle) => Err(Error::Circular(cycle)),
+      None => Ok(()),
+    }
+  }
+
+  /// Adds a variable computed from an expression, e.g. `a * 2`, which is computed again by
+  /// `recompute` when the variables it reads change.
+  pub fn define_formula<S: Into<String>>(
+    &mut self,
+    name: S,
+    formula: &str,
+  ) -> Result<Value, Error> {
+    let name = name.into();
+    self.check_formula(&name, formula)?;
+    let (_, value) = eval_value_str_with_context(formula, &*self)?;
+    let top = self.top();
+    top.vars.insert(name.clone(), value.clone());
+    top.formulas.insert(name, formula.trim().to_string());
+    Ok(value)
+  }
+
+  /// Adds the formula of a variable without computing it, e.g. when the formulas are loaded in
+  /// any order. `recompute_all` computes them afterwards.
+  pub fn set_formula<S, F>(&mut self, name: S, formula: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Into<String>,
+  {
+    self.top().formulas.insert(name.into(), formula.into());
+    self
+  }
+
+  /// Computes again the formulas reading some variables, directly or through other formulas,
+  /// after they changed. A formula that fails, e.g. reading a removed variable, leaves its
+  /// variable undefined until it can be computed again.
+  ///
+  /// Returns the variables in the order they were computed, with their value or error.
+  pub fn recompute(&mut self, names: &[&str]) -> Vec<(String, Result<Value, Error>)> {
+    let order = self.formula_graph().dependents(names);
+    self.compute_formulas(order)
+  }
+
+  /// Computes all the formulas, each one after the formulas it reads.
+  pub fn recompute_all(&mut self) -> Vec<(String, Result<Value, Error>)> {
+    let order = self.formula_graph().order();
+    self.compute_formulas(order)
+  }
+
+  fn compute_formulas(&mut self, names: Vec<String>) -> Vec<(String, Result<Value, Error>)> {
+    names
+      .into_iter()
+      .map(|name| {
+        let formula = self.formula(&name).unwrap_or_default().to_string();
+        let result = eval_value_str_with_context(&formula, &*self).map(|(_, value)| value);
+        let top = self.top();
+        match result {
+          Ok(ref value) => top.vars.insert(name.clone(), value.clone()),
+          Err(_) => top.vars.remove(&name),
+        };
+        (name, result)
+      })
+      .collect()
+  }
+
   /// Adds a new function of one argument.
   pub fn func<S, F>(&mut self, name: S, func: F) -> &mut Self
   where
     S: Into<String>,
     F: Fn(f64) -> f64 + 'a + Send + Sync,
   {
-    self.funcs.insert(
-      name.into(),
-      Arc::new(move |args: &[f64]| {
-        if args.len() == 1 {
-          Ok(func(args[0]))
-        } else {
-          Err(FuncEvalError::NumberArgs(1))
-        }
-      }),
-    );
+    self.insert_func(name.into(), 1, move |args: &[f64]| Ok(func(args[0])));
     self
   }
 
@@ -185,16 +803,27 @@ impl<'a> Context<'a> {
     S: Into<String>,
     F: Fn(f64, f64) -> f64 + 'a + Send + Sync,
   {
-    self.funcs.insert(
-      name.into(),
-      Arc::new(move |args: &[f64]| {
-        if args.len() == 2 {
-          Ok(func(args[0], args[1]))
-        } else {
-          Err(FuncEvalError::NumberArgs(2))
-        }
-      }),
-    );
+    self.insert_func(name.into(), 2, move |args: &[f64]| Ok(func(args[0], args[1])));
+    self
+  }
+
+  /// Adds a new function of one argument that can be undefined for some arguments.
+  pub fn try_func<S, F>(&mut self, name: S, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(f64) -> Result<f64, DomainError> + 'a + Send + Sync,
+  {
+    self.insert_func(name.into(), 1, move |args: &[f64]| Ok(func(args[0])?));
+    self
+  }
+
+  /// Adds a new function of two arguments that can be undefined for some arguments.
+  pub fn try_func2<S, F>(&mut self, name: S, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(f64, f64) -> Result<f64, DomainError> + 'a + Send + Sync,
+  {
+    self.insert_func(name.into(), 2, move |args: &[f64]| Ok(func(args[0], args[1])?));
     self
   }
 
@@ -204,6 +833,10 @@ impl<'a> Context<'a> {
   /// `n..m`, `..`, `n..`, `..m`. The range is half-open, exclusive on the right, as is common in
   /// Rust standard library.
   ///
+  /// A name can have several implementations taking different numbers of arguments, the one
+  /// accepting the number of arguments of the call is evaluated. Adding a function replaces the
+  /// implementations that accept any of the same numbers.
+  ///
   /// # Example
   ///
   /// ```rust
@@ -221,9 +854,337 @@ impl<'a> Context<'a> {
     F: Fn(&[f64]) -> f64 + 'a + Send + Sync,
     N: ArgGuard,
   {
-    self.funcs.insert(name.into(), n_args.to_arg_guard(func));
+    self.insert_func(name.into(), n_args, move |args: &[f64]| Ok(func(args)));
+    self
+  }
+
+  /// Adds a new function of a variable number of arguments that can be undefined for some
+  /// arguments.
+  pub fn try_funcn<S, F, E, N>(&mut self, name: S, func: F, n_args: N) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(&[f64]) -> Result<f64, E> + 'a + Send + Sync,
+    E: Into<FuncEvalError>,
+    N: ArgGuard,
+  {
+    self.insert_func(name.into(), n_args, move |args: &[f64]| func(args).map_err(Into::into));
+    self
+  }
+
+  /// Adds the complex counterpart of a function of one argument.
+  ///
+  /// It is used when the argument is complex, or in complex mode when the real function has no
+  /// result (e.g. `sqrt(-1)`).
+  pub fn complex_func<S, F>(&mut self, name: S, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(Complex64) -> Complex64 + 'a + Send + Sync,
+  {
+    self.top().complex_funcs.insert(name.into(), Arc::new(func));
+    self
+  }
+
+  /// Adds a function taking `n_args` values of any kind, it takes precedence over the real and
+  /// complex functions of the same name.
+  pub fn value_func<S, F>(&mut self, name: S, n_args: usize, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(&[Value]) -> Result<Value, FuncEvalError> + 'a + Send + Sync,
+  {
+    self.value_funcn(name, func, n_args)
+  }
+
+  /// Adds a function taking a variable number of values of any kind, `n_args` is the same as in
+  /// `funcn`.
+  pub fn value_funcn<S, F, N>(&mut self, name: S, func: F, n_args: N) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(&[Value]) -> Result<Value, FuncEvalError> + 'a + Send + Sync,
+    N: ArgGuard,
+  {
+    let overloads = self.top_overloads(name.into(), |s| &s.value_funcs, |s| &mut s.value_funcs);
+    overloads.insert(n_args.arity(), Arc::new(func));
+    self
+  }
+
+  /// Documents a function, replacing the signatures of the name taking any of the same numbers of
+  /// arguments.
+  pub fn describe(&mut self, signature: Signature) -> &mut Self {
+    let name = signature.name.clone();
+    let overloads = self.top_overloads(name, |s| &s.signatures, |s| &mut s.signatures);
+    overloads.insert(signature.arity, signature);
+    self
+  }
+
+  /// The signatures of a function, those of the implementations without documentation, e.g. user
+  /// functions, only have the parameters. Empty for unknown functions.
+  pub fn signatures(&self, name: &str) -> Vec<Signature> {
+    let documented: Vec<Signature> = self
+      .find(|scope| scope.signatures.get(name))
+      .map_or(vec![], |overloads| overloads.entries.iter().map(|(_, s)| s.clone()).collect());
+    let arities = self.find(|scope| scope.funcs.get(name)).into_iter().flat_map(Overloads::arities);
+    let value_funcs = self.find(|scope| scope.value_funcs.get(name));
+    let arities = arities.chain(value_funcs.into_iter().flat_map(Overloads::arities));
+    let mut undocumented: Vec<Signature> = vec![];
+    for arity in arities {
+      let known = documented.iter().chain(&undocumented).any(|s| s.arity.overlaps(&arity));
+      if !known {
+        undocumented.push(Signature::generic(name, arity));
+      }
+    }
+    let mut signatures: Vec<Signature> = documented.into_iter().chain(undocumented).collect();
+    signatures.sort_by_key(|signature| signature.arity);
+    signatures
+  }
+
+  /// The signatures of all the functions, sorted by name.
+  pub fn all_signatures(&self) -> Vec<Signature> {
+    let mut names: Vec<&String> = self
+      .layers
+      .iter()
+      .flat_map(|scope| {
+        scope.signatures.keys().chain(scope.funcs.keys()).chain(scope.value_funcs.keys())
+      })
+      .collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().flat_map(|name| self.signatures(name)).collect()
+  }
+
+  /// Adds an implementation of a real function, the implementations of the name taking other
+  /// numbers of arguments are kept.
+  fn insert_func<F, N>(&mut self, name: String, n_args: N, func: F)
+  where
+    F: Fn(&[f64]) -> Result<f64, FuncEvalError> + 'a + Send + Sync,
+    N: ArgGuard,
+  {
+    let overloads = self.top_overloads(name, |s| &s.funcs, |s| &mut s.funcs);
+    overloads.insert(n_args.arity(), Arc::new(func));
+  }
+
+  /// Adds the exact decimal implementation of a function, used in decimal mode.
+  ///
+  /// The function returns `None` when it has no exact result for the arguments (wrong number of
+  /// arguments, negative square root, ...) and the f64 implementation should be used instead.
+  pub fn decimal_func<S, F>(&mut self, name: S, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(&[BigDecimal], &bigdecimal::Context) -> Option<BigDecimal> + 'a + Send + Sync,
+  {
+    self.top().decimal_funcs.insert(name.into(), Arc::new(func));
     self
   }
+
+  /// Adds the exact rational implementation of a function, used in fraction mode.
+  ///
+  /// The function returns `None` when the result is not rational or it has no implementation for
+  /// the arguments, and the f64 implementation should be used instead.
+  pub fn fraction_func<S, F>(&mut self, name: S, func: F) -> &mut Self
+  where
+    S: Into<String>,
+    F: Fn(&[BigRational]) -> Option<BigRational> + 'a + Send + Sync,
+  {
+    self.top().fraction_funcs.insert(name.into(), Arc::new(func));
+    self
+  }
+
+  /// Evaluates a function with its real, complex or value implementation.
+  fn eval_float_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    let has_real_func = self.find(|scope| scope.funcs.get(name)).is_some();
+    if let Some(overloads) = self.find(|scope| scope.value_funcs.get(name)) {
+      match overloads.get(args.len()) {
+        Ok(f) => return f(args),
+        // A real implementation may take this number of arguments instead
+        Err(e) if !has_real_func => return Err(e),
+        Err(_) => {}
+      }
+    }
+    if args.iter().any(|arg| matches!(arg, Value::List(_) | Value::Table(_))) {
+      return Err(FuncEvalError::ListArgument);
+    }
+    if args.iter().any(|arg| matches!(arg, Value::Quantity(_))) {
+      return Err(FuncEvalError::UnitArgument);
+    }
+    if args.iter().any(|arg| matches!(arg, Value::Date(_))) {
+      return Err(FuncEvalError::DateArgument);
+    }
+    let complex_func = self.find(|scope| scope.complex_funcs.get(name));
+
+    if let Some(reals) = args.iter().map(Value::as_real).collect::<Option<Vec<f64>>>() {
+      let r = self.eval_func(name, &reals)?;
+      return match (complex_func, reals.as_slice()) {
+        (Some(f), &[x]) if self.complex && r.is_nan() && !x.is_nan() => {
+          Ok(Value::from_complex(f(Complex64::new(x, 0.))))
+        }
+        _ => Ok(Value::Real(r)),
+      };
+    }
+
+    match (complex_func, args) {
+      (Some(f), [z]) => Ok(Value::from_complex(f(complex_arg(z)?))),
+      (Some(_), _) => Err(FuncEvalError::NumberArgs(1)),
+      (None, _) if has_real_func => Err(FuncEvalError::ComplexArgument),
+      (None, _) => Err(FuncEvalError::UnknownFunction),
+    }
+  }
+
+  /// Evaluates a function with the implementation of the number mode.
+  fn eval_mode_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    let value = match self.number_mode {
+      NumberMode::Float => self.eval_float_func(name, args),
+      NumberMode::Decimal => match self.eval_decimal_func(name, args) {
+        Some(d) => Ok(Value::Decimal(d)),
+        // No exact decimal implementation, fall back to f64 and flag the result
+        None => self.eval_float_func(name, args).map(Decimal::approximate),
+      },
+      // Irrational functions (`sqrt`, `sin`, ...) fall back to f64, the result is a plain real
+      NumberMode::Fraction => match self.eval_fraction_func(name, args) {
+        Some(f) => Ok(Value::Fraction(f)),
+        None => self.eval_float_func(name, args),
+      },
+    }?;
+    // Integer builtins (`nCr`, `modpow`, ...) return exact integers whatever the number mode
+    match value {
+      Value::Integer(n) => Ok(integer::to_mode_value(n, self.number_mode)),
+      value => Ok(value),
+    }
+  }
+
+  /// Evaluates a trigonometric function in degrees or gradians, the arguments of `sin`, `cos` and
+  /// `tan` are converted to radians and the results of their inverses from radians. With snapping
+  /// enabled, the rounding error of `sin(pi)` is also removed in radians.
+  fn eval_angle_func(&self, name: &str, args: &[Value]) -> Option<Result<Value, FuncEvalError>> {
+    let (factor, turn) = (self.angle_mode.radians(), self.angle_mode.turn());
+    if turn.is_none() && !self.snap {
+      return None;
+    }
+    if ANGLE_FUNCS.contains(&name) {
+      let args: Vec<Value> = args
+        .iter()
+        .map(|arg| match arg.as_real() {
+          // A whole number of turns is removed first, `sin(360)` is exactly 0
+          Some(x) => Value::Real(turn.map_or(x, |turn| x % turn) * factor),
+          None => match arg.as_complex() {
+            Some(z) => Value::from_complex(z * factor),
+            None => arg.clone(),
+          },
+        })
+        .collect();
+      // The error of the argument grows with its magnitude, `sin(1000 pi)` is off by ~1e-13
+      let error = args
+        .first()
+        .and_then(Value::as_real)
+        .map_or(0., |x| (4. * f64::EPSILON * x.abs()).min(MAX_SNAP_ERROR));
+      let value = self.eval_mode_func(name, &args);
+      return Some(value.map(|value| match value.as_real() {
+        Some(x) if x.abs() <= error => self.real(0.),
+        Some(x) if x.is_finite() => self.real(snap(x, &[-1., -0.5, 0.5, 1.])),
+        _ => value,
+      }));
+    }
+    if INVERSE_ANGLE_FUNCS.contains(&name) && turn.is_some() {
+      let value = self.eval_mode_func(name, args);
+      return Some(value.map(|value| match value.as_real() {
+        Some(x) => self.real(snap(x / factor, &[(x / factor).round()])),
+        None => match value.as_complex() {
+          Some(z) => Value::from_complex(z / factor),
+          None => value,
+        },
+      }));
+    }
+    None
+  }
+
+  /// Evaluates a random function, `None` for other functions.
+  fn eval_random_func(&self, name: &str, args: &[Value]) -> Option<Result<Value, FuncEvalError>> {
+    if !RANDOM_FUNCS.contains(&name) {
+      return None;
+    }
+    let mut rng = self.rng.get();
+    // `choose` and `shuffle` take the items or a single list of items
+    let items = match args {
+      [Value::List(items)] => items.as_slice(),
+      args => args,
+    };
+    let value = match (name, args) {
+      ("rand", []) => Ok(self.real(rng.next_f64())),
+      ("randn", []) => Ok(self.real(rng.normal())),
+      ("rand", _) | ("randn", _) => Err(FuncEvalError::NumberArgs(0)),
+      ("randint", [a, b]) => randint(&mut rng, a, b)
+        .map(|n| integer::to_mode_value(n.into(), self.number_mode)),
+      ("randint", _) => Err(FuncEvalError::NumberArgs(2)),
+      (_, []) => Err(FuncEvalError::TooFewArguments),
+      // An empty list, e.g. `choose(factor(1))`, has nothing to choose from
+      ("choose", _) => match rng.below(items.len() as u64) {
+        Some(i) => Ok(items[i as usize].clone()),
+        None => Err(FuncEvalError::TooFewArguments),
+      },
+      _ => {
+        let mut items = items.to_vec();
+        rng.shuffle(&mut items);
+        Ok(Value::List(items))
+      }
+    };
+    self.rng.set(rng);
+    Some(value)
+  }
+
+  /// A computed real number, flagged as approximate in decimal mode.
+  fn real(&self, x: f64) -> Value {
+    match self.number_mode {
+      NumberMode::Decimal => Decimal::approximate(Value::Real(x)),
+      _ => Value::Real(x),
+    }
+  }
+
+  /// Evaluates a function in decimal mode, `None` if it has no exact decimal implementation.
+  fn eval_decimal_func(&self, name: &str, args: &[Value]) -> Option<Decimal> {
+    let func = self.find(|scope| scope.decimal_funcs.get(name))?;
+    let args = args
+      .iter()
+      .map(Decimal::from_value)
+      .collect::<Option<Vec<Decimal>>>()?;
+    let approximate = args.iter().any(|arg| arg.approximate);
+    let decimals: Vec<BigDecimal> = args.into_iter().map(|arg| arg.decimal).collect();
+    func(&decimals, &self.decimal.context()).map(|decimal| Decimal {
+      decimal: decimal.normalized(),
+      approximate,
+    })
+  }
+
+  /// Evaluates a function in fraction mode, `None` if it has no exact rational implementation.
+  fn eval_fraction_func(&self, name: &str, args: &[Value]) -> Option<Fraction> {
+    let func = self.find(|scope| scope.fraction_funcs.get(name))?;
+    let args = args
+      .iter()
+      .map(|arg| Fraction::from_value(arg).map(|f| f.to_rational()))
+      .collect::<Option<Vec<BigRational>>>()?;
+    func(&args).map(Fraction::new)
+  }
+}
+
+/// Applies `func` to the only argument of a fraction builtin.
+fn unary_fraction<F: Fn(&BigRational) -> BigRational>(
+  xs: &[BigRational],
+  func: F,
+) -> Option<BigRational> {
+  match xs {
+    [x] => Some(func(x)),
+    _ => None,
+  }
+}
+
+/// Applies `func` to the only argument of a decimal builtin.
+fn unary_decimal<F: Fn(&BigDecimal) -> BigDecimal>(xs: &[BigDecimal], func: F) -> Option<BigDecimal> {
+  match xs {
+    [x] => Some(func(x)),
+    _ => None,
+  }
+}
+
+/// Returns the argument of a complex builtin, real numbers are promoted.
+fn complex_arg(arg: &Value) -> Result<Complex64, FuncEvalError> {
+  arg.as_complex().ok_or(FuncEvalError::ListArgument)
 }
 
 impl<'a> Default for Context<'a> {
@@ -234,37 +1195,135 @@ impl<'a> Default for Context<'a> {
 
 impl<'a> ContextProvider for Context<'a> {
   fn get_var(&self, name: &str) -> Option<f64> {
-    self.vars.get(name).cloned()
+    self.lookup_value(name).and_then(Value::as_real)
   }
   fn eval_func(&self, name: &str, args: &[f64]) -> Result<f64, FuncEvalError> {
-    self
-      .funcs
-      .get(name)
-      .map_or(Err(FuncEvalError::UnknownFunction), |f| f(args))
+    match self.find(|scope| scope.funcs.get(name)) {
+      Some(overloads) => overloads.get(args.len())?(args),
+      None => Err(FuncEvalError::UnknownFunction),
+    }
+  }
+
+  fn get_value(&self, name: &str) -> Option<Value> {
+    match self.lookup_value(name) {
+      Some(value) => Some(value.clone()),
+      None if self.complex && (name == "i" || name == "j") => Some(Value::Complex(Complex64::i())),
+      None if name == "today" => Some(Value::Date(Date::today())),
+      None if name == "now" => Some(Value::Date(Date::now())),
+      None => self.constant(name).map(Constant::to_value),
+    }
+  }
+
+  fn get_unit(&self, name: &str) -> Option<Unit> {
+    if let Some(unit) = self.find(|scope| scope.units.get(name)) {
+      return Some(unit.clone());
+    }
+    // `30°` is a number of degrees whatever the mode, converted to the unit of the mode
+    match units::angle(name) {
+      Some(radians) => Some(Unit::new(name, radians / self.angle_mode.radians(), Default::default())),
+      None => units::lookup(name),
+    }
+  }
+
+  fn eval_value_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    if let Some(value) = self.eval_random_func(name, args) {
+      return value;
+    }
+    match self.eval_angle_func(name, args) {
+      Some(value) => value,
+      None => self.eval_mode_func(name, args),
+    }
+  }
+
+  fn complex_mode(&self) -> bool {
+    self.complex
+  }
+
+  fn number_mode(&self) -> NumberMode {
+    self.number_mode
+  }
+
+  fn decimal_options(&self) -> DecimalOptions {
+    self.decimal
   }
 }
 
 impl<'a, T: ContextProvider> ContextProvider for &'a T {
   fn get_var(&self, name: &str) -> Option<f64> {
-    (&**self).get_var(name)
+    (**self).get_var(name)
   }
 
   fn eval_func(&self, name: &str, args: &[f64]) -> Result<f64, FuncEvalError> {
-    (&**self).eval_func(name, args)
+    (**self).eval_func(name, args)
+  }
+
+  fn get_value(&self, name: &str) -> Option<Value> {
+    (**self).get_value(name)
+  }
+
+  fn get_unit(&self, name: &str) -> Option<Unit> {
+    (**self).get_unit(name)
+  }
+
+  fn eval_value_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    (**self).eval_value_func(name, args)
+  }
+
+  fn complex_mode(&self) -> bool {
+    (**self).complex_mode()
+  }
+
+  fn number_mode(&self) -> NumberMode {
+    (**self).number_mode()
+  }
+
+  fn decimal_options(&self) -> DecimalOptions {
+    (**self).decimal_options()
   }
 }
 
 impl<'a, T: ContextProvider> ContextProvider for &'a mut T {
   fn get_var(&self, name: &str) -> Option<f64> {
-    (&**self).get_var(name)
+    (**self).get_var(name)
   }
 
   fn eval_func(&self, name: &str, args: &[f64]) -> Result<f64, FuncEvalError> {
-    (&**self).eval_func(name, args)
+    (**self).eval_func(name, args)
+  }
+
+  fn get_value(&self, name: &str) -> Option<Value> {
+    (**self).get_value(name)
+  }
+
+  fn get_unit(&self, name: &str) -> Option<Unit> {
+    (**self).get_unit(name)
+  }
+
+  fn eval_value_func(&self, name: &str, args: &[Value]) -> Result<Value, FuncEvalError> {
+    (**self).eval_value_func(name, args)
+  }
+
+  fn complex_mode(&self) -> bool {
+    (**self).complex_mode()
+  }
+
+  fn number_mode(&self) -> NumberMode {
+    (**self).number_mode()
+  }
+
+  fn decimal_options(&self) -> DecimalOptions {
+    (**self).decimal_options()
   }
 }
 
-type GuardedFunc<'a> = Arc<dyn Fn(&[f64]) -> Result<f64, FuncEvalError> + 'a + Send + Sync>;
+type GuardedFn<'a, T, R> = Arc<dyn Fn(&[T]) -> Result<R, FuncEvalError> + 'a + Send + Sync>;
+pub(super) type GuardedFunc<'a> = GuardedFn<'a, f64, f64>;
+pub(super) type ComplexFunc<'a> = Arc<dyn Fn(Complex64) -> Complex64 + 'a + Send + Sync>;
+pub(super) type ValueFunc<'a> = GuardedFn<'a, Value, Value>;
+pub(super) type DecimalFunc<'a> =
+  Arc<dyn Fn(&[BigDecimal], &bigdecimal::Context) -> Option<BigDecimal> + 'a + Send + Sync>;
+pub(super) type FractionFunc<'a> =
+  Arc<dyn Fn(&[BigRational]) -> Option<BigRational> + 'a + Send + Sync>;
 
 /// Trait for types that can specify the number of required arguments for a function with a
 /// variable number of arguments.
@@ -281,62 +1340,114 @@ type GuardedFunc<'a> = Arc<dyn Fn(&[f64]) -> Result<f64, FuncEvalError> + 'a + S
 /// ctx.funcn("sum", |xs| xs.iter().sum(), ..);
 /// ```
 pub trait ArgGuard {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a>;
+  fn arity(&self) -> Arity;
 }
 
 impl ArgGuard for usize {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a> {
-    Arc::new(move |args: &[f64]| {
-      if args.len() == self {
-        Ok(func(args))
-      } else {
-        Err(FuncEvalError::NumberArgs(1))
-      }
-    })
+  fn arity(&self) -> Arity {
+    Arity { min: *self, max: Some(*self) }
   }
 }
 
 impl ArgGuard for std::ops::RangeFrom<usize> {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a> {
-    Arc::new(move |args: &[f64]| {
-      if args.len() >= self.start {
-        Ok(func(args))
-      } else {
-        Err(FuncEvalError::TooFewArguments)
-      }
-    })
+  fn arity(&self) -> Arity {
+    Arity { min: self.start, max: None }
   }
 }
 
 impl ArgGuard for std::ops::RangeTo<usize> {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a> {
-    Arc::new(move |args: &[f64]| {
-      if args.len() < self.end {
-        Ok(func(args))
-      } else {
-        Err(FuncEvalError::TooManyArguments)
-      }
-    })
+  fn arity(&self) -> Arity {
+    Arity { min: 0, max: Some(self.end.saturating_sub(1)) }
   }
 }
 
 impl ArgGuard for std::ops::Range<usize> {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a> {
-    Arc::new(move |args: &[f64]| {
-      if args.len() >= self.start && args.len() < self.end {
-        Ok(func(args))
-      } else if args.len() < self.start {
-        Err(FuncEvalError::TooFewArguments)
-      } else {
-        Err(FuncEvalError::TooManyArguments)
-      }
-    })
+  fn arity(&self) -> Arity {
+    Arity { min: self.start, max: Some(self.end.saturating_sub(1)) }
   }
 }
 
 impl ArgGuard for std::ops::RangeFull {
-  fn to_arg_guard<'a, F: Fn(&[f64]) -> f64 + 'a + Send + Sync>(self, func: F) -> GuardedFunc<'a> {
-    Arc::new(move |args: &[f64]| Ok(func(args)))
+  fn arity(&self) -> Arity {
+    Arity { min: 0, max: None }
+  }
+}
+
+/// The numbers of arguments accepted by a function, from `min` to `max` included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Arity {
+  pub min: usize,
+  /// `None` for functions taking any number of arguments from `min`, like `sum`.
+  pub max: Option<usize>,
+}
+
+impl Arity {
+  pub fn accepts(&self, n: usize) -> bool {
+    n >= self.min && self.max.is_none_or(|max| n <= max)
+  }
+
+  fn overlaps(&self, other: &Arity) -> bool {
+    let below = |a: &Arity, b: &Arity| a.max.is_some_and(|max| max < b.min);
+    !below(self, other) && !below(other, self)
+  }
+
+  /// The error of a function with only this arity called with `n` arguments.
+  fn error(&self, n: usize) -> FuncEvalError {
+    match self.max {
+      Some(max) if max == self.min => FuncEvalError::NumberArgs(max),
+      _ if n < self.min => FuncEvalError::TooFewArguments,
+      _ => FuncEvalError::TooManyArguments,
+    }
+  }
+}
+
+impl fmt::Display for Arity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match (self.min, self.max) {
+      (min, Some(max)) if min == max => write!(f, "{}", min),
+      (0, Some(max)) => write!(f, "at most {}", max),
+      (min, Some(max)) => write!(f, "{} to {}", min, max),
+      (0, None) => write!(f, "any number of"),
+      (min, None) => write!(f, "at least {}", min),
+    }
+  }
+}
+
+/// The implementations of a function name, each taking a different number of arguments, e.g. `g(x)`
+/// and `g(x, y)`.
+#[derive(Clone)]
+pub(super) struct Overloads<F> {
+  entries: Vec<(Arity, F)>,
+}
+
+impl<F> Default for Overloads<F> {
+  fn default() -> Self {
+    Overloads { entries: vec![] }
+  }
+}
+
+impl<F> Overloads<F> {
+  /// Adds an implementation, it replaces those accepting any of the same numbers of arguments.
+  pub(super) fn insert(&mut self, arity: Arity, func: F) {
+    self.entries.retain(|(other, _)| !other.overlaps(&arity));
+    self.entries.push((arity, func));
+    self.entries.sort_by_key(|&(arity, _)| arity);
+  }
+
+  fn arities(&self) -> impl Iterator<Item = Arity> + '_ {
+    self.entries.iter().map(|&(arity, _)| arity)
+  }
+
+  /// The implementation taking `n` arguments, the error lists the accepted arities if there are
+  /// several.
+  pub(super) fn get(&self, n: usize) -> Result<&F, FuncEvalError> {
+    if let Some((_, func)) = self.entries.iter().find(|(arity, _)| arity.accepts(n)) {
+      return Ok(func);
+    }
+    match self.entries.as_slice() {
+      [(arity, _)] => Err(arity.error(n)),
+      _ => Err(FuncEvalError::Arities(self.arities().collect())),
+    }
   }
 }
 
@@ -357,6 +1468,74 @@ mod tests {
     assert_eq!(ctx.get_var("y"), None);
     assert_eq!(ctx.eval_func("f", &[1.0]), Ok(1.));
     assert_eq!(ctx.eval_func("g", &[1.0, 2.0]), Ok(3.));
+    assert_eq!(ctx.eval_func("g", &[1.0]), Ok(4.));
+  }
+
+  #[test]
+  fn test_overloads() {
+    let mut ctx = Context::empty();
+    ctx.func("g", |x| x).func2("g", |x, y| x * y).funcn("g", |xs| xs.iter().sum(), 4..);
+    assert_eq!(ctx.eval_func("g", &[3.]), Ok(3.));
+    assert_eq!(ctx.eval_func("g", &[3., 4.]), Ok(12.));
+    assert_eq!(ctx.eval_func("g", &[1., 2., 3., 4., 5.]), Ok(15.));
+    let arities = vec![
+      Arity { min: 1, max: Some(1) },
+      Arity { min: 2, max: Some(2) },
+      Arity { min: 4, max: None },
+    ];
+    assert_eq!(ctx.eval_func("g", &[1., 2., 3.]), Err(FuncEvalError::Arities(arities.clone())));
+    assert_eq!(
+      FuncEvalError::Arities(arities).to_string(),
+      "Expected 1, 2 or at least 4 arguments"
+    );
+
+    // An implementation replaces those taking any of the same numbers of arguments
+    ctx.funcn("g", |xs| -xs[0], 2..4);
+    assert_eq!(ctx.eval_func("g", &[3.]), Ok(3.));
+    assert_eq!(ctx.eval_func("g", &[3., 4.]), Ok(-3.));
+    assert_eq!(ctx.eval_func("g", &[3., 4., 5.]), Ok(-3.));
+
+    ctx.funcn("h", |xs| xs[0], 3).funcn("k", |xs| xs[0], 1..3);
+    assert_eq!(ctx.eval_func("h", &[1.]), Err(FuncEvalError::NumberArgs(3)));
+    assert_eq!(ctx.eval_func("k", &[]), Err(FuncEvalError::TooFewArguments));
+    assert_eq!(ctx.eval_func("k", &[1., 2., 3.]), Err(FuncEvalError::TooManyArguments));
+
+    ctx.value_func("g", 3, |args| Ok(args[2].clone()));
+    let reals = |xs: &[f64]| xs.iter().map(|&x| Value::Real(x)).collect::<Vec<Value>>();
+    assert_eq!(ctx.eval_value_func("g", &reals(&[1., 2., 3.])), Ok(Value::Real(3.)));
+    assert_eq!(ctx.eval_value_func("g", &reals(&[3.])), Ok(Value::Real(3.)));
+  }
+
+  #[test]
+  fn test_signatures() {
+    let mut ctx = Context::new();
+    let builtins = &ctx.layers[0];
+    let funcs = builtins.funcs.iter().flat_map(|(n, f)| f.arities().map(move |a| (n, a)));
+    let values = builtins.value_funcs.iter().flat_map(|(n, f)| f.arities().map(move |a| (n, a)));
+    let implemented: Vec<(&String, Arity)> = funcs.chain(values).collect();
+    for (name, arity) in implemented {
+      let documented =
+        builtins.signatures.get(name).is_some_and(|s| s.arities().any(|a| a == arity));
+      assert!(documented, "{}({}) is not documented", name, arity);
+    }
+    for name in builtins.signatures.keys() {
+      let implemented = builtins.defines_func(name);
+      assert!(implemented || RANDOM_FUNCS.contains(&name.as_str()), "Unknown function {}", name);
+    }
+
+    assert_eq!(ctx.signatures("round")[0].usage(), "round(x, digits?)");
+    ctx.funcn("round", |xs| xs[0], 3).funcn("f", |xs| xs[0], 2..);
+    let usages = |ctx: &Context, name| {
+      ctx.signatures(name).iter().map(Signature::usage).collect::<Vec<String>>()
+    };
+    assert_eq!(usages(&ctx, "round"), vec!["round(x, digits?)", "round(x, y, z)"]);
+    assert_eq!(usages(&ctx, "f"), vec!["f(x, y...)"]);
+    assert!(ctx.signatures("g").is_empty());
+    ctx.describe(Signature::new("f", &["a", "b..."], None, "Adds", None));
+    assert_eq!(usages(&ctx, "f"), vec!["f(a, b...)"]);
+    let all = ctx.all_signatures();
+    assert!(all.windows(2).all(|pair| pair[0].name <= pair[1].name));
+    assert!(all.iter().any(|signature| signature.name == "rand"));
   }
 
   #[test]
@@ -366,4 +1545,223 @@ mod tests {
     assert_eq!(ctx.eval_func("logn", &[10., 100.]), Ok(2.));
     assert_eq!(ctx.eval_func("logn", &[100., 10.]), Ok(0.5));
   }
+
+  #[test]
+  fn test_complex_functions() {
+    let mut ctx = Context::new();
+    let i = Value::Complex(Complex64::i());
+    assert!(ctx.eval_value_func("sqrt", &[Value::Real(-1.)]).unwrap().as_real().unwrap().is_nan());
+    assert_eq!(ctx.get_value("i"), None);
+
+    ctx.set_complex_mode(true);
+    assert_eq!(ctx.get_value("i"), Some(i.clone()));
+    assert_eq!(ctx.eval_value_func("sqrt", &[Value::Real(-1.)]), Ok(i.clone()));
+    assert_eq!(ctx.eval_value_func("sqrt", &[Value::Real(4.)]), Ok(Value::Real(2.)));
+    assert_eq!(
+      ctx.eval_value_func("ln", &[Value::Real(-1.)]),
+      Ok(Value::Complex(Complex64::new(0., consts::PI)))
+    );
+    assert_eq!(ctx.eval_value_func("abs", std::slice::from_ref(&i)), Ok(Value::Real(1.)));
+    assert_eq!(ctx.eval_value_func("im", std::slice::from_ref(&i)), Ok(Value::Real(1.)));
+    assert_eq!(ctx.eval_value_func("re", &[Value::Real(3.)]), Ok(Value::Real(3.)));
+    assert_eq!(
+      ctx.eval_value_func("conj", std::slice::from_ref(&i)),
+      Ok(Value::Complex(-Complex64::i()))
+    );
+    assert_eq!(
+      ctx.eval_value_func("floor", std::slice::from_ref(&i)),
+      Err(FuncEvalError::ComplexArgument)
+    );
+
+    ctx.value_var("i", Value::Real(2.));
+    assert_eq!(ctx.get_value("i"), Some(Value::Real(2.)));
+  }
+
+  #[test]
+  fn test_angle_modes() {
+    let mut ctx = Context::new();
+    let eval = |ctx: &Context, name: &str, args: &[f64]| {
+      let args: Vec<Value> = args.iter().map(|&x| Value::Real(x)).collect();
+      ctx.eval_value_func(name, &args).unwrap().as_real().unwrap()
+    };
+    assert_eq!(eval(&ctx, "sin", &[30.]), 30f64.sin());
+    assert_eq!(ctx.get_unit("deg").map(|u| u.factor), Some(consts::PI / 180.));
+    assert_eq!(eval(&ctx, "sin", &[consts::PI]), consts::PI.sin());
+
+    ctx.set_snap(true);
+    assert_eq!(eval(&ctx, "sin", &[consts::PI]), 0.);
+    assert_eq!(eval(&ctx, "cos", &[consts::FRAC_PI_2]), 0.);
+    assert_eq!(eval(&ctx, "sin", &[consts::FRAC_PI_6]), 0.5);
+    assert_eq!(eval(&ctx, "sin", &[1000. * consts::PI]), 0.);
+    assert_eq!(eval(&ctx, "sin", &[1e-20]), 1e-20);
+    // Only rounding errors are snapped, not the results of large arguments
+    assert_eq!(eval(&ctx, "sin", &[1e16]), 1e16f64.sin());
+    assert_eq!(eval(&ctx, "cos", &[1e17]), 1e17f64.cos());
+    assert_eq!(eval(&ctx, "asin", &[1.]), consts::FRAC_PI_2);
+
+    ctx.set_angle_mode(AngleMode::Degrees);
+    assert_eq!(eval(&ctx, "sin", &[30.]), 0.5);
+    assert_eq!(eval(&ctx, "cos", &[-90.]), 0.);
+    assert_eq!(eval(&ctx, "tan", &[405.]), 1.);
+    assert_eq!(eval(&ctx, "asin", &[0.5]), 30.);
+    assert_eq!(eval(&ctx, "atan2", &[1., -1.]), 135.);
+    assert_eq!(eval(&ctx, "sinh", &[1.]), 1f64.sinh());
+    assert_eq!(ctx.get_unit("°").map(|u| u.factor), Some(1.));
+    assert_eq!(ctx.get_unit("rad").map(|u| u.factor), Some(180. / consts::PI));
+
+    ctx.set_angle_mode(AngleMode::Gradians);
+    assert_eq!(eval(&ctx, "cos", &[200.]), -1.);
+    assert_eq!(eval(&ctx, "acos", &[0.]), 100.);
+    assert!((ctx.get_unit("deg").unwrap().factor - 200. / 180.).abs() < 1e-15);
+  }
+
+  #[test]
+  fn test_layers() {
+    let mut ctx = Context::new();
+    ctx.var("x", 1.).funcn("round", |xs| xs.iter().sum(), 3);
+    // The builtins are shared, a definition goes to the layer of the context
+    let builtin = Context::new();
+    assert_eq!(builtin.eval_func("round", &[1., 2., 3.]), Err(FuncEvalError::TooManyArguments));
+    assert_eq!(ctx.eval_func("round", &[1., 2., 3.]), Ok(6.));
+    assert_eq!(ctx.eval_func("round", &[1.4]), Ok(1.));
+
+    ctx.push_layer();
+    ctx.var("x", 2.).var("y", 3.).func("round", |x| x);
+    assert_eq!(ctx.get_var("x"), Some(2.));
+    assert_eq!(ctx.eval_func("round", &[1.4]), Ok(1.4));
+    assert_eq!(ctx.eval_func("round", &[1., 2., 3.]), Ok(6.));
+    ctx.clear();
+    assert_eq!(ctx.get_var("x"), Some(1.));
+    assert_eq!(ctx.get_var("y"), None);
+    assert_eq!(ctx.get_var("pi"), Some(consts::PI));
+  }
+
+  #[test]
+  fn test_formulas() {
+    let mut ctx = Context::new();
+    ctx.var("a", 2.);
+    assert_eq!(ctx.define_formula("b", " a * 2"), Ok(Value::Real(4.)));
+    assert_eq!(ctx.define_formula("c", "b + a"), Ok(Value::Real(6.)));
+    assert_eq!(ctx.formula("b"), Some("a * 2"));
+    assert_eq!(ctx.define_formula("d", "x"), Err(Error::UnknownVariable("x".into())));
+    assert_eq!(ctx.formula("d"), None);
+
+    // The formulas reading a variable are computed again in order
+    ctx.var("a", 5.);
+    let changed = ctx.recompute(&["a"]);
+    assert_eq!(
+      changed,
+      vec![("b".to_string(), Ok(Value::Real(10.))), ("c".to_string(), Ok(Value::Real(15.)))]
+    );
+    assert_eq!(ctx.get_var("c"), Some(15.));
+
+    let cycle = ctx.define_formula("a", "c - 1").unwrap_err();
+    assert_eq!(cycle.to_string(), "Evaluation error: circular formula `a -> c -> b -> a`.");
+    assert_eq!(ctx.check_formula("b", "b + 1"), Err(Error::Circular(vec!["b".into(), "b".into()])));
+
+    // An assignment replaces a formula, and a formula failing leaves its variable undefined
+    ctx.var("b", 1.);
+    assert_eq!(ctx.formula("b"), None);
+    assert_eq!(ctx.recompute(&["b"]), vec![("c".to_string(), Ok(Value::Real(6.)))]);
+    ctx.clear();
+    ctx.var("b", 1.).set_formula("c", "b + a");
+    let unknown = Err(Error::UnknownVariable("a".into()));
+    assert_eq!(ctx.recompute_all(), vec![("c".to_string(), unknown)]);
+    assert_eq!(ctx.get_var("c"), None);
+  }
+
+  #[test]
+  fn test_reserved() {
+    let mut ctx = Context::new();
+    assert_eq!(ctx.reserved("pi"), Some(Reserved::Constant));
+    assert_eq!(ctx.reserved("sqrt"), Some(Reserved::Function));
+    assert_eq!(ctx.reserved("rand"), Some(Reserved::Function));
+    assert_eq!(ctx.reserved("now"), Some(Reserved::Keyword));
+    assert_eq!(ctx.reserved("i"), None);
+    assert_eq!(ctx.reserved("x"), None);
+    ctx.set_complex_mode(true);
+    assert_eq!(ctx.reserved("i"), Some(Reserved::Keyword));
+
+    // The constants come before the variables and are kept by `clear`
+    ctx.var("x", 1.).var("pi", 3.);
+    ctx.define_constant("x", Value::Real(2.));
+    assert_eq!(ctx.reserved("x"), Some(Reserved::Constant));
+    ctx.clear();
+    assert_eq!(ctx.get_var("x"), Some(2.));
+    assert_eq!(ctx.get_var("pi"), Some(consts::PI));
+  }
+
+  #[test]
+  fn test_namespaces() {
+    let mut ctx = Context::new();
+    assert_eq!(ctx.get_value("math.tau"), Some(Value::Real(consts::TAU)));
+    assert_eq!(ctx.get_value("tau"), None);
+    assert_eq!(ctx.get_value("k_B"), None);
+
+    assert!(ctx.use_namespace("math"));
+    assert!(ctx.use_namespace("phys"));
+    assert!(!ctx.use_namespace("chem"));
+    assert_eq!(ctx.get_value("tau"), Some(Value::Real(consts::TAU)));
+    assert_eq!(ctx.get_value("c").map(|c| c.to_string()), Some("299792458 m/s".into()));
+    // Constants and variables come first, then the namespaces in the order they were brought into
+    // scope
+    assert_eq!(ctx.get_value("e"), Some(Value::Real(consts::E)));
+    assert_eq!(ctx.constant("e").map(Constant::qualified_name), Some("math.e".into()));
+    ctx.var("c", 3.);
+    assert_eq!(ctx.get_value("c"), Some(Value::Real(3.)));
+  }
+
+  #[test]
+  fn test_random() {
+    let mut ctx = Context::new();
+    let eval = |ctx: &Context, name: &str, args: &[Value]| ctx.eval_value_func(name, args);
+    let draws = |ctx: &mut Context| {
+      ctx.seed(42);
+      let x = eval(ctx, "rand", &[]).unwrap();
+      let n = eval(ctx, "randint", &[Value::Real(1.), Value::Real(6.)]).unwrap();
+      (x, n)
+    };
+    let (x, n) = draws(&mut ctx);
+    assert_eq!(draws(&mut ctx), (x.clone(), n.clone()));
+    assert!((0. ..1.).contains(&x.as_real().unwrap()));
+    assert!((1. ..=6.).contains(&n.as_real().unwrap()));
+    assert_ne!(eval(&ctx, "rand", &[]).unwrap(), x);
+
+    // The preview does not advance the generator
+    let preview = ctx.preview(|ctx| eval(ctx, "randn", &[]));
+    assert_eq!(eval(&ctx, "randn", &[]), preview);
+
+    let items = vec![Value::Real(1.), Value::Real(2.), Value::Real(3.)];
+    assert!(items.contains(&eval(&ctx, "choose", &items).unwrap()));
+    let list = Value::List(items.clone());
+    assert!(items.contains(&eval(&ctx, "choose", std::slice::from_ref(&list)).unwrap()));
+    let shuffled = match eval(&ctx, "shuffle", &[list]).unwrap() {
+      Value::List(mut xs) => {
+        xs.sort_by(|a, b| a.as_real().partial_cmp(&b.as_real()).unwrap());
+        xs
+      }
+      value => panic!("{}", value),
+    };
+    assert_eq!(shuffled, items);
+
+    assert_eq!(eval(&ctx, "rand", &[Value::Real(1.)]), Err(FuncEvalError::NumberArgs(0)));
+    assert_eq!(eval(&ctx, "choose", &[]), Err(FuncEvalError::TooFewArguments));
+    assert_eq!(
+      eval_value_str_with_context("choose(factor(1))", &ctx),
+      Err(Error::Function("choose".into(), FuncEvalError::TooFewArguments))
+    );
+    assert_eq!(
+      eval(&ctx, "randint", &[Value::Real(1.5), Value::Real(6.)]),
+      Err(FuncEvalError::Domain(DomainError::NotInteger(1.5)))
+    );
+    assert_eq!(
+      eval(&ctx, "randint", &[Value::Real(6.), Value::Real(1.)]),
+      Err(FuncEvalError::Domain(DomainError::OutOfRange(1., 6., f64::INFINITY)))
+    );
+    ctx.set_number_mode(NumberMode::Fraction);
+    assert!(matches!(
+      eval(&ctx, "randint", &[Value::Real(5.), Value::Real(5.)]),
+      Ok(Value::Fraction(f)) if f.to_string() == "5"
+    ));
+  }
 }
@@ -0,0 +1,406 @@
+//! Calendar dates and times, e.g. `today + 90 days`, `date(2026, 12, 25) - today` or
+//! `now in Asia/Tokyo`.
+//!
+//! A date is a wall-clock time in a time zone, the local one unless it was converted. Durations
+//! are times with a unit (`3 h`) or plain numbers of days, months and years are calendar months.
+use super::context::FuncEvalError;
+use super::extra_math::DomainError;
+use super::units::{self, Quantity};
+use super::value::Value;
+use super::{Error, Operation};
+use chrono::{
+  DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+  Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const SECONDS_PER_DAY: f64 = 86400.;
+
+/// A date with an optional time of day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Date {
+  /// The wall-clock date and time, midnight for a plain date.
+  #[serde(rename = "date")]
+  pub datetime: NaiveDateTime,
+  /// Whether the time of day is shown, `false` for `today` and date literals.
+  pub time: bool,
+  /// The IANA time zone of the wall-clock time, `None` for the local time zone.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub zone: Option<String>,
+}
+
+impl Date {
+  pub fn new(datetime: NaiveDateTime, time: bool) -> Date {
+    Date {
+      datetime,
+      time,
+      zone: None,
+    }
+  }
+
+  pub fn today() -> Date {
+    Date::new(Local::now().date_naive().into(), false)
+  }
+
+  /// The current local time, to the second.
+  pub fn now() -> Date {
+    let now = Local::now().naive_local();
+    Date::new(now.with_nanosecond(0).unwrap_or(now), true)
+  }
+
+  pub fn date(&self) -> NaiveDate {
+    self.datetime.date()
+  }
+
+  /// The instant of the wall-clock time in its time zone.
+  fn to_utc(&self) -> Result<DateTime<Utc>, DateError> {
+    let instant = match self.zone {
+      Some(ref zone) => parse_zone(zone)?
+        .from_local_datetime(&self.datetime)
+        .earliest()
+        .map(|d| d.with_timezone(&Utc)),
+      None => Local
+        .from_local_datetime(&self.datetime)
+        .earliest()
+        .map(|d| d.with_timezone(&Utc)),
+    };
+    // Wall-clock times skipped by a DST change are read as UTC
+    Ok(instant.unwrap_or_else(|| Utc.from_utc_datetime(&self.datetime)))
+  }
+
+  /// Shifts the date by a duration, keeping its time zone.
+  fn shift(&self, duration: &Value, sign: i32) -> Result<Date, Error> {
+    let datetime = match *duration {
+      Value::Quantity(ref q) => match calendar_months(q) {
+        Some(months) => add_months(self.datetime, months * sign),
+        None if q.unit.dimension == units::lookup("s").unwrap().dimension => {
+          let millis = (q.to_si() * 1000.).round() * sign as f64;
+          add_millis(self.datetime, millis)
+        }
+        None => {
+          return Err(Error::Unit(units::UnitError::DimensionMismatch(
+            units::lookup("s").unwrap().dimension,
+            q.unit.dimension,
+          )))
+        }
+      },
+      ref days => {
+        let days = days.as_real().ok_or(Error::Date(DateError::NotADuration))?;
+        add_millis(self.datetime, days * SECONDS_PER_DAY * 1000. * sign as f64)
+      }
+    };
+    let datetime = datetime.ok_or(Error::Date(DateError::OutOfRange))?;
+    Ok(Date {
+      datetime,
+      time: self.time || datetime.time() != self.datetime.time(),
+      zone: self.zone.clone(),
+    })
+  }
+}
+
+/// Whole calendar months and years, `1 month` is not always 30.4 days.
+fn calendar_months(q: &Quantity) -> Option<i32> {
+  let months = match q.unit.symbols.as_slice() {
+    [(symbol, 1)] if symbol == "month" => q.value,
+    [(symbol, 1)] if symbol == "yr" => q.value * 12.,
+    _ => return None,
+  };
+  if months.fract() == 0. && months.abs() <= i32::MAX as f64 {
+    Some(months as i32)
+  } else {
+    None
+  }
+}
+
+fn add_months(datetime: NaiveDateTime, months: i32) -> Option<NaiveDateTime> {
+  if months >= 0 {
+    datetime.checked_add_months(Months::new(months as u32))
+  } else {
+    datetime.checked_sub_months(Months::new(months.unsigned_abs()))
+  }
+}
+
+fn add_millis(datetime: NaiveDateTime, millis: f64) -> Option<NaiveDateTime> {
+  if !millis.is_finite() || millis.abs() > i64::MAX as f64 {
+    return None;
+  }
+  datetime.checked_add_signed(Duration::try_milliseconds(millis as i64)?)
+}
+
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match (self.time, self.datetime.second()) {
+      (false, _) => write!(f, "{}", self.datetime.format("%Y-%m-%d"))?,
+      (true, 0) => write!(f, "{}", self.datetime.format("%Y-%m-%d %H:%M"))?,
+      (true, _) => write!(f, "{}", self.datetime.format("%Y-%m-%d %H:%M:%S"))?,
+    }
+    match self.zone {
+      Some(ref zone) => write!(f, " {}", zone),
+      None => Ok(()),
+    }
+  }
+}
+
+/// An error in a computation with dates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateError {
+  UnknownTimeZone(String),
+  /// A date too far away to be represented, e.g. `today + 1e9 yr`.
+  OutOfRange,
+  /// Only durations and numbers of days can be added to a date.
+  NotADuration,
+}
+
+impl fmt::Display for DateError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DateError::UnknownTimeZone(ref zone) => write!(f, "Unknown time zone `{}`", zone),
+      DateError::OutOfRange => write!(f, "The date is out of range"),
+      DateError::NotADuration => write!(f, "Only durations can be added to a date"),
+    }
+  }
+}
+
+impl std::error::Error for DateError {}
+
+fn parse_zone(zone: &str) -> Result<Tz, DateError> {
+  zone
+    .parse::<Tz>()
+    .map_err(|_| DateError::UnknownTimeZone(zone.to_string()))
+}
+
+/// The name of a time zone written after `to`/`in`, which is read as a unit: `Europe/Paris` is
+/// `Europe` divided by `Paris`.
+pub fn zone_name(factors: &[(String, i32)]) -> Option<String> {
+  let (first, rest) = factors.split_first()?;
+  if first.1 != 1 || rest.iter().any(|&(_, power)| power != -1) {
+    return None;
+  }
+  let names: Vec<&str> = factors.iter().map(|(name, _)| name.as_str()).collect();
+  Some(names.join("/"))
+}
+
+/// Converts a date to the wall-clock time of another time zone.
+pub fn convert(date: &Date, zone: &str) -> Result<Value, Error> {
+  let tz = parse_zone(zone).map_err(Error::Date)?;
+  let instant = date.to_utc().map_err(Error::Date)?;
+  Ok(Value::Date(Date {
+    datetime: tz.from_utc_datetime(&instant.naive_utc()).naive_local(),
+    time: true,
+    zone: Some(zone.to_string()),
+  }))
+}
+
+/// Evaluates a binary operation where at least one operand is a date.
+pub fn binary(op: Operation, left: &Value, right: &Value) -> Result<Value, Error> {
+  match (op, left, right) {
+    (Operation::Minus, Value::Date(a), Value::Date(b)) => {
+      let difference = if a.zone == b.zone {
+        a.datetime - b.datetime
+      } else {
+        a.to_utc().map_err(Error::Date)? - b.to_utc().map_err(Error::Date)?
+      };
+      let days = difference.num_milliseconds() as f64 / (SECONDS_PER_DAY * 1000.);
+      units::apply(&Value::Real(days), units::lookup("day").unwrap()).map_err(Error::Unit)
+    }
+    (Operation::Plus, Value::Date(date), duration) | (Operation::Plus, duration, Value::Date(date)) => {
+      date.shift(duration, 1).map(Value::Date)
+    }
+    (Operation::Minus, Value::Date(date), duration) => date.shift(duration, -1).map(Value::Date),
+    (op, _, _) => Err(Error::EvalError(format!(
+      "Binary operation {:?} is not supported on dates",
+      op
+    ))),
+  }
+}
+
+fn date_arg(arg: &Value) -> Result<&Date, FuncEvalError> {
+  match *arg {
+    Value::Date(ref date) => Ok(date),
+    _ => Err(FuncEvalError::NotADate),
+  }
+}
+
+fn integer_arg(arg: &Value) -> Result<i64, FuncEvalError> {
+  let x = arg.as_real().ok_or(FuncEvalError::ComplexArgument)?;
+  if x.fract() != 0. || x.abs() > i32::MAX as f64 {
+    return Err(DomainError::NotInteger(x).into());
+  }
+  Ok(x as i64)
+}
+
+fn in_range(x: i64, min: i64, max: i64) -> Result<i64, FuncEvalError> {
+  if (min..=max).contains(&x) {
+    Ok(x)
+  } else {
+    Err(DomainError::OutOfRange(x as f64, min as f64, max as f64).into())
+  }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+  let first = NaiveDate::from_ymd_opt(year, month, 1).expect("The month is valid");
+  let next = first + Months::new(1);
+  (next - first).num_days() as u32
+}
+
+/// `date(year, month, day, hour = 0, minute = 0, second = 0)`
+pub fn date(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let year = in_range(integer_arg(&args[0])?, -9999, 9999)? as i32;
+  let month = in_range(integer_arg(&args[1])?, 1, 12)? as u32;
+  let day = in_range(integer_arg(&args[2])?, 1, days_in_month(year, month) as i64)? as u32;
+  let mut time = [0; 3];
+  for (t, (arg, max)) in time.iter_mut().zip(args[3..].iter().zip([23, 59, 59])) {
+    *t = in_range(integer_arg(arg)?, 0, max)? as u32;
+  }
+  let datetime = NaiveDate::from_ymd_opt(year, month, day)
+    .and_then(|d| d.and_hms_opt(time[0], time[1], time[2]))
+    .expect("The date is valid");
+  Ok(Value::Date(Date::new(datetime, args.len() > 3)))
+}
+
+/// `weekday(date)`, from 1 for Monday to 7 for Sunday.
+pub fn weekday(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let day = date_arg(&args[0])?.date().weekday();
+  Ok(Value::Integer(day.number_from_monday().into()))
+}
+
+/// `weeknum(date)`, the ISO 8601 week number.
+pub fn weeknum(args: &[Value]) -> Result<Value, FuncEvalError> {
+  Ok(Value::Integer(date_arg(&args[0])?.date().iso_week().week().into()))
+}
+
+/// `eomonth(date, months = 0)`, the last day of the month `months` after the date.
+pub fn eomonth(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let date = date_arg(&args[0])?.date();
+  let months = match args.get(1) {
+    Some(months) => integer_arg(months)? as i32,
+    None => 0,
+  };
+  let first = date.with_day(1).expect("Every month has a first day");
+  let last = add_months(first.into(), months + 1)
+    .and_then(|next| next.date().pred_opt())
+    .ok_or(DomainError::OutOfRange(months as f64, -1e5, 1e5))?;
+  Ok(Value::Date(Date::new(last.into(), false)))
+}
+
+fn is_workday(date: NaiveDate, holidays: &[NaiveDate]) -> bool {
+  !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !holidays.contains(&date)
+}
+
+/// `workdays(start, end)`, the number of days from Monday to Friday between the two dates, both
+/// included, which are not holidays. Negative when `end` is before `start`.
+pub fn workdays(args: &[Value], holidays: &[NaiveDate]) -> Result<Value, FuncEvalError> {
+  let (start, end) = (date_arg(&args[0])?.date(), date_arg(&args[1])?.date());
+  let (from, to, sign) = if start <= end {
+    (start, end, 1)
+  } else {
+    (end, start, -1)
+  };
+  // Whole weeks have 5 weekdays, only the remaining days and the holidays are checked one by one
+  let days = (to - from).num_days() + 1;
+  let mut count = days / 7 * 5;
+  let rest = from + Duration::days(days / 7 * 7);
+  count += rest
+    .iter_days()
+    .take_while(|&d| d <= to)
+    .filter(|&d| is_workday(d, &[]))
+    .count() as i64;
+  let mut holidays: Vec<&NaiveDate> = holidays.iter().filter(|&&d| from <= d && d <= to).collect();
+  holidays.sort();
+  holidays.dedup();
+  count -= holidays.iter().filter(|&&&d| is_workday(d, &[])).count() as i64;
+  Ok(Value::Integer((sign * count).into()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ymd(y: i32, m: u32, d: u32) -> Value {
+    Value::Date(Date::new(NaiveDate::from_ymd_opt(y, m, d).unwrap().into(), false))
+  }
+
+  fn real(x: f64) -> Value {
+    Value::Real(x)
+  }
+
+  fn int(n: i64) -> Value {
+    Value::Integer(n.into())
+  }
+
+  fn quantity(x: f64, unit: &str) -> Value {
+    units::apply(&Value::Real(x), units::lookup(unit).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    let day = ymd(2026, 10, 18);
+    assert_eq!(binary(Operation::Plus, &day, &quantity(90., "day")), Ok(ymd(2027, 1, 16)));
+    assert_eq!(binary(Operation::Plus, &real(1.), &day), Ok(ymd(2026, 10, 19)));
+    assert_eq!(binary(Operation::Minus, &day, &quantity(2., "week")), Ok(ymd(2026, 10, 4)));
+    assert_eq!(
+      binary(Operation::Plus, &ymd(2026, 1, 31), &quantity(1., "month")),
+      Ok(ymd(2026, 2, 28))
+    );
+    assert_eq!(binary(Operation::Plus, &day, &quantity(1., "yr")), Ok(ymd(2027, 10, 18)));
+    assert_eq!(
+      binary(Operation::Minus, &ymd(2026, 12, 25), &day).map(|d| d.to_string()),
+      Ok("68 day".into())
+    );
+    assert_eq!(
+      binary(Operation::Plus, &day, &quantity(36., "h")).map(|d| d.to_string()),
+      Ok("2026-10-19 12:00".into())
+    );
+    assert!(matches!(
+      binary(Operation::Plus, &day, &quantity(1., "m")),
+      Err(Error::Unit(units::UnitError::DimensionMismatch(..)))
+    ));
+    assert!(matches!(binary(Operation::Times, &day, &real(2.)), Err(Error::EvalError(_))));
+  }
+
+  #[test]
+  fn test_time_zones() {
+    let noon = Date {
+      datetime: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap().and_hms_opt(12, 0, 0).unwrap(),
+      time: true,
+      zone: Some("Europe/Paris".into()),
+    };
+    assert_eq!(convert(&noon, "Asia/Tokyo").map(|d| d.to_string()), Ok("2026-07-01 19:00 Asia/Tokyo".into()));
+    assert_eq!(convert(&noon, "UTC").map(|d| d.to_string()), Ok("2026-07-01 10:00 UTC".into()));
+    assert_eq!(
+      convert(&noon, "Mars/Olympus"),
+      Err(Error::Date(DateError::UnknownTimeZone("Mars/Olympus".into())))
+    );
+    assert_eq!(
+      zone_name(&[("America".into(), 1), ("Argentina".into(), -1), ("Salta".into(), -1)]),
+      Some("America/Argentina/Salta".into())
+    );
+    assert_eq!(zone_name(&[("m".into(), 1), ("s".into(), -2)]), None);
+  }
+
+  #[test]
+  fn test_functions() {
+    assert_eq!(date(&[real(2026.), real(12.), real(25.)]), Ok(ymd(2026, 12, 25)));
+    assert_eq!(
+      date(&[real(2026.), real(2.), real(29.)]),
+      Err(FuncEvalError::Domain(DomainError::OutOfRange(29., 1., 28.)))
+    );
+    assert_eq!(weekday(&[ymd(2026, 10, 18)]), Ok(int(7)));
+    assert_eq!(weeknum(&[ymd(2026, 1, 1)]), Ok(int(1)));
+    assert_eq!(weeknum(&[ymd(2027, 1, 1)]), Ok(int(53)));
+    assert_eq!(eomonth(&[ymd(2026, 1, 15)]), Ok(ymd(2026, 1, 31)));
+    assert_eq!(eomonth(&[ymd(2026, 1, 15), real(1.)]), Ok(ymd(2026, 2, 28)));
+    assert_eq!(eomonth(&[ymd(2026, 3, 31), real(-1.)]), Ok(ymd(2026, 2, 28)));
+    assert_eq!(weekday(&[real(1.)]), Err(FuncEvalError::NotADate));
+
+    let holidays = [
+      NaiveDate::from_ymd_opt(2026, 12, 25).unwrap(),
+      NaiveDate::from_ymd_opt(2026, 12, 26).unwrap(),
+    ];
+    assert_eq!(workdays(&[ymd(2026, 10, 19), ymd(2026, 10, 23)], &[]), Ok(int(5)));
+    assert_eq!(workdays(&[ymd(2026, 10, 18), ymd(2026, 11, 18)], &[]), Ok(int(23)));
+    assert_eq!(workdays(&[ymd(2026, 12, 31), ymd(2026, 12, 1)], &holidays), Ok(int(-22)));
+  }
+}
@@ -0,0 +1,243 @@
+//! Arbitrary-precision decimal arithmetic, used when the number mode is `NumberMode::Decimal` so
+//! that `0.1 + 0.2` is exactly `0.3`.
+use super::parsers::Operation;
+use super::value::Value;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+/// Largest supported precision, divisions are never computed with more digits than this.
+pub const MAX_PRECISION: u64 = 100;
+
+/// How results are rounded to the configured precision.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+  Up,
+  Down,
+  Ceiling,
+  Floor,
+  HalfUp,
+  HalfDown,
+  #[default]
+  HalfEven,
+}
+
+impl From<Rounding> for RoundingMode {
+  fn from(rounding: Rounding) -> RoundingMode {
+    match rounding {
+      Rounding::Up => RoundingMode::Up,
+      Rounding::Down => RoundingMode::Down,
+      Rounding::Ceiling => RoundingMode::Ceiling,
+      Rounding::Floor => RoundingMode::Floor,
+      Rounding::HalfUp => RoundingMode::HalfUp,
+      Rounding::HalfDown => RoundingMode::HalfDown,
+      Rounding::HalfEven => RoundingMode::HalfEven,
+    }
+  }
+}
+
+/// Precision (in significant digits) and rounding mode of decimal results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecimalOptions {
+  pub precision: u64,
+  pub rounding: Rounding,
+}
+
+impl Default for DecimalOptions {
+  fn default() -> Self {
+    DecimalOptions {
+      precision: 34,
+      rounding: Rounding::default(),
+    }
+  }
+}
+
+impl DecimalOptions {
+  pub fn context(&self) -> bigdecimal::Context {
+    let precision = self.precision.clamp(1, MAX_PRECISION);
+    bigdecimal::Context::new(
+      NonZeroU64::new(precision).expect("Precision is at least 1"),
+      self.rounding.into(),
+    )
+  }
+}
+
+/// A decimal number.
+///
+/// `approximate` is set once part of the computation had to fall back to f64, e.g. `sin(0.5)`,
+/// so the UI can tell the result apart from an exact decimal one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decimal {
+  #[serde(with = "decimal_repr")]
+  pub decimal: BigDecimal,
+  pub approximate: bool,
+}
+
+impl Decimal {
+  pub fn exact(decimal: BigDecimal) -> Decimal {
+    Decimal {
+      decimal,
+      approximate: false,
+    }
+  }
+
+  /// Converts a number literal from the digits that were typed, none of them is lost to a float.
+  pub fn from_literal(text: &str) -> Option<Decimal> {
+    BigDecimal::from_str(text).ok().map(Decimal::exact)
+  }
+
+  /// Converts any real value to a decimal, floats are only exact when they are integers.
+  pub fn from_value(value: &Value) -> Option<Decimal> {
+    match *value {
+      Value::Decimal(ref d) => Some(d.clone()),
+      Value::Integer(ref n) => Some(Decimal::exact(BigDecimal::from(n.clone()))),
+      Value::Real(x) => float_to_decimal(x).map(|decimal| Decimal {
+        decimal,
+        approximate: x.fract() != 0. || x.abs() > 9007199254740992.,
+      }),
+      _ => None,
+    }
+  }
+
+  /// Wraps the result of an f64 fallback, non-finite results stay floats.
+  pub fn approximate(value: Value) -> Value {
+    match value {
+      Value::Real(x) => match float_to_decimal(x) {
+        Some(decimal) => Value::Decimal(Decimal {
+          decimal,
+          approximate: true,
+        }),
+        None => Value::Real(x),
+      },
+      value => value,
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.decimal.to_f64().unwrap_or(f64::NAN)
+  }
+}
+
+fn float_to_decimal(x: f64) -> Option<BigDecimal> {
+  if x.is_finite() {
+    BigDecimal::from_str(&x.to_string()).ok()
+  } else {
+    None
+  }
+}
+
+/// Evaluates a binary operation exactly (up to the precision), `None` if there is no exact
+/// decimal implementation and the caller has to fall back to f64.
+pub fn binary(op: Operation, left: &Decimal, right: &Decimal, options: &DecimalOptions) -> Option<Decimal> {
+  use super::parsers::Operation::*;
+
+  let ctx = options.context();
+  let (l, r) = (&left.decimal, &right.decimal);
+  let result = match op {
+    Plus => ctx.round_decimal(l + r),
+    Minus => ctx.round_decimal(l - r),
+    Times => ctx.round_decimal(l * r),
+    Div if r.is_zero() => return None,
+    Div => ctx.round_decimal(l / r),
+    Mod if r.is_zero() => return None,
+    Mod => l % r,
+    Pow if r.is_integer() => {
+      let exp = r.to_i64().filter(|exp| exp.abs() <= 1_000_000)?;
+      if exp < 0 && l.is_zero() {
+        return None;
+      }
+      l.powi_with_context(exp, &ctx)
+    }
+    _ => return None,
+  };
+  Some(Decimal {
+    decimal: result.normalized(),
+    approximate: left.approximate || right.approximate,
+  })
+}
+
+/// Exact factorial of a non-negative integer, `None` for everything else.
+pub fn factorial(x: &Decimal) -> Option<Decimal> {
+  if !x.decimal.is_integer() || x.decimal < BigDecimal::zero() {
+    return None;
+  }
+  let n = x.decimal.to_u64().filter(|&n| n <= 10_000)?;
+  let product = (2..=n).fold(BigInt::from(1), |acc, k| acc * k);
+  Some(Decimal {
+    decimal: BigDecimal::from(product),
+    approximate: x.approximate,
+  })
+}
+
+/// Rounds to an integer with the given rounding mode.
+pub fn round(x: &BigDecimal, mode: RoundingMode) -> BigDecimal {
+  x.with_scale_round(0, mode)
+}
+
+mod decimal_repr {
+  use bigdecimal::BigDecimal;
+  use serde::{Deserialize, Deserializer, Serializer};
+  use std::str::FromStr;
+
+  pub fn serialize<S: Serializer>(d: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&d.to_string())
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BigDecimal::from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::parsers::Operation::*;
+  use super::*;
+
+  fn dec(s: &str) -> Decimal {
+    Decimal::exact(BigDecimal::from_str(s).unwrap())
+  }
+
+  #[test]
+  fn test_binary() {
+    let options = DecimalOptions::default();
+    assert_eq!(binary(Plus, &dec("0.1"), &dec("0.2"), &options), Some(dec("0.3")));
+    assert_eq!(binary(Times, &dec("1.10"), &dec("3"), &options), Some(dec("3.3")));
+    assert_eq!(binary(Pow, &dec("1.1"), &dec("2"), &options), Some(dec("1.21")));
+    assert_eq!(binary(Pow, &dec("2"), &dec("-1"), &options), Some(dec("0.5")));
+    assert_eq!(binary(Mod, &dec("10.5"), &dec("3"), &options), Some(dec("1.5")));
+    assert_eq!(binary(Div, &dec("1"), &dec("0"), &options), None);
+    assert_eq!(binary(Pow, &dec("2"), &dec("0.5"), &options), None);
+
+    let third = binary(Div, &dec("1"), &dec("3"), &options).unwrap();
+    assert_eq!(third.decimal.digits(), 34);
+
+    let options = DecimalOptions {
+      precision: 4,
+      rounding: Rounding::Down,
+    };
+    assert_eq!(binary(Div, &dec("2"), &dec("3"), &options), Some(dec("0.6666")));
+  }
+
+  #[test]
+  fn test_conversions() {
+    assert_eq!(Decimal::from_literal("0.1"), Some(dec("0.1")));
+    assert_eq!(
+      Decimal::from_literal("12345678901234567890.1"),
+      Some(dec("12345678901234567890.1"))
+    );
+    assert_eq!(Decimal::from_literal("2.5e3"), Some(dec("2500")));
+    assert_eq!(Decimal::from_value(&Value::Real(3.)), Some(dec("3")));
+    assert!(Decimal::from_value(&Value::Real(0.5)).unwrap().approximate);
+    assert!(matches!(
+      Decimal::approximate(Value::Real(0.5)),
+      Value::Decimal(Decimal { approximate: true, .. })
+    ));
+    assert_eq!(Decimal::approximate(Value::Real(f64::INFINITY)), Value::Real(f64::INFINITY));
+    assert_eq!(factorial(&dec("25")), Some(dec("15511210043330985984000000")));
+    assert_eq!(factorial(&dec("2.5")), None);
+  }
+}
@@ -0,0 +1,320 @@
+//! Probability distributions: density (or mass), cumulative distribution and inverse functions of
+//! the normal, binomial, Poisson, Student's t, chi-squared and exponential distributions.
+//!
+//! Invalid parameters are reported as a `DomainError` instead of returning NaN.
+use super::extra_math::{self, DomainError};
+use std::f64::consts;
+
+fn positive(x: f64) -> Result<f64, DomainError> {
+  if x > 0. && x.is_finite() {
+    Ok(x)
+  } else {
+    Err(DomainError::NotPositive(x))
+  }
+}
+
+fn non_negative(x: f64) -> Result<f64, DomainError> {
+  if x >= 0. {
+    Ok(x)
+  } else {
+    Err(DomainError::Negative(x))
+  }
+}
+
+fn count(x: f64) -> Result<f64, DomainError> {
+  if x.fract() != 0. {
+    Err(DomainError::NotInteger(x))
+  } else {
+    non_negative(x)
+  }
+}
+
+fn probability(p: f64) -> Result<f64, DomainError> {
+  if (0. ..=1.).contains(&p) {
+    Ok(p)
+  } else {
+    Err(DomainError::OutOfRange(p, 0., 1.))
+  }
+}
+
+/// `x * ln(y)`, 0 when `x` is 0 even if `y` is 0.
+fn xlogy(x: f64, y: f64) -> f64 {
+  if x == 0. {
+    0.
+  } else {
+    x * y.ln()
+  }
+}
+
+/// The optional `mu` and `sigma` of `normpdf(x, mu, sigma)`, a standard normal by default.
+fn normal_args(args: &[f64]) -> Result<(f64, f64, f64), DomainError> {
+  let mu = args.get(1).copied().unwrap_or(0.);
+  let sigma = positive(args.get(2).copied().unwrap_or(1.))?;
+  Ok((args[0], mu, sigma))
+}
+
+/// `normpdf(x, mu = 0, sigma = 1)`
+pub fn normpdf(args: &[f64]) -> Result<f64, DomainError> {
+  let (x, mu, sigma) = normal_args(args)?;
+  let z = (x - mu) / sigma;
+  Ok((-z * z / 2.).exp() / (sigma * (2. * consts::PI).sqrt()))
+}
+
+/// `normcdf(x, mu = 0, sigma = 1)`
+pub fn normcdf(args: &[f64]) -> Result<f64, DomainError> {
+  let (x, mu, sigma) = normal_args(args)?;
+  Ok(extra_math::erfc(-(x - mu) / (sigma * consts::SQRT_2)) / 2.)
+}
+
+/// `invnorm(p, mu = 0, sigma = 1)`, the quantile function of the normal distribution.
+pub fn invnorm(args: &[f64]) -> Result<f64, DomainError> {
+  let (p, mu, sigma) = normal_args(args)?;
+  Ok(mu + sigma * standard_normal_quantile(probability(p)?))
+}
+
+/// Acklam's rational approximation refined by one step of Halley's method.
+fn standard_normal_quantile(p: f64) -> f64 {
+  const A: [f64; 6] = [
+    -3.969683028665376e1,
+    2.209460984245205e2,
+    -2.759285104469687e2,
+    1.38357751867269e2,
+    -3.066479806614716e1,
+    2.506628277459239,
+  ];
+  const B: [f64; 5] = [
+    -5.447609879822406e1,
+    1.615858368580409e2,
+    -1.556989798598866e2,
+    6.680131188771972e1,
+    -1.328068155288572e1,
+  ];
+  const C: [f64; 6] = [
+    -7.784894002430293e-3,
+    -3.223964580411365e-1,
+    -2.400758277161838,
+    -2.549732539343734,
+    4.374664141464968,
+    2.938163982698783,
+  ];
+  const D: [f64; 4] = [
+    7.784695709041462e-3,
+    3.224671290700398e-1,
+    2.445134137142996,
+    3.754408661907416,
+  ];
+  const P_LOW: f64 = 0.02425;
+
+  if p == 0. {
+    return f64::NEG_INFINITY;
+  }
+  if p == 1. {
+    return f64::INFINITY;
+  }
+  let poly = |coefs: &[f64], x: f64| coefs.iter().fold(0., |acc, c| acc * x + c);
+  let x = if p < P_LOW {
+    let q = (-2. * p.ln()).sqrt();
+    poly(&C, q) / (poly(&D, q) * q + 1.)
+  } else if p <= 1. - P_LOW {
+    let q = p - 0.5;
+    let r = q * q;
+    poly(&A, r) * q / (poly(&B, r) * r + 1.)
+  } else {
+    let q = (-2. * (1. - p).ln()).sqrt();
+    -poly(&C, q) / (poly(&D, q) * q + 1.)
+  };
+  let e = extra_math::erfc(-x / consts::SQRT_2) / 2. - p;
+  let u = e * (2. * consts::PI).sqrt() * (x * x / 2.).exp();
+  x - u / (1. + x * u / 2.)
+}
+
+fn binomial_args(args: &[f64]) -> Result<(f64, f64, f64), DomainError> {
+  Ok((count(args[0])?, probability(args[1])?, count(args[2])?))
+}
+
+/// `binompdf(n, p, k)`, the probability of exactly `k` successes in `n` trials.
+pub fn binompdf(args: &[f64]) -> Result<f64, DomainError> {
+  let (n, p, k) = binomial_args(args)?;
+  if k > n {
+    return Ok(0.);
+  }
+  let ln_choose =
+    extra_math::lgamma(n + 1.)? - extra_math::lgamma(k + 1.)? - extra_math::lgamma(n - k + 1.)?;
+  Ok((ln_choose + xlogy(k, p) + xlogy(n - k, 1. - p)).exp())
+}
+
+/// `binomcdf(n, p, k)`, the probability of at most `k` successes in `n` trials.
+pub fn binomcdf(args: &[f64]) -> Result<f64, DomainError> {
+  let (n, p, k) = binomial_args(args)?;
+  if k >= n {
+    return Ok(1.);
+  }
+  extra_math::beta_inc(n - k, k + 1., 1. - p)
+}
+
+/// `poissonpdf(lambda, k)`
+pub fn poissonpdf(lambda: f64, k: f64) -> Result<f64, DomainError> {
+  let (lambda, k) = (non_negative(lambda)?, count(k)?);
+  Ok((xlogy(k, lambda) - lambda - extra_math::lgamma(k + 1.)?).exp())
+}
+
+/// `poissoncdf(lambda, k)`, the probability of at most `k` events.
+pub fn poissoncdf(lambda: f64, k: f64) -> Result<f64, DomainError> {
+  let (lambda, k) = (non_negative(lambda)?, count(k)?);
+  extra_math::gamma_q(k + 1., lambda)
+}
+
+/// `tpdf(x, df)`, Student's t distribution with `df` degrees of freedom.
+pub fn tpdf(x: f64, df: f64) -> Result<f64, DomainError> {
+  let df = positive(df)?;
+  let ln_norm = extra_math::lgamma((df + 1.) / 2.)? - extra_math::lgamma(df / 2.)?;
+  Ok((ln_norm - (df + 1.) / 2. * (x * x / df).ln_1p()).exp() / (df * consts::PI).sqrt())
+}
+
+/// `tcdf(x, df)`
+pub fn tcdf(x: f64, df: f64) -> Result<f64, DomainError> {
+  let df = positive(df)?;
+  if x.is_infinite() {
+    return Ok(if x > 0. { 1. } else { 0. });
+  }
+  let tail = extra_math::beta_inc(df / 2., 0.5, df / (df + x * x))? / 2.;
+  Ok(if x > 0. { 1. - tail } else { tail })
+}
+
+/// `invt(p, df)`
+pub fn invt(p: f64, df: f64) -> Result<f64, DomainError> {
+  let (p, df) = (probability(p)?, positive(df)?);
+  invert(|x| tcdf(x, df), p, f64::NEG_INFINITY)
+}
+
+/// `chi2pdf(x, df)`, the chi-squared distribution with `df` degrees of freedom.
+pub fn chi2pdf(x: f64, df: f64) -> Result<f64, DomainError> {
+  let df = positive(df)?;
+  if x < 0. {
+    return Ok(0.);
+  }
+  let k = df / 2.;
+  Ok((xlogy(k - 1., x) - x / 2. - k * consts::LN_2 - extra_math::lgamma(k)?).exp())
+}
+
+/// `chi2cdf(x, df)`
+pub fn chi2cdf(x: f64, df: f64) -> Result<f64, DomainError> {
+  let df = positive(df)?;
+  if x <= 0. {
+    return Ok(0.);
+  }
+  extra_math::gamma_p(df / 2., x / 2.)
+}
+
+/// `invchi2(p, df)`
+pub fn invchi2(p: f64, df: f64) -> Result<f64, DomainError> {
+  let (p, df) = (probability(p)?, positive(df)?);
+  invert(|x| chi2cdf(x, df), p, 0.)
+}
+
+/// `exppdf(x, lambda)`, the exponential distribution with rate `lambda`.
+pub fn exppdf(x: f64, lambda: f64) -> Result<f64, DomainError> {
+  let lambda = positive(lambda)?;
+  Ok(if x < 0. { 0. } else { lambda * (-lambda * x).exp() })
+}
+
+/// `expcdf(x, lambda)`
+pub fn expcdf(x: f64, lambda: f64) -> Result<f64, DomainError> {
+  let lambda = positive(lambda)?;
+  Ok(if x < 0. { 0. } else { -(-lambda * x).exp_m1() })
+}
+
+/// `invexp(p, lambda)`
+pub fn invexp(p: f64, lambda: f64) -> Result<f64, DomainError> {
+  let (p, lambda) = (probability(p)?, positive(lambda)?);
+  Ok(-(-p).ln_1p() / lambda)
+}
+
+/// Finds `x` such that `cdf(x) = p` by bisection, `min` is the lower bound of the support.
+fn invert<F>(cdf: F, p: f64, min: f64) -> Result<f64, DomainError>
+where
+  F: Fn(f64) -> Result<f64, DomainError>,
+{
+  if p == 0. {
+    return Ok(min);
+  }
+  if p == 1. {
+    return Ok(f64::INFINITY);
+  }
+  let (mut lo, mut hi) = (if min.is_finite() { min } else { -1. }, 1.);
+  while cdf(lo)? > p {
+    lo *= 2.;
+  }
+  while cdf(hi)? < p {
+    hi *= 2.;
+  }
+  for _ in 0..200 {
+    let mid = (lo + hi) / 2.;
+    if mid <= lo || mid >= hi {
+      break;
+    }
+    if cdf(mid)? < p {
+      lo = mid;
+    } else {
+      hi = mid;
+    }
+  }
+  Ok((lo + hi) / 2.)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: Result<f64, DomainError>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+      (actual - expected).abs() <= 1e-9 * expected.abs().max(1e-12),
+      "{} != {}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn test_normal() {
+    assert_close(normpdf(&[0.]), 0.3989422804014327);
+    assert_close(normpdf(&[1., 1., 2.]), 0.19947114020071635);
+    assert_close(normcdf(&[1.96]), 0.9750021048517795);
+    assert_close(normcdf(&[110., 100., 15.]), 0.7475074624530771);
+    assert_close(invnorm(&[0.975]), 1.959963984540054);
+    assert_close(invnorm(&[0.001]), -3.090232306167813);
+    assert_close(invnorm(&[0.5, 100., 15.]), 100.);
+    assert_eq!(normcdf(&[0., 0., -1.]), Err(DomainError::NotPositive(-1.)));
+    assert_eq!(invnorm(&[1.5]), Err(DomainError::OutOfRange(1.5, 0., 1.)));
+  }
+
+  #[test]
+  fn test_discrete() {
+    assert_close(binompdf(&[10., 0.5, 3.]), 0.1171875);
+    assert_close(binomcdf(&[10., 0.5, 3.]), 0.171875);
+    assert_close(binompdf(&[5., 0., 0.]), 1.);
+    assert_eq!(binompdf(&[5., 0.5, 6.]), Ok(0.));
+    assert_close(poissonpdf(3., 2.), 0.22404180765538775);
+    assert_close(poissoncdf(3., 2.), 0.42319008112684364);
+    assert_eq!(binomcdf(&[10., -0.1, 3.]), Err(DomainError::OutOfRange(-0.1, 0., 1.)));
+    assert_eq!(binompdf(&[10., 0.5, 2.5]), Err(DomainError::NotInteger(2.5)));
+    assert_eq!(poissonpdf(-1., 2.), Err(DomainError::Negative(-1.)));
+  }
+
+  #[test]
+  fn test_continuous() {
+    assert_close(tpdf(0., 1.), 1. / consts::PI);
+    assert_close(tcdf(2., 5.), 0.9490302605850709);
+    assert_close(tcdf(-2., 5.), 0.05096973941492914);
+    assert_close(invt(0.975, 10.), 2.2281388519649385);
+    assert_close(chi2pdf(2., 2.), 0.18393972058572117);
+    assert_close(chi2cdf(3.84, 1.), 0.9499564787512949);
+    assert_close(invchi2(0.95, 3.), 7.814727903251178);
+    assert_close(exppdf(1., 2.), 0.2706705664732254);
+    assert_close(expcdf(1., 2.), 0.8646647167633873);
+    assert_close(invexp(0.5, 2.), consts::LN_2 / 2.);
+    assert_eq!(tcdf(1., 0.), Err(DomainError::NotPositive(0.)));
+    assert_eq!(invchi2(2., 1.), Err(DomainError::OutOfRange(2., 0., 1.)));
+  }
+}
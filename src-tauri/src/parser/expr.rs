@@ -1,9 +1,19 @@
-use super::context::ContextProvider;
+use super::context::{ContextProvider, NumberMode};
+use super::dates;
+use super::decimal::{self, Decimal};
 use super::extra_math::factorial;
-use super::parsers::{starts_with_assignment, Token};
+use super::extra_math::DomainError;
+use super::integer;
+use super::number_theory::double_factorial;
+use super::parsers::{ends_with_representation, starts_with_assignment, Operation, Token};
+use super::rational::{self, Fraction};
+use super::representation::{Representation, RepresentationError};
 use super::shunting_yard::to_rpn;
 use super::tokenize;
+use super::units;
+use super::value::Value;
 use super::Error;
+use num_complex::Complex64;
 use std::str::FromStr;
 
 /// Representation of a parsed expression.
@@ -25,65 +35,75 @@ use std::str::FromStr;
 pub struct Expr {
     rpn: Vec<Token>,
     assign_to: Option<String>,
+    /// The representation of the result requested with a `to hex`-like suffix.
+    representation: Option<Representation>,
 }
 
 impl Expr {
-    // Evaluates the expression with the given context.
+    // Evaluates the expression with the given context, the result must be a real number.
     pub fn eval_with_context<C: ContextProvider>(&self, ctx: C) -> Result<f64, Error> {
-        use super::parsers::Operation::*;
+        let value = self.eval_value_with_context(ctx)?;
+        value
+            .as_real()
+            .ok_or_else(|| Error::EvalError(format!("The result is not a real number: {}", value)))
+    }
+
+    // Evaluates the expression with the given context.
+    pub fn eval_value_with_context<C: ContextProvider>(&self, ctx: C) -> Result<Value, Error> {
         use super::parsers::Token::*;
 
-        let mut stack = Vec::with_capacity(16);
+        let mut stack: Vec<Value> = Vec::with_capacity(16);
 
         for token in &self.rpn {
             match *token {
                 Var(ref n) => {
-                    if let Some(v) = ctx.get_var(n) {
+                    if let Some(v) = ctx.get_value(n) {
                         stack.push(v);
+                    } else if let Some(unit) = ctx.get_unit(n) {
+                        // A bare unit such as `kg` in `3 * kg` is one of that unit
+                        stack.push(units::apply(&Value::Real(1.), unit).map_err(Error::Unit)?);
                     } else {
                         return Err(Error::UnknownVariable(n.clone()));
                     }
                 }
-                Number(f) => stack.push(f),
+                Number(f, ref text) => stack.push(number_literal(f, text, ctx.number_mode())),
+                Date(datetime, time) => stack.push(Value::Date(dates::Date::new(datetime, time))),
+                Duration(seconds) => stack.push(Value::Quantity(units::Quantity {
+                    value: seconds,
+                    unit: units::Unit::duration(),
+                })),
+                Imaginary(f) => {
+                    if !ctx.complex_mode() {
+                        return Err(Error::EvalError(format!(
+                            "Complex mode is disabled, cannot use `{}i`",
+                            f
+                        )));
+                    }
+                    stack.push(Value::from_complex(Complex64::new(0., f)));
+                }
                 Binary(op) => {
                     let right = stack.pop().unwrap();
                     let left = stack.pop().unwrap();
-                    let r = match op {
-                        Plus => left + right,
-                        Minus => left - right,
-                        Times => left * right,
-                        Div => left / right,
-                        Mod => left % right,
-                        Pow => left.powf(right),
-                        _ => {
-                            return Err(Error::EvalError(format!(
-                                "Unimplemented binary operation: {:?}",
-                                op
-                            )));
-                        }
-                    };
-                    stack.push(r);
+                    stack.push(eval_binary(op, left, right, &ctx)?);
                 }
                 Unary(op) => {
                     let x = stack.pop().unwrap();
-                    let r = match op {
-                        Plus => x,
-                        Minus => -x,
-                        Fact => {
-                            // Check to make sure x has no fractional component (can be converted to int without loss)
-                            match factorial(x) {
-                                Ok(res) => res,
-                                Err(e) => return Err(Error::EvalError(String::from(e))),
-                            }
-                        }
-                        _ => {
-                            return Err(Error::EvalError(format!(
-                                "Unimplemented unary operation: {:?}",
-                                op
-                            )));
-                        }
-                    };
-                    stack.push(r);
+                    stack.push(eval_unary(op, x, &ctx)?);
+                }
+                Unit(ref n, power) => {
+                    let x = stack.pop().unwrap();
+                    let unit = units::compound(&[(n.clone(), power)], |n| ctx.get_unit(n));
+                    stack.push(unit.and_then(|unit| units::apply(&x, unit)).map_err(Error::Unit)?);
+                }
+                Convert(ref factors) => {
+                    let x = stack.pop().unwrap();
+                    // `now in Asia/Tokyo` reads the zone name as a compound unit
+                    if let (Value::Date(ref date), Some(zone)) = (&x, dates::zone_name(factors)) {
+                        stack.push(dates::convert(date, &zone)?);
+                        continue;
+                    }
+                    let unit = units::compound(factors, |n| ctx.get_unit(n));
+                    stack.push(unit.and_then(|unit| units::convert(&x, unit)).map_err(Error::Unit)?);
                 }
                 Func(ref n, Some(i)) => {
                     if stack.len() < i {
@@ -93,7 +113,7 @@ impl Expr {
                             token
                         )));
                     }
-                    match ctx.eval_func(n, &stack[stack.len() - i..]) {
+                    match ctx.eval_value_func(n, &stack[stack.len() - i..]) {
                         Ok(r) => {
                             let nl = stack.len() - i;
                             stack.truncate(nl);
@@ -113,10 +133,192 @@ impl Expr {
                 stack.len()
             )));
         }
+        if let Some(representation) = self.representation {
+            representation.check(&r).map_err(Error::Representation)?;
+        }
         Ok(r)
     }
 }
 
+/// Converts a number literal to the representation of the number mode, the exact modes and the
+/// integers beyond 2^53 read the digits as typed.
+fn number_literal(f: f64, text: &str, number_mode: NumberMode) -> Value {
+    let value = match number_mode {
+        NumberMode::Float => integer::from_literal(text).map(integer::to_value),
+        NumberMode::Decimal => Decimal::from_literal(text).map(Value::Decimal),
+        NumberMode::Fraction => Fraction::from_literal(text).map(Value::Fraction),
+    };
+    value.unwrap_or(Value::Real(f))
+}
+
+fn eval_binary<C: ContextProvider>(
+    op: Operation,
+    left: Value,
+    right: Value,
+    ctx: &C,
+) -> Result<Value, Error> {
+    if matches!(left, Value::Date(_)) || matches!(right, Value::Date(_)) {
+        return dates::binary(op, &left, &right);
+    }
+    if matches!(left, Value::Quantity(_)) || matches!(right, Value::Quantity(_)) {
+        return units::binary(op, &left, &right).map_err(Error::Unit);
+    }
+    if ctx.number_mode() == NumberMode::Decimal {
+        if let (Some(l), Some(r)) = (Decimal::from_value(&left), Decimal::from_value(&right)) {
+            return match decimal::binary(op, &l, &r, &ctx.decimal_options()) {
+                Some(d) => Ok(Value::Decimal(d)),
+                // No exact decimal result (e.g. 2^0.5), fall back to f64 and flag the result
+                None => eval_float_binary(op, left, right, ctx.complex_mode())
+                    .map(Decimal::approximate),
+            };
+        }
+    }
+    if ctx.number_mode() == NumberMode::Float {
+        if let (Some(l), Some(r)) = (integer::from_value(&left), integer::from_value(&right)) {
+            if let Some(n) = integer::binary(op, &l, &r) {
+                return Ok(integer::to_value(n));
+            }
+            // Non-integer results (e.g. 7/2) fall back to f64
+        }
+    }
+    if ctx.number_mode() == NumberMode::Fraction {
+        if let (Some(l), Some(r)) = (Fraction::from_value(&left), Fraction::from_value(&right)) {
+            // A float would be rounded away silently, `(1/3)^1000000` is not 0
+            if op == Operation::Pow && rational::pow_too_large(&l, &r) {
+                return Err(Error::Domain(DomainError::TooLarge(r.to_f64())));
+            }
+            if let Some(f) = rational::binary(op, &l, &r) {
+                return Ok(Value::Fraction(f));
+            }
+            // Irrational results (e.g. 2^(1/2)) fall back to f64
+        }
+    }
+    eval_float_binary(op, left, right, ctx.complex_mode())
+}
+
+fn eval_float_binary(op: Operation, left: Value, right: Value, complex: bool) -> Result<Value, Error> {
+    use super::parsers::Operation::*;
+
+    if let (Some(left), Some(right)) = (left.as_real(), right.as_real()) {
+        let r = match op {
+            Plus => left + right,
+            Minus => left - right,
+            Times => left * right,
+            Div => left / right,
+            Mod => left % right,
+            Pow => {
+                let r = left.powf(right);
+                if complex && r.is_nan() && !left.is_nan() && !right.is_nan() {
+                    // e.g. (-8)^(1/3), use the principal complex root
+                    return Ok(Value::from_complex(
+                        Complex64::new(left, 0.).powc(Complex64::new(right, 0.)),
+                    ));
+                }
+                r
+            }
+            _ => {
+                return Err(Error::EvalError(format!(
+                    "Unimplemented binary operation: {:?}",
+                    op
+                )));
+            }
+        };
+        return Ok(Value::Real(r));
+    }
+
+    let (left, right) = match (left.as_complex(), right.as_complex()) {
+        (Some(left), Some(right)) => (left, right),
+        _ => {
+            return Err(Error::EvalError(format!(
+                "Binary operation {:?} is not supported on lists",
+                op
+            )))
+        }
+    };
+    let r = match op {
+        Plus => left + right,
+        Minus => left - right,
+        Times => left * right,
+        Div => left / right,
+        // Integer powers are computed by repeated multiplication so that i^2 is exactly -1
+        Pow if right.im == 0. && right.re.fract() == 0. && right.re.abs() <= i32::MAX as f64 => {
+            left.powi(right.re as i32)
+        }
+        Pow => left.powc(right),
+        _ => {
+            return Err(Error::EvalError(format!(
+                "Binary operation {:?} is not supported on complex numbers",
+                op
+            )));
+        }
+    };
+    Ok(Value::from_complex(r))
+}
+
+fn eval_unary<C: ContextProvider>(op: Operation, x: Value, ctx: &C) -> Result<Value, Error> {
+    use super::parsers::Operation::*;
+
+    // `n!!` is exact like `n!`, whatever the representation of `n`
+    if op == DoubleFact {
+        if let Some(n) = integer::from_value(&x) {
+            return double_factorial(&n)
+                .map(|n| integer::to_mode_value(n, ctx.number_mode()))
+                .map_err(Error::Domain);
+        }
+        if let Some(real) = x.as_real() {
+            return Err(Error::Domain(DomainError::NotInteger(real)));
+        }
+    }
+    if op == Fact && ctx.number_mode() == NumberMode::Decimal {
+        return match Decimal::from_value(&x).and_then(|d| decimal::factorial(&d)) {
+            Some(d) => Ok(Value::Decimal(d)),
+            None => eval_float_unary(op, x).map(Decimal::approximate),
+        };
+    }
+    if op == Fact && ctx.number_mode() == NumberMode::Float {
+        if let Some(n) = integer::from_value(&x).and_then(|n| integer::factorial(&n)) {
+            return Ok(integer::to_value(n));
+        }
+    }
+    if op == Fact && ctx.number_mode() == NumberMode::Fraction {
+        if let Some(f) = Fraction::from_value(&x).and_then(|f| rational::factorial(&f)) {
+            return Ok(Value::Fraction(f));
+        }
+    }
+    eval_float_unary(op, x)
+}
+
+fn eval_float_unary(op: Operation, x: Value) -> Result<Value, Error> {
+    use super::parsers::Operation::*;
+
+    match (op, x) {
+        (Plus, x) => Ok(x),
+        (Minus, Value::Real(x)) => Ok(Value::Real(-x)),
+        (Minus, Value::Complex(z)) => Ok(Value::Complex(-z)),
+        (Minus, Value::Decimal(d)) => Ok(Value::Decimal(Decimal {
+            decimal: -d.decimal,
+            approximate: d.approximate,
+        })),
+        (Minus, Value::Fraction(f)) => Ok(Value::Fraction(Fraction {
+            numer: -f.numer,
+            ..f
+        })),
+        (Fact, Value::Decimal(d)) => eval_float_unary(op, Value::Real(d.to_f64())),
+        (Minus, Value::Integer(n)) => Ok(Value::Integer(-n)),
+        (Minus, Value::Quantity(q)) => Ok(Value::Quantity(units::Quantity {
+            value: -q.value,
+            ..q
+        })),
+        (Fact, Value::Fraction(f)) => eval_float_unary(op, Value::Real(f.to_f64())),
+        (Fact, Value::Integer(n)) => eval_float_unary(op, Value::Real(integer::to_f64(&n))),
+        (Fact, Value::Real(x)) => factorial(x).map(Value::Real).map_err(Error::Domain),
+        (op, x) => Err(Error::EvalError(format!(
+            "Unary operation {:?} is not supported on {}",
+            op, x
+        ))),
+    }
+}
+
 /// Evaluates a string with the given context.
 ///
 /// No built-ins are defined in this case.
@@ -134,6 +336,21 @@ pub fn eval_str_with_context<S: AsRef<str>, C: ContextProvider>(
     }
 }
 
+/// Evaluates a string with the given context, the result may be complex or a list.
+pub fn eval_value_str_with_context<S: AsRef<str>, C: ContextProvider>(
+    expr: S,
+    ctx: C,
+) -> Result<(Option<String>, Value), Error> {
+    let expr = Expr::from_str(expr.as_ref())?;
+
+    let res = expr.eval_value_with_context(&ctx);
+
+    match res {
+        Ok(r) => Ok((expr.assign_to, r)),
+        Err(e) => Err(e),
+    }
+}
+
 impl FromStr for Expr {
     type Err = Error;
     /// Constructs an expression by parsing a string.
@@ -145,6 +362,14 @@ impl FromStr for Expr {
             },
             Err(_) => (s, None),
         };
+        let (expr, representation) = match ends_with_representation(expr) {
+            Some((expr, representation)) => (expr, Some(representation)),
+            None => (expr, None),
+        };
+        if let (Some((_, first)), Some(second)) = (ends_with_representation(expr), representation) {
+            // `10 to hex to bin` would otherwise fail on an unknown unit `hex`
+            return Err(Error::Representation(RepresentationError::Chained(first, second)));
+        }
         let tokens = tokenize(expr)?;
 
         let rpn = to_rpn(&tokens)?;
@@ -152,13 +377,14 @@ impl FromStr for Expr {
         Ok(Expr {
             rpn: rpn,
             assign_to: var,
+            representation,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::context::builtin;
+    use super::super::context::{builtin, FuncEvalError};
     use super::*;
 
     #[test]
@@ -179,8 +405,23 @@ mod tests {
         );
         assert_eq!(
             eval_str_with_context("-171!", &context),
+            Err(Error::Domain(DomainError::Pole(-171.)))
+        );
+        assert_eq!(
+            eval_str_with_context("-(171!)", &context),
             Ok((None, std::f64::NEG_INFINITY))
         );
+        assert_eq!(
+            eval_str_with_context("0.5! - gamma(1.5)", &context),
+            Ok((None, 0.))
+        );
+        assert_eq!(
+            eval_str_with_context("gamma(-2)", &context),
+            Err(Error::Function(
+                "gamma".into(),
+                FuncEvalError::Domain(DomainError::Pole(-2.))
+            ))
+        );
         assert_eq!(
             eval_str_with_context("150!/148!", &context),
             Ok((None, 22350.))
@@ -217,7 +458,7 @@ mod tests {
 
         assert!(matches!(
             eval_str_with_context("0.5!", &context),
-            Err(Error::EvalError { .. })
+            Ok((None, x)) if (x - std::f64::consts::PI.sqrt() / 2.).abs() < 1e-14
         ));
     }
 
@@ -235,6 +476,263 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rounding() {
+        use super::super::context::Context;
+        let context = builtin();
+        let eval = |s| eval_str_with_context(s, &context).map(|(_, x)| x);
+
+        assert_eq!(eval("round(1.23456, 2)"), Ok(1.23));
+        assert_eq!(eval("round(2.5) + round(-2.5)"), Ok(0.));
+        assert_eq!(eval("round(1234.5, -2)"), Ok(1200.));
+        assert_eq!(eval("roundeven(2.5) + roundeven(3.5)"), Ok(6.));
+        assert_eq!(eval("roundeven(0.125, 2)"), Ok(0.12));
+        assert_eq!(eval("floor(-2.5) + ceil(-2.5)"), Ok(-5.));
+        assert_eq!(eval("floor(2.567, 1)"), Ok(2.5));
+        assert_eq!(eval("ceil(2.512, 1)"), Ok(2.6));
+        assert_eq!(eval("roundup(-2.1)"), Ok(-3.));
+        assert_eq!(eval("rounddown(-2.9) + trunc(2.9)"), Ok(0.));
+        assert_eq!(eval("trunc(1.23456, 3)"), Ok(1.234));
+        assert_eq!(eval("fract(3.75)"), Ok(0.75));
+        assert_eq!(eval("roundto(7.3, 0.25)"), Ok(7.25));
+        assert_eq!(eval("mround(17, 5)"), Ok(15.));
+        assert_eq!(eval("clamp(12, 0, 10)"), Ok(10.));
+        assert_eq!(eval("lerp(10, 20, 0.25)"), Ok(12.5));
+        assert_eq!(eval("hypot(2, 3, 6)"), Ok(7.));
+        assert_eq!(eval("cbrt(-27)"), Ok(-3.));
+        assert_eq!(eval("nthroot(-32, 5)"), Ok(-2.));
+        assert_eq!(eval("log2(1024)"), Ok(10.));
+        assert!(matches!(eval("round(1, 0.5)"), Err(Error::Function(..))));
+        assert!(matches!(eval("round(1, 2, 3)"), Err(Error::Function(..))));
+        assert!(matches!(eval("mround(-7, 2)"), Err(Error::Function(..))));
+        assert!(matches!(eval("nthroot(-16, 4)"), Err(Error::Function(..))));
+
+        let mut context = Context::new();
+        context.set_number_mode(NumberMode::Decimal);
+        let eval = |s, ctx: &Context| {
+            eval_value_str_with_context(s, ctx).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(eval("round(2.675, 2)", &context), Ok("2.68".into()));
+        assert_eq!(eval("roundeven(2.665, 2)", &context), Ok("2.66".into()));
+        assert_eq!(eval("fract(-3.75)", &context), Ok("-0.75".into()));
+        assert_eq!(eval("lerp(0.1, 0.2, 0.5)", &context), Ok("0.15".into()));
+
+        context.set_number_mode(NumberMode::Fraction);
+        assert_eq!(eval("round(1/3, 2)", &context), Ok("33/100".into()));
+        assert_eq!(eval("roundeven(5/2)", &context), Ok("2".into()));
+        assert_eq!(eval("clamp(7/2, 0, 3)", &context), Ok("3".into()));
+    }
+
+    #[test]
+    fn test_complex() {
+        use super::super::context::Context;
+        let mut context = Context::new();
+
+        assert!(eval_value_str_with_context("3+4i", &context).is_err());
+
+        context.set_complex_mode(true);
+        let eval = |s| eval_value_str_with_context(s, &context).map(|(_, v)| v);
+        assert_eq!(eval("sqrt(-4)"), Ok(Value::Complex(Complex64::new(0., 2.))));
+        assert_eq!(eval("i^2"), Ok(Value::Real(-1.)));
+        assert_eq!(eval("(1+2i)*(3-i)"), Ok(Value::Complex(Complex64::new(5., 5.))));
+        assert_eq!(eval("abs(3+4i)"), Ok(Value::Real(5.)));
+        assert_eq!(eval("j*j"), Ok(Value::Real(-1.)));
+        assert_eq!(eval("-(2i)"), Ok(Value::Complex(Complex64::new(0., -2.))));
+        assert_eq!(eval("sqrt(16)"), Ok(Value::Real(4.)));
+        assert_eq!(
+            eval("polar(3+4i)"),
+            Ok(Value::List(vec![Value::Real(5.), Value::Real(4f64.atan2(3.))]))
+        );
+        assert_eq!(
+            eval("rect(2, 0)"),
+            Ok(Value::Real(2.))
+        );
+        match eval("(-8)^(1/3)") {
+            Ok(Value::Complex(z)) => {
+                assert!((z.re - 1.).abs() < 1e-12 && (z.im - 3f64.sqrt()).abs() < 1e-12)
+            }
+            r => panic!("Expected a complex root, got {:?}", r),
+        }
+        assert!(matches!(eval("(2i)!"), Err(Error::EvalError { .. })));
+        assert!(matches!(
+            eval_str_with_context("sqrt(-1)", &context),
+            Err(Error::EvalError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decimal() {
+        use super::super::context::Context;
+        use super::super::decimal::{DecimalOptions, Rounding};
+        let mut context = Context::new();
+        context.set_number_mode(NumberMode::Decimal);
+
+        let eval = |s, ctx: &Context| {
+            eval_value_str_with_context(s, ctx).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(eval("0.1 + 0.2", &context), Ok("0.3".into()));
+        assert_eq!(eval("1.1 * 1.1", &context), Ok("1.21".into()));
+        assert_eq!(eval("(0.1 + 0.2) * 10 - 3", &context), Ok("0".into()));
+        assert_eq!(eval("10 % 0.3", &context), Ok("0.1".into()));
+        assert_eq!(eval("(-0.1)^2", &context), Ok("0.01".into()));
+        assert_eq!(eval("25!", &context), Ok("15511210043330985984000000".into()));
+        assert_eq!(eval("max(0.1, 0.25) + round(2.5)", &context), Ok("3.25".into()));
+        assert_eq!(eval("sqrt(0.0144)", &context), Ok("0.12".into()));
+        assert_eq!(
+            eval("1/3", &context),
+            Ok("0.3333333333333333333333333333333333".into())
+        );
+        assert_eq!(eval("sin(0.5) + 1", &context), Ok(format!("≈{}", 0.5f64.sin() + 1.)));
+        assert_eq!(eval("pi * 2", &context), Ok(format!("≈{}", std::f64::consts::PI * 2.)));
+        assert_eq!(eval("2^0.5", &context), Ok(format!("≈{}", 2f64.sqrt())));
+        assert_eq!(eval("1/0", &context), Ok("inf".into()));
+        assert_eq!(eval_str_with_context("0.1 + 0.2", &context), Ok((None, 0.3)));
+        // Literals keep the digits a float would lose
+        assert_eq!(
+            eval("12345678901234567890.1 + 0", &context),
+            Ok("12345678901234567890.1".into())
+        );
+        assert_eq!(
+            eval("0.1000000000000000000001 - 0.1", &context),
+            Ok("1E-22".into())
+        );
+
+        context.set_decimal_options(DecimalOptions {
+            precision: 5,
+            rounding: Rounding::Up,
+        });
+        assert_eq!(eval("2/3", &context), Ok("0.66667".into()));
+        assert_eq!(eval("1/7", &context), Ok("0.14286".into()));
+    }
+
+    #[test]
+    fn test_big_integers() {
+        let context = builtin();
+        let eval = |s| eval_value_str_with_context(s, &context).map(|(_, v)| v.to_string());
+        assert_eq!(
+            eval("52!"),
+            Ok("80658175170943878571660636856403766975289505440883277824000000000000".into())
+        );
+        assert_eq!(eval("2^64 + 1"), Ok("18446744073709551617".into()));
+        assert_eq!(eval("-(2^64) * 3 - 1"), Ok("-55340232221128654849".into()));
+        assert_eq!(eval("200!/198!"), Ok("39800".into()));
+        assert_eq!(eval("(2^70) % 1000"), Ok("424".into()));
+        assert_eq!(eval("2^53 + 1"), Ok("9007199254740993".into()));
+        assert_eq!(eval("9007199254740993 + 0"), Ok("9007199254740993".into()));
+        assert_eq!(
+            eval("123456789012345678901234567890 + 1"),
+            Ok("123456789012345678901234567891".into())
+        );
+        assert_eq!(eval("12345678901234567890.5"), Ok("12345678901234567000".into()));
+        assert_eq!(eval("2^64 / 3"), Ok(format!("{}", 2f64.powi(64) / 3.)));
+        assert_eq!(eval("2^64 * 0.5"), Ok(format!("{}", 2f64.powi(63))));
+        assert_eq!(eval("2^-2"), Ok("0.25".into()));
+        assert_eq!(eval("7/2"), Ok("3.5".into()));
+        assert_eq!(eval("2^10000000"), Ok("inf".into()));
+        assert_eq!(eval("sqrt(2^100)"), Ok(format!("{}", 2f64.powi(50))));
+        // Rounded once from the exact result, the recursive f64 factorial is off by a few ulps
+        assert_eq!(
+            eval_str_with_context("30!", &context),
+            Ok((None, "265252859812191058636308480000000".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let context = builtin();
+        assert_eq!(eval_str_with_context("median(5, 1, 3, 2)", &context), Ok((None, 2.5)));
+        assert_eq!(eval_str_with_context("stdevp(2, 4, 4, 4, 5, 5, 7, 9)", &context), Ok((None, 2.)));
+        assert_eq!(eval_str_with_context("percentile(25, 1, 2, 3, 4, 5)", &context), Ok((None, 2.)));
+        assert_eq!(eval_str_with_context("sum(1, 2, 3) + sumsq(1, 2)", &context), Ok((None, 11.)));
+        assert_eq!(
+            eval_value_str_with_context("quartiles(1, 2, 3, 4, 5)", &context).map(|(_, v)| v),
+            Ok(Value::List(vec![Value::Real(2.), Value::Real(3.), Value::Real(4.)]))
+        );
+        assert_eq!(
+            eval_str_with_context("var(3)", &context),
+            Err(Error::Function("var".into(), FuncEvalError::TooFewArguments))
+        );
+        assert_eq!(
+            context.eval_func("avg", &[]),
+            Err(FuncEvalError::TooFewArguments)
+        );
+    }
+
+    #[test]
+    fn test_number_theory() {
+        use super::super::context::Context;
+        let mut context = builtin();
+        let eval = |s, ctx: &Context| {
+            eval_value_str_with_context(s, ctx).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(eval("gcd(12, 18) + lcm(4, 6)", &context), Ok("18".into()));
+        assert_eq!(eval("nCr(52, 5)", &context), Ok("2598960".into()));
+        assert_eq!(eval("nPr(30, 15)", &context), Ok("202843204931727360000".into()));
+        assert_eq!(eval("7!! + 2", &context), Ok("107".into()));
+        assert_eq!(eval("3!!^2", &context), Ok("9".into()));
+        assert_eq!(eval("40!!", &context), Ok("2551082656125828464640000".into()));
+        // `n!!` is exact like `n!`, the integers an f64 holds exactly are reals
+        let value = |s| eval_value_str_with_context(s, &context).map(|(_, v)| v);
+        assert_eq!(value("5!!"), Ok(Value::Real(15.)));
+        assert_eq!(value("5!"), Ok(Value::Real(120.)));
+        assert_eq!(value("31!!"), Ok(Value::Integer(191898783962510625u64.into())));
+        assert_eq!(value("(2^60 / 2^55)!!"), Ok(Value::Integer(1371195958099968000u64.into())));
+        assert_eq!(eval("factor(360)", &context), Ok("[2, 2, 2, 3, 3, 5]".into()));
+        assert_eq!(eval("isprime(2^61 - 1)", &context), Ok("1".into()));
+        assert_eq!(eval("modpow(2, 100, 1000000007)", &context), Ok("976371285".into()));
+        assert_eq!(eval("emod(-7, 3) + modinv(3, 11)", &context), Ok("6".into()));
+        assert_eq!(
+            eval("2.5!!", &context),
+            Err(Error::Domain(DomainError::NotInteger(2.5)))
+        );
+        assert_eq!(
+            eval("gcd(4, 2.5)", &context),
+            Err(Error::Function(
+                "gcd".into(),
+                FuncEvalError::Domain(DomainError::NotInteger(2.5))
+            ))
+        );
+
+        context.set_number_mode(NumberMode::Decimal);
+        assert_eq!(eval("nCr(5, 2) / 4", &context), Ok("2.5".into()));
+        assert_eq!(eval("7!!", &context), Ok("105".into()));
+        context.set_number_mode(NumberMode::Fraction);
+        assert_eq!(eval("nCr(5, 2) / 4", &context), Ok("5/2".into()));
+    }
+
+    #[test]
+    fn test_fraction() {
+        use super::super::context::Context;
+        let mut context = Context::new();
+        context.set_number_mode(NumberMode::Fraction);
+
+        let eval = |s, ctx: &Context| {
+            eval_value_str_with_context(s, ctx).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(eval("1/3 + 1/6", &context), Ok("1/2".into()));
+        assert_eq!(eval("1/3 * 3", &context), Ok("1".into()));
+        assert_eq!(eval("0.25 - 1", &context), Ok("-3/4".into()));
+        assert_eq!(eval("(2/3)^-2", &context), Ok("9/4".into()));
+        assert_eq!(
+            eval("(1/3)^1000000", &context),
+            Err(Error::Domain(DomainError::TooLarge(1e6)))
+        );
+        assert_eq!(eval("-(1/2)", &context), Ok("-1/2".into()));
+        assert_eq!(eval("20!/18!", &context), Ok("380".into()));
+        assert_eq!(eval("abs(-7/3) + max(1/2, 1/3)", &context), Ok("17/6".into()));
+        assert_eq!(eval("mixed(7/2)", &context), Ok("3 1/2".into()));
+        assert_eq!(eval("mixed(-7/3)", &context), Ok("-2 1/3".into()));
+        assert_eq!(eval("sqrt(2)", &context), Ok(format!("{}", 2f64.sqrt())));
+        assert_eq!(eval("2^(1/2)", &context), Ok(format!("{}", 2f64.sqrt())));
+        assert_eq!(eval("1/0", &context), Ok("inf".into()));
+        assert_eq!(eval_str_with_context("1/4", &context), Ok((None, 0.25)));
+        assert_eq!(eval("mixed(1.5)", &builtin()), Ok("1.5".into()));
+        assert_eq!(
+            eval("0.1000000000000000000001 - 0.1", &context),
+            Ok("1/10000000000000000000000".into())
+        );
+    }
+
     #[test]
     fn test_eval_func_ctx() {
         use super::super::context::Context;
@@ -267,6 +765,151 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_units() {
+        use super::super::units::{Dimension, Unit, UnitError};
+
+        let ctx = builtin();
+        let eval = |s: &str| eval_value_str_with_context(s, &ctx).map(|(_, v)| v.to_string());
+        let close = |s: &str, expected: f64| {
+            let value = match eval_value_str_with_context(s, &ctx) {
+                Ok((_, Value::Quantity(q))) => q.value,
+                r => panic!("Expected a quantity for {}, got {:?}", s, r),
+            };
+            assert!((value - expected).abs() < 1e-9 * expected.abs(), "{}: {}", s, value);
+        };
+        assert_eq!(eval("5 km + 300 m"), Ok("5.3 km".into()));
+        close("5 km + 300 m in mi", 3.29326731885787);
+        close("60 mph to m/s", 26.8224);
+        assert_eq!(eval("3 kW * 2 h in kWh"), Ok("6 kWh".into()));
+        close("20 degC to degF", 68.);
+        assert_eq!(eval("2 m^2 * 3 m"), Ok("6 m^3".into()));
+        assert_eq!(eval("-(2 kg)"), Ok("-2 kg".into()));
+        assert_eq!(eval("10 kg * 9.81 m/s^2 to N"), Ok("98.10000000000001 N".into()));
+        assert_eq!(eval("1 km / 250 m"), Ok("4".into()));
+        assert_eq!(eval("3 * kg"), Ok("3 kg".into()));
+        assert_eq!(eval("2 kg * phys.g_n in N"), Ok("19.6133 N".into()));
+        assert_eq!(eval("cos(60°) + 2 * sin(30deg)"), Ok("1.5".into()));
+        assert_eq!(eval("sin(100grad)"), Ok("1".into()));
+
+        let length = Dimension([1, 0, 0, 0, 0, 0, 0, 0]);
+        let time = Dimension([0, 0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            Expr::from_str("1 m + 1 s").unwrap().eval_with_context(&ctx),
+            Err(Error::Unit(UnitError::DimensionMismatch(length, time)))
+        );
+        assert_eq!(
+            eval("1 m to s"),
+            Err(Error::Unit(UnitError::DimensionMismatch(length, time)))
+        );
+        assert_eq!(eval("2 parsnip"), Err(Error::Unit(UnitError::UnknownUnit("parsnip".into()))));
+        assert_eq!(
+            eval("sqrt(4 m)"),
+            Err(Error::Function("sqrt".into(), FuncEvalError::UnitArgument))
+        );
+
+        let mut ctx = builtin();
+        ctx.unit("furlong", Unit::define("furlong", &Value::Real(201.168)).unwrap());
+        assert_eq!(eval_str_with_context("2 furlong", &ctx), Ok((None, 402.336)));
+    }
+
+    #[test]
+    fn test_dates() {
+        use super::super::{DateError, ParserError};
+
+        let ctx = builtin();
+        let eval = |s: &str| eval_value_str_with_context(s, &ctx).map(|(_, v)| v.to_string());
+        assert_eq!(eval("2026-10-18 + 90 days"), Ok("2027-01-16".into()));
+        assert_eq!(eval("2026-12-25 - 2026-10-18"), Ok("68 day".into()));
+        assert_eq!(eval("date(2026, 1, 31) + 1 month"), Ok("2026-02-28".into()));
+        assert_eq!(eval("2026-07-01T12:00 + 90 min"), Ok("2026-07-01 13:30".into()));
+        assert_eq!(eval("eomonth(2026-02-10)"), Ok("2026-02-28".into()));
+        assert_eq!(eval("weekday(2026-10-18)"), Ok("7".into()));
+        assert_eq!(eval("workdays(2026-10-19, 2026-10-30)"), Ok("10".into()));
+        assert_eq!(eval("today - today"), Ok("0 day".into()));
+        // The local time zone of the machine is unknown, only the instant is checked
+        assert_eq!(eval("(2026-07-01T12:00 in Asia/Tokyo) - 2026-07-01T12:00"), Ok("0 day".into()));
+        assert!(eval("now in America/New_York").unwrap().ends_with(" America/New_York"));
+        assert_eq!(
+            eval("now in Mars/Olympus"),
+            Err(Error::Date(DateError::UnknownTimeZone("Mars/Olympus".into())))
+        );
+        assert_eq!(
+            eval("2026-02-30 + 1 day"),
+            Err(Error::ParseError(ParserError::InvalidDate("2026-02-30".into())))
+        );
+        assert_eq!(
+            eval("sqrt(today)"),
+            Err(Error::Function("sqrt".into(), FuncEvalError::DateArgument))
+        );
+
+        let mut ctx = builtin();
+        ctx.set_holidays(vec![chrono::NaiveDate::from_ymd_opt(2026, 10, 26).unwrap()]);
+        assert_eq!(
+            eval_str_with_context("workdays(2026-10-19, 2026-10-30)", &ctx),
+            Ok((None, 9.))
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        let ctx = builtin();
+        let eval = |s: &str| eval_value_str_with_context(s, &ctx).map(|(_, v)| v.to_string());
+        assert_eq!(eval("1h30m * 3"), Ok("4:30:00".into()));
+        assert_eq!(eval("8:00 + 7h45m"), Ok("15:45:00".into()));
+        assert_eq!(eval("2:45:10 - 3d 4h"), Ok("-73:14:50".into()));
+        assert_eq!(eval("1h30m in minutes"), Ok("90 min".into()));
+        assert_eq!(eval("1h30m / 45m0s"), Ok("2".into()));
+        assert_eq!(eval("90s"), Ok("90 s".into()));
+        assert_eq!(eval("2026-10-18 + 1h30m"), Ok("2026-10-18 01:30".into()));
+        assert_eq!(eval("3 kW * 1h30m in kWh"), Ok("4.5 kWh".into()));
+    }
+
+    #[test]
+    fn test_representations() {
+
+        let ctx = builtin();
+        let expr = Expr::from_str("a = 255 to hex").unwrap();
+        assert_eq!(expr.assign_to, Some("a".into()));
+        assert_eq!(expr.representation, Some(Representation::Radix(16)));
+        assert_eq!(
+            eval_value_str_with_context("a = 255 to hex", &ctx),
+            Ok((Some("a".into()), Value::Real(255.)))
+        );
+        assert_eq!(
+            eval_value_str_with_context("5/2 to roman", &ctx),
+            Err(Error::Representation(RepresentationError::NotRoman))
+        );
+        assert_eq!(
+            eval_value_str_with_context("5 km to hex", &ctx),
+            Err(Error::Representation(RepresentationError::NotANumber(Representation::Radix(16))))
+        );
+        assert_eq!(
+            eval_value_str_with_context("1 to base 40", &ctx),
+            Err(Error::Representation(RepresentationError::InvalidBase(40)))
+        );
+        assert_eq!(
+            eval_value_str_with_context("10 to hex to bin", &ctx),
+            Err(Error::Representation(RepresentationError::Chained(
+                Representation::Radix(16),
+                Representation::Radix(2)
+            )))
+        );
+        assert_eq!(Expr::from_str("5 km to mi").unwrap().representation, None);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut ctx = builtin();
+        ctx.seed(1);
+        let eval = |s: &str| eval_value_str_with_context(s, &ctx).map(|(_, v)| v);
+        let x = eval("rand()").unwrap().as_real().unwrap();
+        assert!((0. ..1.).contains(&x));
+        assert!(eval("randint(1, 6) + randn() * 0").is_ok());
+        assert_eq!(eval("choose(7, 7)"), Ok(Value::Real(7.)));
+        assert_eq!(eval("shuffle(7)"), Ok(Value::List(vec![Value::Real(7.)])));
+    }
+
     #[test]
     fn test_variable_assignment() {
         assert_eq!(Expr::from_str("a = 2").unwrap().assign_to, Some("a".into()),);
@@ -1,33 +1,398 @@
-// Why the heck does factorial take a float?
-// This is to take advantage of the fact that std::f64::MAX >>> std::u64::MAX
-fn factorial_unsafe(num: f64) -> f64 {
-  if num == 0. || num == 1. {
-    return 1.;
-  } else {
-    return num * factorial_unsafe(num - 1.);
+use std::f64::consts;
+use std::fmt;
+
+/// A math function evaluated outside of its domain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomainError {
+  /// The function has a pole at this point, e.g. `gamma(0)` or `(-1)!`.
+  Pole(f64),
+  /// An integer function was given a number with a fractional part.
+  NotInteger(f64),
+  /// The argument must be zero or more, e.g. `nCr(-1, 2)`.
+  Negative(f64),
+  /// The argument must be greater than zero, e.g. `factor(0)` or `geomean(-1)`.
+  NotPositive(f64),
+  /// The number has no inverse for the modulus, e.g. `modinv(2, 4)`.
+  NoInverse(f64, f64),
+  /// The argument is too large to compute the result, e.g. `factor` above 2^64.
+  TooLarge(f64),
+  /// The argument is outside of `[min, max]`, e.g. `percentile(101, 1, 2)`.
+  OutOfRange(f64, f64, f64),
+  /// An equation has no solution, e.g. `irr` of cash flows that are all positive.
+  NoSolution,
+  DivisionByZero,
+  /// The arguments must have the same sign, e.g. `mround(-7, 2)`.
+  SignMismatch(f64, f64),
+}
+
+impl fmt::Display for DomainError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      DomainError::Pole(x) => write!(f, "Undefined at {} (pole)", x),
+      DomainError::NotInteger(x) => write!(f, "Expected an integer, got {}", x),
+      DomainError::Negative(x) => write!(f, "Expected a non-negative number, got {}", x),
+      DomainError::NotPositive(x) => write!(f, "Expected a positive number, got {}", x),
+      DomainError::NoInverse(a, m) => write!(f, "{} has no inverse modulo {}", a, m),
+      DomainError::TooLarge(x) => write!(f, "{} is too large", x),
+      DomainError::OutOfRange(x, min, max) => {
+        write!(f, "Expected a number between {} and {}, got {}", min, max, x)
+      }
+      DomainError::NoSolution => write!(f, "No solution found"),
+      DomainError::DivisionByZero => write!(f, "Division by zero"),
+      DomainError::SignMismatch(x, y) => write!(f, "{} and {} have different signs", x, y),
+    }
+  }
+}
+
+impl std::error::Error for DomainError {
+  fn description(&self) -> &str {
+    match *self {
+      DomainError::Pole(_) => "pole",
+      DomainError::NotInteger(_) => "non-integer argument",
+      DomainError::Negative(_) => "negative argument",
+      DomainError::NotPositive(_) => "non-positive argument",
+      DomainError::NoInverse(..) => "no modular inverse",
+      DomainError::TooLarge(_) => "argument too large",
+      DomainError::OutOfRange(..) => "argument out of range",
+      DomainError::NoSolution => "no solution",
+      DomainError::DivisionByZero => "division by zero",
+      DomainError::SignMismatch(..) => "arguments with different signs",
+    }
   }
 }
 
-pub fn factorial(num: f64) -> Result<f64, &'static str> {
-  let neg = num < 0.;
-  let num = num.abs();
+fn is_non_positive_integer(x: f64) -> bool {
+  x <= 0. && x.fract() == 0.
+}
+
+/// `x!`, defined as `gamma(x + 1)` for non-integers.
+///
+/// Integers are computed as a product so that small factorials are exact, negative integers are
+/// poles.
+pub fn factorial(num: f64) -> Result<f64, DomainError> {
   if num.fract() != 0. {
-    return Err("Number must be non-negative with no fractional component!");
-  } else if num > 170. {
-    return Ok(if neg {
-      std::f64::NEG_INFINITY
-    } else {
-      std::f64::INFINITY
-    });
+    return gamma(num + 1.);
+  }
+  if num < 0. {
+    return Err(DomainError::Pole(num));
+  }
+  if num > 170. {
+    return Ok(f64::INFINITY);
+  }
+  Ok((2..=num as u64).fold(1., |acc, k| acc * k as f64))
+}
+
+/// Lanczos approximation coefficients (g = 7, n = 9).
+const LANCZOS_G: f64 = 7.;
+const LANCZOS: [f64; 9] = [
+  0.999_999_999_999_809_9,
+  676.520_368_121_885_1,
+  -1_259.139_216_722_402_8,
+  771.323_428_777_653_1,
+  -176.615_029_162_140_6,
+  12.507_343_278_686_905,
+  -0.138_571_095_265_720_12,
+  9.984_369_578_019_572e-6,
+  1.505_632_735_149_311_6e-7,
+];
+
+/// Returns `t` and the Lanczos sum for `x >= 0.5`.
+fn lanczos(x: f64) -> (f64, f64) {
+  let x = x - 1.;
+  let sum = LANCZOS[1..]
+    .iter()
+    .enumerate()
+    .fold(LANCZOS[0], |acc, (i, p)| acc + p / (x + i as f64 + 1.));
+  (x + LANCZOS_G + 0.5, sum)
+}
+
+/// The gamma function, with poles at 0, -1, -2, ...
+pub fn gamma(x: f64) -> Result<f64, DomainError> {
+  if is_non_positive_integer(x) {
+    return Err(DomainError::Pole(x));
+  }
+  if x.fract() == 0. {
+    return factorial(x - 1.);
+  }
+  if x < 0.5 {
+    // Reflection formula
+    return Ok(consts::PI / ((consts::PI * x).sin() * gamma(1. - x)?));
+  }
+  if x > 171.7 {
+    return Ok(f64::INFINITY);
+  }
+  let (t, sum) = lanczos(x);
+  // t^(x - 0.5) is split in two so that it does not overflow before e^-t brings it back
+  let half = t.powf((x - 0.5) / 2.);
+  Ok((2. * consts::PI).sqrt() * half * (half * (-t).exp()) * sum)
+}
+
+/// The natural logarithm of the absolute value of the gamma function.
+pub fn lgamma(x: f64) -> Result<f64, DomainError> {
+  if is_non_positive_integer(x) {
+    return Err(DomainError::Pole(x));
+  }
+  if x < 0.5 {
+    return Ok((consts::PI / (consts::PI * x).sin().abs()).ln() - lgamma(1. - x)?);
+  }
+  let (t, sum) = lanczos(x);
+  Ok(0.5 * (2. * consts::PI).ln() + (x - 0.5) * t.ln() - t + sum.ln())
+}
+
+/// The sign of the gamma function.
+fn gamma_sign(x: f64) -> f64 {
+  if x > 0. || (x.floor() as i64) % 2 == 0 {
+    1.
+  } else {
+    -1.
+  }
+}
+
+/// The beta function `gamma(a) gamma(b) / gamma(a + b)`.
+pub fn beta(a: f64, b: f64) -> Result<f64, DomainError> {
+  if is_non_positive_integer(a) {
+    return Err(DomainError::Pole(a));
+  }
+  if is_non_positive_integer(b) {
+    return Err(DomainError::Pole(b));
+  }
+  if is_non_positive_integer(a + b) {
+    return Ok(0.);
+  }
+  let sign = gamma_sign(a) * gamma_sign(b) * gamma_sign(a + b);
+  Ok(sign * (lgamma(a)? + lgamma(b)? - lgamma(a + b)?).exp())
+}
+
+/// The error function.
+pub fn erf(x: f64) -> f64 {
+  if x.is_nan() {
+    x
+  } else if x.abs() < 1. {
+    erf_series(x)
+  } else {
+    x.signum() * (1. - erfc_fraction(x.abs()))
+  }
+}
+
+/// The complementary error function `1 - erf(x)`, accurate for large `x`.
+pub fn erfc(x: f64) -> f64 {
+  if x.is_nan() {
+    x
+  } else if x.abs() < 1. {
+    1. - erf_series(x)
+  } else if x > 0. {
+    erfc_fraction(x)
   } else {
-    return Ok(factorial_unsafe(num) * (if neg { -1. } else { 1. }));
+    2. - erfc_fraction(-x)
+  }
+}
+
+/// Taylor series of erf, used for |x| < 1 where it converges quickly without cancellation.
+fn erf_series(x: f64) -> f64 {
+  let x2 = x * x;
+  let mut term = x;
+  let mut sum = x;
+  let mut n = 0.;
+  while term.abs() > 1e-17 * sum.abs() {
+    n += 1.;
+    term *= -x2 / n;
+    sum += term / (2. * n + 1.);
   }
+  sum * 2. / consts::PI.sqrt()
+}
+
+/// Continued fraction of erfc for x >= 1, evaluated with the modified Lentz method.
+fn erfc_fraction(x: f64) -> f64 {
+  const TINY: f64 = 1e-300;
+  let mut f = x;
+  let mut c = x;
+  let mut d = 0.;
+  for n in 1..500 {
+    let a = n as f64 / 2.;
+    d = x + a * d;
+    d = if d == 0. { TINY } else { 1. / d };
+    c = x + a / c;
+    if c == 0. {
+      c = TINY;
+    }
+    let delta = c * d;
+    f *= delta;
+    if (delta - 1.).abs() < 1e-16 {
+      break;
+    }
+  }
+  (-x * x).exp() / consts::PI.sqrt() / f
+}
+
+/// The Riemann zeta function, with a pole at 1.
+pub fn zeta(s: f64) -> Result<f64, DomainError> {
+  if s == 1. {
+    return Err(DomainError::Pole(s));
+  }
+  if s == 0. {
+    return Ok(-0.5);
+  }
+  if s < 0. && s % 2. == 0. {
+    // Trivial zeros
+    return Ok(0.);
+  }
+  if s < 0.5 {
+    // Functional equation
+    return Ok(
+      2f64.powf(s)
+        * consts::PI.powf(s - 1.)
+        * (consts::PI * s / 2.).sin()
+        * gamma(1. - s)?
+        * zeta(1. - s)?,
+    );
+  }
+  Ok(eta(s) / (1. - 2f64.powf(1. - s)))
+}
+
+/// Dirichlet eta function for s >= 0.5, using Borwein's acceleration of the alternating series.
+fn eta(s: f64) -> f64 {
+  const N: usize = 30;
+  let mut d = [0.; N + 1];
+  let mut term = 1. / N as f64;
+  let mut sum = term;
+  d[0] = N as f64 * sum;
+  for (i, d_i) in d.iter_mut().enumerate().skip(1) {
+    // (n + i - 1)! 4^i / ((n - i)! (2i)!)
+    term *= (N + i - 1) as f64 * (N - i + 1) as f64 * 4. / ((2 * i - 1) as f64 * (2 * i) as f64);
+    sum += term;
+    *d_i = N as f64 * sum;
+  }
+  let total = (0..N).fold(0., |acc, k| {
+    let sign = if k % 2 == 0 { 1. } else { -1. };
+    acc + sign * (d[k] - d[N]) / ((k + 1) as f64).powf(s)
+  });
+  -total / d[N]
+}
+
+/// The digamma function, the logarithmic derivative of gamma, with poles at 0, -1, -2, ...
+pub fn digamma(x: f64) -> Result<f64, DomainError> {
+  if is_non_positive_integer(x) {
+    return Err(DomainError::Pole(x));
+  }
+  if x < 0. {
+    // Reflection formula
+    return Ok(digamma(1. - x)? - consts::PI / (consts::PI * x).tan());
+  }
+  // Shift x up with psi(x) = psi(x + 1) - 1/x, then use the asymptotic series
+  let mut x = x;
+  let mut result = 0.;
+  while x < 20. {
+    result -= 1. / x;
+    x += 1.;
+  }
+  let x2 = 1. / (x * x);
+  let series = x2
+    * (1. / 12.
+      - x2 * (1. / 120. - x2 * (1. / 252. - x2 * (1. / 240. - x2 * (1. / 132.)))));
+  Ok(result + x.ln() - 0.5 / x - series)
+}
+
+/// Regularized lower incomplete gamma function P(a, x), for a > 0 and x >= 0.
+pub fn gamma_p(a: f64, x: f64) -> Result<f64, DomainError> {
+  if x < a + 1. {
+    gamma_series(a, x)
+  } else {
+    gamma_fraction(a, x).map(|q| 1. - q)
+  }
+}
+
+/// Regularized upper incomplete gamma function Q(a, x) = 1 - P(a, x).
+pub fn gamma_q(a: f64, x: f64) -> Result<f64, DomainError> {
+  if x < a + 1. {
+    gamma_series(a, x).map(|p| 1. - p)
+  } else {
+    gamma_fraction(a, x)
+  }
+}
+
+fn gamma_series(a: f64, x: f64) -> Result<f64, DomainError> {
+  if x == 0. {
+    return Ok(0.);
+  }
+  let (mut term, mut sum, mut n) = (1. / a, 1. / a, a);
+  while term.abs() > sum.abs() * f64::EPSILON {
+    n += 1.;
+    term *= x / n;
+    sum += term;
+  }
+  Ok(sum * (a * x.ln() - x - lgamma(a)?).exp())
+}
+
+fn gamma_fraction(a: f64, x: f64) -> Result<f64, DomainError> {
+  let fraction = lentz(|n| (n * (a - n), x + 2. * n + 1. - a), x + 1. - a);
+  Ok((a * x.ln() - x - lgamma(a)?).exp() / fraction)
+}
+
+/// Regularized incomplete beta function I_x(a, b), for a, b > 0 and x between 0 and 1.
+pub fn beta_inc(a: f64, b: f64, x: f64) -> Result<f64, DomainError> {
+  if x <= 0. {
+    return Ok(0.);
+  }
+  if x >= 1. {
+    return Ok(1.);
+  }
+  // The continued fraction converges quickly below the mean, use the symmetry above it
+  if x > (a + 1.) / (a + b + 2.) {
+    return beta_inc(b, a, 1. - x).map(|i| 1. - i);
+  }
+  let front = (a * x.ln() + b * (1. - x).ln() + lgamma(a + b)? - lgamma(a)? - lgamma(b)?).exp();
+  let fraction = lentz(
+    |n| {
+      let m = (n / 2.).floor();
+      let numer = if n % 2. == 0. {
+        m * (b - m) * x / ((a + 2. * m - 1.) * (a + 2. * m))
+      } else {
+        -(a + m) * (a + b + m) * x / ((a + 2. * m) * (a + 2. * m + 1.))
+      };
+      (numer, 1.)
+    },
+    1.,
+  );
+  Ok(front / (a * fraction))
+}
+
+/// Evaluates `b0 + a1 / (b1 + a2 / (b2 + ...))` with the modified Lentz method, `terms(n)` gives
+/// `(a_n, b_n)`.
+fn lentz<F: Fn(f64) -> (f64, f64)>(terms: F, b0: f64) -> f64 {
+  const TINY: f64 = 1e-300;
+  let mut f = if b0 == 0. { TINY } else { b0 };
+  let (mut c, mut d) = (f, 0.);
+  for n in 1..1000 {
+    let (a, b) = terms(n as f64);
+    d = b + a * d;
+    d = if d == 0. { 1. / TINY } else { 1. / d };
+    c = b + a / c;
+    if c == 0. {
+      c = TINY;
+    }
+    let delta = c * d;
+    f *= delta;
+    if (delta - 1.).abs() < f64::EPSILON {
+      break;
+    }
+  }
+  f
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  fn assert_close(actual: Result<f64, DomainError>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+      (actual - expected).abs() <= 1e-13 * expected.abs().max(1e-300),
+      "{} != {}",
+      actual,
+      expected
+    );
+  }
+
   #[test]
   fn test_factorial() {
     assert_eq!(factorial(0.), Ok(1.));
@@ -36,12 +401,52 @@ mod tests {
     assert_eq!(factorial(3.), Ok(6.));
     assert_eq!(factorial(170.), Ok(7.257415615307994e306));
     assert_eq!(factorial(171.), Ok(std::f64::INFINITY));
-    assert_eq!(factorial(-171.), Ok(std::f64::NEG_INFINITY));
-    assert_eq!(factorial(-3.), Ok(-6.));
+    assert_eq!(factorial(-171.), Err(DomainError::Pole(-171.)));
+    assert_eq!(factorial(-3.), Err(DomainError::Pole(-3.)));
+    assert_close(factorial(0.5), consts::PI.sqrt() / 2.);
+    assert_close(factorial(1.1), 1.0464858468535924);
+    assert_close(factorial(-0.5), consts::PI.sqrt());
+  }
 
-    assert!(
-      matches!(factorial(1.1), Err { .. }),
-      "Shouldn't be able to do factorial on number with fractional component!"
-    );
+  #[test]
+  fn test_gamma() {
+    assert_eq!(gamma(5.), Ok(24.));
+    assert_close(gamma(0.5), consts::PI.sqrt());
+    assert_close(gamma(-0.5), -2. * consts::PI.sqrt());
+    assert_close(gamma(0.1), 9.513507698668732);
+    assert_close(gamma(151.5), 150.5 * gamma(150.5).unwrap());
+    assert_eq!(gamma(0.), Err(DomainError::Pole(0.)));
+    assert_eq!(gamma(-2.), Err(DomainError::Pole(-2.)));
+    assert_close(lgamma(100.), 359.1342053695754);
+    assert_close(lgamma(-0.5), (2. * consts::PI.sqrt()).ln());
+    assert_close(beta(2., 3.), 1. / 12.);
+    assert_close(beta(-0.5, 2.), -4.);
+    assert_eq!(beta(-1., 2.), Err(DomainError::Pole(-1.)));
+  }
+
+  #[test]
+  fn test_erf() {
+    assert_eq!(erf(0.), 0.);
+    assert_close(Ok(erf(0.5)), 0.5204998778130465);
+    assert_close(Ok(erf(1.)), 0.8427007929497149);
+    assert_close(Ok(erf(-2.)), -0.9953222650189527);
+    assert_close(Ok(erfc(2.)), 0.004677734981047266);
+    assert_close(Ok(erfc(5.)), 1.537459794428035e-12);
+    assert_close(Ok(erfc(-1.)), 1.842700792949715);
+  }
+
+  #[test]
+  fn test_zeta() {
+    assert_close(zeta(2.), consts::PI * consts::PI / 6.);
+    assert_close(zeta(3.), 1.2020569031595942);
+    assert_close(zeta(0.5), -1.4603545088095868);
+    assert_close(zeta(-1.), -1. / 12.);
+    assert_eq!(zeta(0.), Ok(-0.5));
+    assert_eq!(zeta(-2.), Ok(0.));
+    assert_eq!(zeta(1.), Err(DomainError::Pole(1.)));
+    assert_close(digamma(1.), -0.5772156649015329);
+    assert_close(digamma(0.5), -1.9635100260214235);
+    assert_close(digamma(-0.5), 0.03648997397857652);
+    assert_eq!(digamma(-1.), Err(DomainError::Pole(-1.)));
   }
 }
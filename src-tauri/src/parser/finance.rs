@@ -0,0 +1,259 @@
+//! Time value of money with spreadsheet semantics: money paid out is negative, money received is
+//! positive, and `type` is 0 for payments at the end of each period or 1 at the beginning.
+use super::context::FuncEvalError;
+use super::extra_math::DomainError;
+use super::value::{Table, Value};
+
+/// Schedules are limited to this many periods (a 100-year loan paid weekly fits).
+const MAX_SCHEDULE_PERIODS: f64 = 10_000.;
+
+fn rate_arg(rate: f64) -> Result<f64, DomainError> {
+  if rate > -1. {
+    Ok(rate)
+  } else {
+    Err(DomainError::OutOfRange(rate, -1., f64::INFINITY))
+  }
+}
+
+/// The optional trailing arguments, 0 when they are missing.
+fn optional(args: &[f64], i: usize) -> f64 {
+  args.get(i).copied().unwrap_or(0.)
+}
+
+/// Any non-zero `type` means payments at the beginning of the periods.
+fn payment_factor(rate: f64, when: f64) -> f64 {
+  if when != 0. {
+    1. + rate
+  } else {
+    1.
+  }
+}
+
+/// `(1 + rate)^nper` and the future value of an annuity of 1, `((1 + rate)^nper - 1) / rate`.
+fn growth(rate: f64, nper: f64) -> (f64, f64) {
+  if rate == 0. {
+    (1., nper)
+  } else {
+    let g = (1. + rate).powf(nper);
+    (g, (g - 1.) / rate)
+  }
+}
+
+/// `fv(rate, nper, pmt, pv = 0, type = 0)`
+pub fn fv(args: &[f64]) -> Result<f64, DomainError> {
+  let (rate, nper, pmt, pv) = (rate_arg(args[0])?, args[1], args[2], optional(args, 3));
+  let (g, annuity) = growth(rate, nper);
+  Ok(-(pv * g + pmt * payment_factor(rate, optional(args, 4)) * annuity))
+}
+
+/// `pv(rate, nper, pmt, fv = 0, type = 0)`
+pub fn pv(args: &[f64]) -> Result<f64, DomainError> {
+  let (rate, nper, pmt, fv) = (rate_arg(args[0])?, args[1], args[2], optional(args, 3));
+  let (g, annuity) = growth(rate, nper);
+  Ok(-(fv + pmt * payment_factor(rate, optional(args, 4)) * annuity) / g)
+}
+
+/// `pmt(rate, nper, pv, fv = 0, type = 0)`
+pub fn pmt(args: &[f64]) -> Result<f64, DomainError> {
+  let (rate, nper, pv, fv) = (rate_arg(args[0])?, args[1], args[2], optional(args, 3));
+  let (g, annuity) = growth(rate, nper);
+  let denominator = payment_factor(rate, optional(args, 4)) * annuity;
+  if denominator == 0. {
+    return Err(DomainError::DivisionByZero);
+  }
+  Ok(-(pv * g + fv) / denominator)
+}
+
+/// `nper(rate, pmt, pv, fv = 0, type = 0)`
+pub fn nper(args: &[f64]) -> Result<f64, DomainError> {
+  let (rate, pmt, pv, fv) = (rate_arg(args[0])?, args[1], args[2], optional(args, 3));
+  let n = if rate == 0. {
+    -(pv + fv) / pmt
+  } else {
+    let payments = pmt * payment_factor(rate, optional(args, 4)) / rate;
+    ((payments - fv) / (payments + pv)).ln() / rate.ln_1p()
+  };
+  if n.is_finite() {
+    Ok(n)
+  } else {
+    Err(DomainError::NoSolution)
+  }
+}
+
+/// `rate(nper, pmt, pv, fv = 0, type = 0, guess = 0.1)`, the interest rate per period.
+pub fn rate(args: &[f64]) -> Result<f64, DomainError> {
+  let (nper, pmt, pv) = (args[0], args[1], args[2]);
+  let (fv, when) = (optional(args, 3), optional(args, 4));
+  let guess = args.get(5).copied().unwrap_or(0.1);
+  solve(
+    |rate| {
+      let (g, annuity) = growth(rate, nper);
+      pv * g + pmt * payment_factor(rate, when) * annuity + fv
+    },
+    guess,
+  )
+}
+
+/// `npv(rate, v1, v2, ...)`, the values are discounted from the end of the first period.
+pub fn npv(args: &[f64]) -> Result<f64, DomainError> {
+  let rate = rate_arg(args[0])?;
+  Ok(present_value(rate, &args[1..]) / (1. + rate))
+}
+
+/// Value of the cash flows at the time of the first one.
+fn present_value(rate: f64, values: &[f64]) -> f64 {
+  values.iter().rev().fold(0., |acc, v| acc / (1. + rate) + v)
+}
+
+/// `irr(v0, v1, ...)`, the rate at which the net present value of the cash flows is 0.
+pub fn irr(values: &[f64]) -> Result<f64, DomainError> {
+  let has_both_signs = values.iter().any(|&v| v > 0.) && values.iter().any(|&v| v < 0.);
+  if !has_both_signs {
+    return Err(DomainError::NoSolution);
+  }
+  solve(|rate| present_value(rate, values), 0.1)
+}
+
+/// Finds a root above -1 of `f` with Newton's method from `guess`, or by bisection of the first
+/// sign change on a grid of rates when Newton's method does not converge.
+fn solve<F: Fn(f64) -> f64>(f: F, guess: f64) -> Result<f64, DomainError> {
+  const TOLERANCE: f64 = 1e-12;
+
+  let mut x = guess;
+  for _ in 0..50 {
+    let y = f(x);
+    let h = 1e-7 * x.abs().max(1e-3);
+    let slope = (f(x + h) - f(x - h)) / (2. * h);
+    let next = x - y / slope;
+    if !next.is_finite() || next <= -1. {
+      break;
+    }
+    if (next - x).abs() <= TOLERANCE * next.abs().max(1.) {
+      return Ok(next);
+    }
+    x = next;
+  }
+
+  const GRID: [f64; 16] = [
+    -0.99, -0.9, -0.5, -0.2, -0.1, -0.01, 0., 0.01, 0.05, 0.1, 0.2, 0.5, 1., 10., 100., 1000.,
+  ];
+  let (lo, hi) = GRID
+    .windows(2)
+    .map(|w| (w[0], w[1]))
+    .find(|&(lo, hi)| f(lo).signum() != f(hi).signum())
+    .ok_or(DomainError::NoSolution)?;
+  let (mut lo, mut hi, lo_sign) = (lo, hi, f(lo).signum());
+  while hi - lo > TOLERANCE * hi.abs().max(1.) {
+    let mid = (lo + hi) / 2.;
+    if f(mid).signum() == lo_sign {
+      lo = mid;
+    } else {
+      hi = mid;
+    }
+  }
+  Ok((lo + hi) / 2.)
+}
+
+/// `amortize(principal, rate, periods)`, the schedule of a loan repaid by equal payments at the
+/// end of each period, as a table of the payment, interest, principal and balance of each period.
+pub fn amortize(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let reals = args
+    .iter()
+    .map(Value::as_real)
+    .collect::<Option<Vec<f64>>>()
+    .ok_or(FuncEvalError::ComplexArgument)?;
+  let (principal, rate, periods) = (reals[0], rate_arg(reals[1])?, reals[2]);
+  if periods.fract() != 0. {
+    return Err(DomainError::NotInteger(periods).into());
+  }
+  if periods < 1. {
+    return Err(DomainError::NotPositive(periods).into());
+  }
+  if periods > MAX_SCHEDULE_PERIODS {
+    return Err(DomainError::TooLarge(periods).into());
+  }
+
+  let payment = -pmt(&[rate, periods, principal])?;
+  let mut balance = principal;
+  let rows = (1..=periods as u32)
+    .map(|period| {
+      let interest = balance * rate;
+      // The last payment clears what rounding errors left of the balance
+      let repaid = if period == periods as u32 {
+        balance
+      } else {
+        payment - interest
+      };
+      balance -= repaid;
+      [period as f64, interest + repaid, interest, repaid, balance]
+        .iter()
+        .map(|&x| Value::Real(x))
+        .collect()
+    })
+    .collect();
+  Ok(Value::Table(Table {
+    columns: ["Period", "Payment", "Interest", "Principal", "Balance"]
+      .iter()
+      .map(|&c| c.to_string())
+      .collect(),
+    rows,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: Result<f64, DomainError>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+      (actual - expected).abs() <= 1e-9 * expected.abs().max(1.),
+      "{} != {}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn test_annuities() {
+    // A 30-year mortgage of 200000 at 6% a year, paid monthly
+    assert_close(pmt(&[0.005, 360., 200000.]), -1199.1010503055138);
+    assert_close(pmt(&[0.005, 360., 200000., 0., 1.]), -1193.1353734383221);
+    assert_close(pmt(&[0., 10., 1000.]), -100.);
+    assert_close(pv(&[0.005, 360., -1199.1010503055138]), 200000.);
+    assert_close(fv(&[0.05, 10., -100.]), 1257.789253554883);
+    assert_close(fv(&[0.05, 10., -100., -1000., 1.]), 2949.57334301007);
+    assert_close(nper(&[0.005, -1199.1010503055138, 200000.]), 360.);
+    assert_close(rate(&[360., -1199.1010503055138, 200000.]), 0.005);
+    assert_close(rate(&[10., -100., 0., 1257.789253554883]), 0.05);
+    assert_eq!(nper(&[0.1, -10., 1000.]), Err(DomainError::NoSolution));
+    assert_eq!(pmt(&[-2., 10., 1000.]), Err(DomainError::OutOfRange(-2., -1., f64::INFINITY)));
+  }
+
+  #[test]
+  fn test_cash_flows() {
+    assert_close(npv(&[0.1, -10000., 3000., 4200., 6800.]), 1188.4434123352216);
+    assert_close(irr(&[-70000., 12000., 15000., 18000., 21000., 26000.]), 0.08663094803653149);
+    assert_close(irr(&[-100., 110.]), 0.1);
+    assert_close(irr(&[-100., 230., -132.]), 0.1);
+    assert_eq!(irr(&[100., 10.]), Err(DomainError::NoSolution));
+  }
+
+  #[test]
+  fn test_amortize() {
+    let args = [Value::Real(1000.), Value::Real(0.01), Value::Real(12.)];
+    let table = match amortize(&args) {
+      Ok(Value::Table(table)) => table,
+      result => panic!("Expected a table, got {:?}", result),
+    };
+    assert_eq!(table.columns, vec!["Period", "Payment", "Interest", "Principal", "Balance"]);
+    assert_eq!(table.rows.len(), 12);
+    assert_close(table.rows[0][1].as_real().ok_or(DomainError::NoSolution), 88.84878867834166);
+    assert_eq!(table.rows[0][2], Value::Real(10.));
+    assert_eq!(table.rows[11][4], Value::Real(0.));
+    assert_eq!(
+      amortize(&[Value::Real(1000.), Value::Real(0.01), Value::Real(0.5)]),
+      Err(FuncEvalError::Domain(DomainError::NotInteger(0.5)))
+    );
+  }
+}
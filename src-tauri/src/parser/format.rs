@@ -0,0 +1,378 @@
+//! Formatting of results for display: notation, decimal places or significant figures, thousands
+//! separators and the decimal mark, so that the history and the live preview agree.
+use super::representation::Representation;
+use super::value::{Table, Value};
+use num_complex::Complex64;
+use serde::{Deserialize, Serialize};
+
+/// How real numbers are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Notation {
+  /// Positional, switching to scientific for very large or small numbers like JavaScript does.
+  #[default]
+  Auto,
+  /// Always positional, `1e21` is written out with all its zeros.
+  Fixed,
+  /// One digit before the decimal mark, `1.5e3`.
+  Scientific,
+  /// An exponent multiple of 3, `15e3`.
+  Engineering,
+}
+
+/// The display settings of results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+  pub notation: Notation,
+  /// Number of digits after the decimal mark, padded with zeros.
+  pub decimal_places: Option<usize>,
+  /// Number of significant digits, takes precedence over the decimal places outside of fixed
+  /// notation.
+  pub significant_figures: Option<usize>,
+  /// Separator of the groups of 3 digits of the integer part, e.g. `,` or a thin space.
+  pub thousands_separator: Option<char>,
+  pub decimal_mark: char,
+  /// Whether float noise is rounded away, `0.1 + 0.2` is shown as 0.3.
+  pub snap: bool,
+  /// Number of digits after the point of `to hex`-like representations of non-integers.
+  pub radix_places: usize,
+}
+
+impl Default for FormatOptions {
+  fn default() -> Self {
+    FormatOptions {
+      notation: Notation::default(),
+      decimal_places: None,
+      significant_figures: None,
+      thousands_separator: None,
+      decimal_mark: '.',
+      snap: true,
+      radix_places: 12,
+    }
+  }
+}
+
+/// Significant digits kept when snapping, the last 2 digits of an f64 are mostly noise.
+const SNAP_DIGITS: usize = 15;
+
+/// Formats a value with the options, values without numbers such as dates keep their display.
+pub fn format_value(value: &Value, options: &FormatOptions) -> String {
+  match *value {
+    Value::Real(x) => format_real(x, options),
+    Value::Complex(z) => format_complex(z, options),
+    Value::Decimal(_) | Value::Fraction(_) | Value::Integer(_) => localize(&value.to_string(), options),
+    Value::List(ref xs) => {
+      // A comma decimal mark would be ambiguous with the usual list separator
+      let separator = if options.decimal_mark == ',' { "; " } else { ", " };
+      let items: Vec<String> = xs.iter().map(|x| format_value(x, options)).collect();
+      format!("[{}]", items.join(separator))
+    }
+    Value::Table(Table { ref columns, ref rows }) => {
+      let mut lines = vec![columns.join(" | ")];
+      for row in rows.iter() {
+        let cells: Vec<String> = row.iter().map(|x| format_value(x, options)).collect();
+        lines.push(cells.join(" | "));
+      }
+      lines.join("\n")
+    }
+    Value::Quantity(ref q) => q.format_with(&format_real(q.value, options)),
+    Value::Date(ref d) => d.to_string(),
+  }
+}
+
+/// Formats a value in the representation requested with a `to hex`-like suffix, if any.
+pub fn format_represented(
+  value: &Value,
+  representation: Option<Representation>,
+  options: &FormatOptions,
+) -> String {
+  let representation = match representation {
+    Some(representation) => representation,
+    None => return format_value(value, options),
+  };
+  if representation == Representation::Scientific {
+    let options = FormatOptions { notation: Notation::Scientific, ..options.clone() };
+    return format_value(value, &options);
+  }
+  match representation.render(value, options.radix_places) {
+    Ok(s) => s.replace('.', &options.decimal_mark.to_string()),
+    Err(_) => format_value(value, options),
+  }
+}
+
+fn format_complex(z: Complex64, options: &FormatOptions) -> String {
+  let (mut re, mut im) = (z.re, z.im);
+  if options.snap {
+    // `exp(i pi)` is -1 rather than -1+1.2246e-16i
+    let noise = SNAP_NOISE * re.abs().max(im.abs());
+    if re.abs() <= noise {
+      re = 0.;
+    }
+    if im.abs() <= noise {
+      im = 0.;
+    }
+  }
+  let imaginary = format_real(im.abs(), options);
+  match (re == 0., im == 0.) {
+    (_, true) => format_real(re, options),
+    (true, false) if im < 0. => format!("-{}i", imaginary),
+    (true, false) => format!("{}i", imaginary),
+    (false, false) => {
+      let sign = if im < 0. { '-' } else { '+' };
+      format!("{}{}{}i", format_real(re, options), sign, imaginary)
+    }
+  }
+}
+
+/// Relative size below which a part of a complex number is noise.
+const SNAP_NOISE: f64 = 4. * f64::EPSILON;
+
+/// Formats a real number.
+pub fn format_real(x: f64, options: &FormatOptions) -> String {
+  if !x.is_finite() {
+    return x.to_string();
+  }
+  let x = if options.snap {
+    format!("{:.*e}", SNAP_DIGITS - 1, x).parse().unwrap_or(x)
+  } else {
+    x
+  };
+  let (digits, exponent) = decompose(x, options.significant_figures);
+  let notation = match options.notation {
+    Notation::Auto => {
+      let precise = options.significant_figures.is_some_and(|n| exponent >= n as i32);
+      if exponent >= 21 || exponent <= -7 || precise {
+        Notation::Scientific
+      } else {
+        Notation::Fixed
+      }
+    }
+    notation => notation,
+  };
+  let places = options.decimal_places;
+  let (integer, fraction, exponent) = match notation {
+    Notation::Fixed if options.significant_figures.is_none() && places.is_some() => {
+      let fixed = format!("{:.*}", places.unwrap_or(0), x.abs());
+      let (integer, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+      (integer.to_string(), fraction.to_string(), None)
+    }
+    Notation::Fixed | Notation::Auto => {
+      let (integer, fraction) = place(&digits, exponent + 1, places);
+      (integer, fraction, None)
+    }
+    Notation::Scientific => {
+      let digits = match (options.significant_figures, places) {
+        (None, Some(places)) => decompose(x, Some(places + 1)).0,
+        _ => digits,
+      };
+      let (integer, fraction) = place(&digits, 1, places);
+      (integer, fraction, Some(exponent))
+    }
+    Notation::Engineering => {
+      let (digits, exponent) = match (options.significant_figures, places) {
+        (None, Some(places)) => {
+          let whole = exponent.rem_euclid(3) as usize + 1;
+          decompose(x, Some(whole + places))
+        }
+        _ => (digits, exponent),
+      };
+      let shift = exponent.rem_euclid(3);
+      let (integer, fraction) = place(&digits, shift + 1, places);
+      (integer, fraction, Some(exponent - shift))
+    }
+  };
+  let zero = integer.chars().chain(fraction.chars()).all(|c| c == '0');
+  let mut s = String::new();
+  if x < 0. && !zero {
+    s.push('-');
+  }
+  s.push_str(&group(&integer, options.thousands_separator));
+  if !fraction.is_empty() {
+    s.push(options.decimal_mark);
+    s.push_str(&fraction);
+  }
+  if let Some(exponent) = exponent {
+    s.push_str(&format!("e{}", exponent));
+  }
+  s
+}
+
+/// The significant digits of `|x|` and the exponent of the first one, `1234` is `("1234", 3)`.
+fn decompose(x: f64, significant_figures: Option<usize>) -> (String, i32) {
+  let s = match significant_figures {
+    Some(n) => format!("{:.*e}", n.max(1) - 1, x.abs()),
+    None => format!("{:e}", x.abs()),
+  };
+  let (mantissa, exponent) = s.split_once('e').expect("Exponent notation has an exponent");
+  (mantissa.replace('.', ""), exponent.parse().expect("Exponents are integers"))
+}
+
+/// Splits digits into an integer and a fractional part with `point` digits before the decimal
+/// mark, the fractional part is padded or cut to the decimal places if any.
+fn place(digits: &str, point: i32, places: Option<usize>) -> (String, String) {
+  let (integer, mut fraction) = if point <= 0 {
+    ("0".to_string(), "0".repeat(point.unsigned_abs() as usize) + digits)
+  } else if point as usize >= digits.len() {
+    (digits.to_string() + &"0".repeat(point as usize - digits.len()), String::new())
+  } else {
+    let (integer, fraction) = digits.split_at(point as usize);
+    (integer.to_string(), fraction.to_string())
+  };
+  match places {
+    Some(places) if fraction.len() < places => fraction.push_str(&"0".repeat(places - fraction.len())),
+    Some(places) => fraction.truncate(places),
+    None => {}
+  }
+  (integer, fraction)
+}
+
+/// Inserts the separator between groups of 3 digits.
+fn group(integer: &str, separator: Option<char>) -> String {
+  let separator = match separator {
+    Some(separator) => separator,
+    None => return integer.to_string(),
+  };
+  let offset = integer.len() % 3;
+  let mut s = String::new();
+  for (i, c) in integer.chars().enumerate() {
+    if i > 0 && i % 3 == offset {
+      s.push(separator);
+    }
+    s.push(c);
+  }
+  s
+}
+
+/// Groups the digits and replaces the decimal point of the numbers written in `s`, e.g. the
+/// numerator and denominator of a fraction.
+fn localize(s: &str, options: &FormatOptions) -> String {
+  let mut result = String::new();
+  let mut chars = s.chars().peekable();
+  while let Some(c) = chars.next() {
+    if !c.is_ascii_digit() {
+      result.push(c);
+      continue;
+    }
+    let mut integer = c.to_string();
+    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+      integer.push(c);
+      chars.next();
+    }
+    result.push_str(&group(&integer, options.thousands_separator));
+    if chars.peek() == Some(&'.') {
+      chars.next();
+      result.push(options.decimal_mark);
+      while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        result.push(c);
+        chars.next();
+      }
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::decimal::Decimal;
+  use super::super::rational::Fraction;
+  use super::*;
+
+  fn options(notation: Notation) -> FormatOptions {
+    FormatOptions {
+      notation,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_notations() {
+    let auto = FormatOptions::default();
+    assert_eq!(format_real(0.1 + 0.2, &auto), "0.3");
+    assert_eq!(format_real(1234.5, &auto), "1234.5");
+    assert_eq!(format_real(-0., &auto), "0");
+    assert_eq!(format_real(1e21, &auto), "1e21");
+    assert_eq!(format_real(1.5e-7, &auto), "1.5e-7");
+    assert_eq!(format_real(0.000001, &auto), "0.000001");
+    assert_eq!(format_real(f64::INFINITY, &auto), "inf");
+
+    let no_snap = FormatOptions { snap: false, ..auto };
+    assert_eq!(format_real(0.1 + 0.2, &no_snap), "0.30000000000000004");
+
+    assert_eq!(format_real(1e21, &options(Notation::Fixed)), "1000000000000000000000");
+    assert_eq!(format_real(1234.5, &options(Notation::Scientific)), "1.2345e3");
+    assert_eq!(format_real(0.00012, &options(Notation::Scientific)), "1.2e-4");
+    assert_eq!(format_real(12345., &options(Notation::Engineering)), "12.345e3");
+    assert_eq!(format_real(-0.0015, &options(Notation::Engineering)), "-1.5e-3");
+    assert_eq!(format_real(100., &options(Notation::Engineering)), "100e0");
+  }
+
+  #[test]
+  fn test_precision() {
+    let places = |notation, n| FormatOptions {
+      decimal_places: Some(n),
+      ..options(notation)
+    };
+    assert_eq!(format_real(1.23456, &places(Notation::Fixed, 2)), "1.23");
+    assert_eq!(format_real(2., &places(Notation::Auto, 3)), "2.000");
+    assert_eq!(format_real(-0.001, &places(Notation::Fixed, 2)), "0.00");
+    assert_eq!(format_real(1234.5, &places(Notation::Scientific, 2)), "1.23e3");
+    assert_eq!(format_real(999.96, &places(Notation::Engineering, 1)), "1.0e3");
+    assert_eq!(format_real(12345., &places(Notation::Engineering, 1)), "12.3e3");
+
+    let figures = |notation, n| FormatOptions {
+      significant_figures: Some(n),
+      ..options(notation)
+    };
+    assert_eq!(format_real(1.23456, &figures(Notation::Auto, 3)), "1.23");
+    assert_eq!(format_real(1.2, &figures(Notation::Auto, 3)), "1.20");
+    assert_eq!(format_real(1., &figures(Notation::Scientific, 2)), "1.0e0");
+    assert_eq!(format_real(0.000123456, &figures(Notation::Auto, 2)), "0.00012");
+    assert_eq!(format_real(123456., &figures(Notation::Auto, 3)), "1.23e5");
+    assert_eq!(format_real(123456., &figures(Notation::Fixed, 3)), "123000");
+    assert_eq!(format_real(123456., &figures(Notation::Engineering, 2)), "120e3");
+  }
+
+  #[test]
+  fn test_locale() {
+    let european = FormatOptions {
+      thousands_separator: Some('.'),
+      decimal_mark: ',',
+      ..Default::default()
+    };
+    assert_eq!(format_real(1234567.891, &european), "1.234.567,891");
+    assert_eq!(format_real(-123.5, &european), "-123,5");
+    let list = Value::List(vec![Value::Real(1.5), Value::Real(2.)]);
+    assert_eq!(format_value(&list, &european), "[1,5; 2]");
+    let decimal = Decimal::exact("12345.678".parse().unwrap());
+    assert_eq!(format_value(&Value::Decimal(decimal), &european), "12.345,678");
+    let fraction = Fraction::from_literal("1234.5").unwrap();
+    assert_eq!(format_value(&Value::Fraction(fraction), &european), "2.469/2");
+    let big: Value = Value::Integer("12345678901234567890".parse().unwrap());
+    assert_eq!(format_value(&big, &european), "12.345.678.901.234.567.890");
+  }
+
+  #[test]
+  fn test_values() {
+    let auto = FormatOptions::default();
+    let z = Complex64::new(-1., 1.2246467991473532e-16);
+    assert_eq!(format_value(&Value::Complex(z), &auto), "-1");
+    assert_eq!(format_value(&Value::Complex(Complex64::new(0.5, -2.)), &auto), "0.5-2i");
+    assert_eq!(format_value(&Value::Complex(Complex64::new(0., -2.)), &auto), "-2i");
+    let sci = options(Notation::Scientific);
+    assert_eq!(format_value(&Value::Complex(Complex64::new(0., 300.)), &sci), "3e2i");
+  }
+
+  #[test]
+  fn test_representations() {
+    let options = FormatOptions { decimal_mark: ',', radix_places: 4, ..Default::default() };
+    let format =
+      |x: f64, representation| format_represented(&Value::Real(x), representation, &options);
+    assert_eq!(format(1234.5, None), "1234,5");
+    assert_eq!(format(1234.5, Some(Representation::Scientific)), "1,2345e3");
+    assert_eq!(format(255.75, Some(Representation::Radix(16))), "0xff,c");
+    assert_eq!(format(1. / 3., Some(Representation::Radix(2))), "0b0,0101…");
+    assert_eq!(format(2024., Some(Representation::Roman)), "MMXXIV");
+    assert_eq!(format(2.5, Some(Representation::Roman)), "2,5");
+  }
+}
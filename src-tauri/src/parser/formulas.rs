@@ -0,0 +1,157 @@
+//! The dependencies between the variables defined with a formula, e.g. `b := a * 2` reads `a`.
+//! A formula is recomputed after the variables it reads, and cannot read itself through others.
+use super::parsers::ends_with_representation;
+use super::tokenizer::{tokenize, ParserError};
+use super::Token;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The names of the variables and units read by an expression, in the order they first appear,
+/// so that redefining a unit computes again the formulas using it.
+pub fn dependencies(formula: &str) -> Result<Vec<String>, ParserError> {
+  let expr = ends_with_representation(formula).map_or(formula, |(expr, _)| expr);
+  let mut names: Vec<String> = vec![];
+  for token in tokenize(expr)? {
+    let read = match token {
+      Token::Var(name) | Token::Unit(name, _) => vec![name],
+      Token::Convert(factors) => factors.into_iter().map(|(name, _)| name).collect(),
+      _ => vec![],
+    };
+    for name in read {
+      if !names.contains(&name) {
+        names.push(name);
+      }
+    }
+  }
+  Ok(names)
+}
+
+/// The variables defined with a formula, each with the names its formula reads.
+pub(super) struct Graph(BTreeMap<String, Vec<String>>);
+
+impl Graph {
+  /// The graph of some formulas, those that cannot be tokenized read nothing.
+  pub fn new<'f>(formulas: impl IntoIterator<Item = (&'f String, &'f String)>) -> Graph {
+    Graph(
+      formulas
+        .into_iter()
+        .map(|(name, formula)| (name.clone(), dependencies(formula).unwrap_or_default()))
+        .collect(),
+    )
+  }
+
+  /// The names from `name` back to itself if a formula of `name` reading `reads` closes a cycle,
+  /// e.g. `["a", "b", "a"]` for `a := b + 1` after `b := a`.
+  pub fn cycle(&self, name: &str, reads: &[String]) -> Option<Vec<String>> {
+    let mut visited = BTreeSet::new();
+    reads.iter().find_map(|read| self.path(read, name, &mut visited)).map(|mut path| {
+      path.insert(0, name.to_string());
+      path
+    })
+  }
+
+  /// The names from a variable to another through the formulas, both included.
+  fn path<'g>(
+    &'g self,
+    from: &'g str,
+    to: &str,
+    visited: &mut BTreeSet<&'g str>,
+  ) -> Option<Vec<String>> {
+    if from == to {
+      return Some(vec![to.to_string()]);
+    }
+    if !visited.insert(from) {
+      return None;
+    }
+    let reads = self.0.get(from)?;
+    reads.iter().find_map(|read| self.path(read, to, visited)).map(|mut path| {
+      path.insert(0, from.to_string());
+      path
+    })
+  }
+
+  /// The formulas reading some variables directly or through other formulas, each one after the
+  /// formulas it reads.
+  pub fn dependents(&self, names: &[&str]) -> Vec<String> {
+    let mut changed: BTreeSet<&str> = names.iter().copied().collect();
+    let mut dependents: BTreeSet<&str> = BTreeSet::new();
+    loop {
+      let found: Vec<&str> = self
+        .0
+        .iter()
+        .filter(|(name, reads)| {
+          !dependents.contains(name.as_str()) && reads.iter().any(|r| changed.contains(r.as_str()))
+        })
+        .map(|(name, _)| name.as_str())
+        .collect();
+      if found.is_empty() {
+        break;
+      }
+      changed.extend(&found);
+      dependents.extend(found);
+    }
+    self.sorted(|name| dependents.contains(name))
+  }
+
+  /// All the formulas, each one after the formulas it reads.
+  pub fn order(&self) -> Vec<String> {
+    self.sorted(|_| true)
+  }
+
+  /// The formulas kept by a filter, sorted depth first so that the formulas read come first.
+  fn sorted(&self, keep: impl Fn(&str) -> bool) -> Vec<String> {
+    fn visit<'g>(
+      graph: &'g Graph,
+      name: &'g str,
+      keep: &impl Fn(&str) -> bool,
+      visited: &mut BTreeSet<&'g str>,
+      sorted: &mut Vec<String>,
+    ) {
+      if !graph.0.contains_key(name) || !keep(name) || !visited.insert(name) {
+        return;
+      }
+      for read in graph.0.get(name).into_iter().flatten() {
+        visit(graph, read, keep, visited, sorted);
+      }
+      sorted.push(name.to_string());
+    }
+
+    let mut visited = BTreeSet::new();
+    let mut sorted = vec![];
+    for name in self.0.keys() {
+      visit(self, name, &keep, &mut visited, &mut sorted);
+    }
+    sorted
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn graph(formulas: &[(&str, &str)]) -> Graph {
+    let formulas: Vec<(String, String)> =
+      formulas.iter().map(|(name, formula)| (name.to_string(), formula.to_string())).collect();
+    Graph::new(formulas.iter().map(|(name, formula)| (name, formula)))
+  }
+
+  #[test]
+  fn test_formulas() {
+    assert_eq!(dependencies("a * 2 + max(a, b) to hex"), Ok(vec!["a".into(), "b".into()]));
+    assert_eq!(dependencies("5 km + sin(1)"), Ok(vec!["km".into()]));
+    assert_eq!(dependencies("d to furlong"), Ok(vec!["d".into(), "furlong".into()]));
+    assert_eq!(dependencies("sin(1)"), Ok(vec![]));
+
+    let formulas = graph(&[("c", "b + a"), ("b", "a * 2"), ("d", "x"), ("e", "c - 1")]);
+    assert_eq!(formulas.dependents(&["a"]), ["b", "c", "e"]);
+    assert_eq!(formulas.dependents(&["b", "x"]), ["c", "d", "e"]);
+    assert_eq!(formulas.dependents(&["e"]), Vec::<String>::new());
+    assert_eq!(formulas.order(), ["b", "c", "d", "e"]);
+
+    assert_eq!(
+      formulas.cycle("a", &["e".into()]),
+      Some(vec!["a", "e", "c", "b", "a"].into_iter().map(String::from).collect())
+    );
+    assert_eq!(formulas.cycle("x", &["x".into()]), Some(vec!["x".into(), "x".into()]));
+    assert_eq!(formulas.cycle("b", &["a".into()]), None);
+  }
+}
@@ -0,0 +1,149 @@
+//! Exact big-integer arithmetic, used in float mode while every operand is an integer so that
+//! `52!` or `2^100` keep all their digits.
+//!
+//! Results that fit in an f64 without loss stay `Value::Real`, only larger ones become
+//! `Value::Integer`.
+use super::context::NumberMode;
+use super::decimal::Decimal;
+use super::parsers::Operation;
+use super::rational::Fraction;
+use super::value::Value;
+use bigdecimal::BigDecimal;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::str::FromStr;
+
+/// Largest integer below which every integer is exactly representable as an f64 (2^53).
+const MAX_EXACT_FLOAT: f64 = 9007199254740992.;
+
+/// Results of `^` are only computed exactly up to this many bits, past that they fall back to f64
+/// (and overflow to infinity).
+const MAX_POW_BITS: u64 = 1_000_000;
+
+/// Factorials are only computed exactly up to this number.
+const MAX_FACTORIAL: u64 = 10_000;
+
+/// Returns the value as an exact integer, floats are only exact up to 2^53.
+pub fn from_value(value: &Value) -> Option<BigInt> {
+  match *value {
+    Value::Integer(ref n) => Some(n.clone()),
+    Value::Decimal(ref d) if d.decimal.is_integer() => {
+      Some(d.decimal.with_scale(0).as_bigint_and_exponent().0)
+    }
+    Value::Fraction(ref f) if f.denom.is_one() => Some(f.numer.clone()),
+    Value::Real(x) if x.fract() == 0. && x.abs() <= MAX_EXACT_FLOAT => Some(BigInt::from(x as i64)),
+    _ => None,
+  }
+}
+
+/// The integer written by a literal of digits such as `9007199254740993`, which an f64 would
+/// round, `None` for `1.5` or `1e20`.
+pub fn from_literal(text: &str) -> Option<BigInt> {
+  BigInt::from_str(text).ok()
+}
+
+/// Builds a value from an exact integer, collapsing it to a real when an f64 holds it exactly.
+pub fn to_value(n: BigInt) -> Value {
+  match n.to_i64() {
+    Some(i) if i.unsigned_abs() <= MAX_EXACT_FLOAT as u64 => Value::Real(i as f64),
+    _ => Value::Integer(n),
+  }
+}
+
+/// Converts an exact integer to the representation of the number mode.
+pub fn to_mode_value(n: BigInt, number_mode: NumberMode) -> Value {
+  match number_mode {
+    NumberMode::Float => to_value(n),
+    NumberMode::Decimal => Value::Decimal(Decimal::exact(BigDecimal::from(n))),
+    NumberMode::Fraction => Value::Fraction(Fraction::new(BigRational::from_integer(n))),
+  }
+}
+
+/// The nearest f64, infinite when the integer is out of range.
+pub fn to_f64(n: &BigInt) -> f64 {
+  n.to_f64().unwrap_or(if n.is_negative() {
+    f64::NEG_INFINITY
+  } else {
+    f64::INFINITY
+  })
+}
+
+/// Evaluates a binary operation exactly, `None` if the result is not an integer (e.g. `7/2`,
+/// `2^-1`) or too large, and the caller has to fall back to f64.
+pub fn binary(op: Operation, l: &BigInt, r: &BigInt) -> Option<BigInt> {
+  use super::parsers::Operation::*;
+
+  match op {
+    Plus => Some(l + r),
+    Minus => Some(l - r),
+    Times => Some(l * r),
+    Div if r.is_zero() || !(l % r).is_zero() => None,
+    Div => Some(l / r),
+    // Same sign convention as `f64`'s `%`: the result has the sign of the dividend
+    Mod if r.is_zero() => None,
+    Mod => Some(l % r),
+    Pow => {
+      let exp = r.to_u32()?;
+      if l.bits().saturating_mul(u64::from(exp)) > MAX_POW_BITS {
+        return None;
+      }
+      Some(num_traits::pow(l.clone(), exp as usize))
+    }
+    _ => None,
+  }
+}
+
+/// Exact factorial of a non-negative integer, `None` for everything else.
+pub fn factorial(n: &BigInt) -> Option<BigInt> {
+  let n = n.to_u64().filter(|&n| n <= MAX_FACTORIAL)?;
+  Some((2..=n).fold(BigInt::one(), |acc, k| acc * k))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::parsers::Operation::*;
+  use super::*;
+
+  fn int(s: &str) -> BigInt {
+    BigInt::from_str(s).unwrap()
+  }
+
+  #[test]
+  fn test_binary() {
+    let big = int("9007199254740993");
+    assert_eq!(binary(Plus, &big, &int("1")), Some(int("9007199254740994")));
+    assert_eq!(binary(Minus, &int("3"), &big), Some(int("-9007199254740990")));
+    assert_eq!(binary(Times, &big, &big), Some(int("81129638414606699710187514626049")));
+    assert_eq!(binary(Div, &int("12"), &int("4")), Some(int("3")));
+    assert_eq!(binary(Div, &int("7"), &int("2")), None);
+    assert_eq!(binary(Div, &int("7"), &int("0")), None);
+    assert_eq!(binary(Mod, &int("-7"), &int("3")), Some(int("-1")));
+    assert_eq!(binary(Pow, &int("2"), &int("100")), Some(int("1267650600228229401496703205376")));
+    assert_eq!(binary(Pow, &int("2"), &int("-1")), None);
+    assert_eq!(binary(Pow, &int("10"), &int("1000000")), None);
+  }
+
+  #[test]
+  fn test_conversions() {
+    assert_eq!(from_value(&Value::Real(3.)), Some(int("3")));
+    assert_eq!(from_value(&Value::Real(0.5)), None);
+    assert_eq!(from_value(&Value::Real(1e300)), None);
+    assert_eq!(from_value(&Value::Fraction(Fraction::from_literal("4.").unwrap())), Some(int("4")));
+    assert_eq!(from_value(&Value::Fraction(Fraction::from_literal("0.5").unwrap())), None);
+    assert_eq!(from_value(&Value::Decimal(Decimal::from_literal("12.").unwrap())), Some(int("12")));
+    assert_eq!(from_value(&Value::Decimal(Decimal::from_literal("1.5").unwrap())), None);
+    let big = "123456789012345678901234567890";
+    assert_eq!(from_literal(big), Some(int(big)));
+    assert_eq!(from_literal("1.5"), None);
+    assert_eq!(from_literal("1e20"), None);
+    assert_eq!(to_value(int("-42")), Value::Real(-42.));
+    assert_eq!(to_value(int("9007199254740993")), Value::Integer(int("9007199254740993")));
+    assert_eq!(to_f64(&factorial(&int("171")).unwrap()), f64::INFINITY);
+    assert_eq!(factorial(&int("-3")), None);
+    assert_eq!(
+      factorial(&int("52")),
+      Some(int("80658175170943878571660636856403766975289505440883277824000000000000"))
+    );
+  }
+}
@@ -1,17 +1,43 @@
+mod constants;
 mod context;
+mod dates;
+mod decimal;
+mod distributions;
 mod expr;
 mod extra_math;
+mod finance;
+mod format;
+mod formulas;
+mod integer;
+mod number_theory;
+mod random;
+mod rational;
+mod representation;
+mod rounding;
+mod scope;
+mod signatures;
+mod statistics;
+mod units;
+mod value;
 pub mod parsers;
 pub mod shunting_yard;
 pub mod tokenizer;
 
 use context::FuncEvalError;
-pub use context::{builtin, Context, ContextProvider};
-pub use expr::eval_str_with_context;
+pub use extra_math::DomainError;
+pub use context::{builtin, AngleMode, Context, ContextProvider, NumberMode, Reserved};
+pub use dates::DateError;
+pub use decimal::{DecimalOptions, Rounding};
+pub use expr::eval_value_str_with_context;
+pub use format::{format_represented, FormatOptions};
 pub use parsers::{Operation, Token};
+pub use representation::{Representation, RepresentationError};
+pub use signatures::Signature;
 use shunting_yard::RPNError;
 use std::fmt;
 pub use tokenizer::{tokenize, ParserError};
+pub use units::{Unit, UnitError};
+pub use value::{from_json, to_json, Value};
 
 /// An error produced during parsing or evaluation.
 #[derive(Debug, Clone, PartialEq)]
@@ -22,6 +48,19 @@ pub enum Error {
   ParseError(ParserError),
   /// The shunting-yard algorithm returned an error.
   RPNError(RPNError),
+  /// An operator applied outside of its domain, e.g. `(-1)!`.
+  Domain(DomainError),
+  /// Incompatible units, e.g. `m + s`.
+  Unit(UnitError),
+  /// Dates that cannot be combined or converted, e.g. to an unknown time zone.
+  Date(DateError),
+  /// A result that cannot be shown as requested, e.g. `2.5 to roman`.
+  Representation(RepresentationError),
+  /// An assignment to a constant, a function or a reserved name, e.g. `pi = 3`.
+  Reserved(String, Reserved),
+  /// A formula reading its own variable, e.g. `a := b` after `b := a * 2`, with the names of the
+  /// cycle.
+  Circular(Vec<String>),
   // A catch all for all other errors during evaluation
   EvalError(String),
 }
@@ -43,6 +82,28 @@ impl fmt::Display for Error {
         write!(f, "RPN error: ").expect("Could not write to formatter.");
         e.fmt(f)
       }
+      Error::Domain(ref e) => {
+        write!(f, "Domain error: ").expect("Could not write to formatter.");
+        e.fmt(f)
+      }
+      Error::Unit(ref e) => {
+        write!(f, "Unit error: ").expect("Could not write to formatter.");
+        e.fmt(f)
+      }
+      Error::Date(ref e) => {
+        write!(f, "Date error: ").expect("Could not write to formatter.");
+        e.fmt(f)
+      }
+      Error::Representation(ref e) => {
+        write!(f, "Representation error: ").expect("Could not write to formatter.");
+        e.fmt(f)
+      }
+      Error::Reserved(ref name, kind) => {
+        write!(f, "Evaluation error: `{}` is {} and cannot be assigned.", name, kind)
+      }
+      Error::Circular(ref names) => {
+        write!(f, "Evaluation error: circular formula `{}`.", names.join(" -> "))
+      }
       Error::EvalError(ref e) => {
         write!(f, "Eval error: ").expect("Could not write to formatter.");
         e.fmt(f)
@@ -69,6 +130,10 @@ impl std::error::Error for Error {
       Error::ParseError(ref e) => Some(e),
       Error::RPNError(ref e) => Some(e),
       Error::Function(_, ref e) => Some(e),
+      Error::Domain(ref e) => Some(e),
+      Error::Unit(ref e) => Some(e),
+      Error::Date(ref e) => Some(e),
+      Error::Representation(ref e) => Some(e),
       _ => None,
     }
   }
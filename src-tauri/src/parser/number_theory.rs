@@ -0,0 +1,358 @@
+//! Number theory and combinatorics builtins: `gcd`, `lcm`, `nCr`, `nPr`, primes, modular
+//! arithmetic and the `!!` double factorial.
+//!
+//! Exact results are returned as `Value::Integer`, the context converts them to the number mode.
+use super::context::FuncEvalError;
+use super::extra_math::{lgamma, DomainError};
+use super::integer;
+use super::value::Value;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// Combinations and permutations with more factors than this are computed with f64.
+const MAX_TERMS: u64 = 100_000;
+
+/// Bases of the Miller-Rabin test, deterministic below 3.3 * 10^24.
+const WITNESSES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn domain(e: DomainError) -> FuncEvalError {
+  FuncEvalError::Domain(e)
+}
+
+/// Returns the argument as an exact integer, anything else is an error rather than truncated.
+fn int_arg(arg: &Value) -> Result<BigInt, FuncEvalError> {
+  match *arg {
+    Value::List(_) => Err(FuncEvalError::ListArgument),
+    Value::Complex(_) => Err(FuncEvalError::ComplexArgument),
+    ref arg => integer::from_value(arg)
+      .ok_or_else(|| domain(DomainError::NotInteger(arg.as_real().unwrap_or(f64::NAN)))),
+  }
+}
+
+fn non_negative_arg(arg: &Value) -> Result<BigInt, FuncEvalError> {
+  let n = int_arg(arg)?;
+  if n.is_negative() {
+    return Err(domain(DomainError::Negative(integer::to_f64(&n))));
+  }
+  Ok(n)
+}
+
+fn positive_arg(arg: &Value) -> Result<BigInt, FuncEvalError> {
+  let n = int_arg(arg)?;
+  if !n.is_positive() {
+    return Err(domain(DomainError::NotPositive(integer::to_f64(&n))));
+  }
+  Ok(n)
+}
+
+fn float_int_arg(x: f64) -> Result<BigInt, DomainError> {
+  integer::from_value(&Value::Real(x)).ok_or(DomainError::NotInteger(x))
+}
+
+/// Greatest common divisor of any number of integers.
+pub fn gcd(xs: &[f64]) -> Result<f64, DomainError> {
+  let gcd = xs.iter().try_fold(BigInt::zero(), |acc, &x| Ok(acc.gcd(&float_int_arg(x)?)))?;
+  Ok(integer::to_f64(&gcd))
+}
+
+/// Least common multiple of any number of integers.
+pub fn lcm(xs: &[f64]) -> Result<f64, DomainError> {
+  let lcm = xs.iter().try_fold(BigInt::one(), |acc, &x| Ok(acc.lcm(&float_int_arg(x)?)))?;
+  Ok(integer::to_f64(&lcm))
+}
+
+/// Number of ways to choose `k` elements out of `n`.
+pub fn ncr(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let n = non_negative_arg(&args[0])?;
+  let k = int_arg(&args[1])?;
+  if k.is_negative() || k > n {
+    return Ok(Value::Integer(BigInt::zero()));
+  }
+  let k = (&n - &k).min(k);
+  match k.to_u64().filter(|&k| k <= MAX_TERMS) {
+    Some(k) => {
+      let ncr = (0..k).fold(BigInt::one(), |acc, i| acc * (&n - i) / (i + 1));
+      Ok(Value::Integer(ncr))
+    }
+    None => {
+      let (n, k) = (integer::to_f64(&n), integer::to_f64(&k));
+      Ok(Value::Real((ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)).exp()))
+    }
+  }
+}
+
+/// Number of ordered arrangements of `k` elements out of `n`.
+pub fn npr(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let n = non_negative_arg(&args[0])?;
+  let k = int_arg(&args[1])?;
+  if k.is_negative() || k > n {
+    return Ok(Value::Integer(BigInt::zero()));
+  }
+  match k.to_u64().filter(|&k| k <= MAX_TERMS) {
+    Some(k) => Ok(Value::Integer((0..k).fold(BigInt::one(), |acc, i| acc * (&n - i)))),
+    None => {
+      let (n, k) = (integer::to_f64(&n), integer::to_f64(&k));
+      Ok(Value::Real((ln_factorial(n) - ln_factorial(n - k)).exp()))
+    }
+  }
+}
+
+fn ln_factorial(n: f64) -> f64 {
+  lgamma(n + 1.).expect("n is non-negative")
+}
+
+/// Miller-Rabin primality test.
+fn is_prime(n: &BigInt) -> bool {
+  if *n < BigInt::from(2) {
+    return false;
+  }
+  for &p in WITNESSES.iter() {
+    if *n == BigInt::from(p) {
+      return true;
+    }
+    if (n % p).is_zero() {
+      return false;
+    }
+  }
+  let n_minus_one = n - 1u32;
+  let s = n_minus_one.trailing_zeros().expect("n - 1 is not zero");
+  let d = &n_minus_one >> s;
+  WITNESSES.iter().all(|&a| {
+    let mut x = BigInt::from(a).modpow(&d, n);
+    if x.is_one() || x == n_minus_one {
+      return true;
+    }
+    for _ in 1..s {
+      x = x.modpow(&BigInt::from(2), n);
+      if x == n_minus_one {
+        return true;
+      }
+    }
+    false
+  })
+}
+
+fn bool_value(b: bool) -> Value {
+  Value::Integer(BigInt::from(b as u8))
+}
+
+/// `1` if the argument is prime, `0` otherwise.
+pub fn isprime(args: &[Value]) -> Result<Value, FuncEvalError> {
+  Ok(bool_value(is_prime(&int_arg(&args[0])?)))
+}
+
+/// The smallest prime greater than the argument.
+pub fn nextprime(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let mut n = int_arg(&args[0])?.max(BigInt::one()) + 1u32;
+  while !is_prime(&n) {
+    n += 1u32;
+  }
+  Ok(Value::Integer(n))
+}
+
+/// Finds a non-trivial divisor of a composite number with Pollard's rho algorithm.
+fn pollard_rho(n: u64) -> u64 {
+  if n.is_even() {
+    return 2;
+  }
+  let mul_mod = |a: u64, b: u64| ((a as u128 * b as u128) % n as u128) as u64;
+  (1..)
+    .find_map(|c: u64| {
+      let f = |x: u64| ((mul_mod(x, x) as u128 + c as u128) % n as u128) as u64;
+      let (mut x, mut y, mut d) = (2, 2, 1);
+      while d == 1 {
+        x = f(x);
+        y = f(f(y));
+        d = x.abs_diff(y).gcd(&n);
+      }
+      if d == n {
+        None
+      } else {
+        Some(d)
+      }
+    })
+    .expect("A composite number has a divisor")
+}
+
+/// Prime factors of `n`, with multiplicity and in increasing order.
+fn prime_factors(n: u64) -> Vec<u64> {
+  let mut factors = vec![];
+  let mut stack = vec![n];
+  while let Some(m) = stack.pop() {
+    if m == 1 {
+      continue;
+    }
+    if is_prime(&BigInt::from(m)) {
+      factors.push(m);
+    } else {
+      let d = pollard_rho(m);
+      stack.push(d);
+      stack.push(m / d);
+    }
+  }
+  factors.sort_unstable();
+  factors
+}
+
+fn factor_arg(arg: &Value) -> Result<u64, FuncEvalError> {
+  let n = positive_arg(arg)?;
+  n.to_u64()
+    .ok_or_else(|| domain(DomainError::TooLarge(integer::to_f64(&n))))
+}
+
+/// The prime factorisation of a positive integer as a list, e.g. `factor(12)` is `[2, 2, 3]`.
+pub fn factor(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let factors = prime_factors(factor_arg(&args[0])?);
+  Ok(Value::List(
+    factors
+      .into_iter()
+      .map(|p| integer::to_value(BigInt::from(p)))
+      .collect(),
+  ))
+}
+
+/// Euler's totient, the number of integers up to `n` that are coprime with it.
+pub fn totient(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let n = factor_arg(&args[0])?;
+  let mut factors = prime_factors(n);
+  factors.dedup();
+  let phi = factors.iter().fold(n, |acc, p| acc / p * (p - 1));
+  Ok(Value::Integer(BigInt::from(phi)))
+}
+
+fn inverse(a: &BigInt, m: &BigInt) -> Result<BigInt, FuncEvalError> {
+  let e = a.mod_floor(m).extended_gcd(m);
+  if !e.gcd.is_one() {
+    return Err(domain(DomainError::NoInverse(
+      integer::to_f64(a),
+      integer::to_f64(m),
+    )));
+  }
+  Ok(e.x.mod_floor(m))
+}
+
+/// `a^b mod m`, negative exponents use the modular inverse.
+pub fn modpow(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let (a, b, m) = (int_arg(&args[0])?, int_arg(&args[1])?, positive_arg(&args[2])?);
+  let base = if b.is_negative() { inverse(&a, &m)? } else { a.mod_floor(&m) };
+  Ok(Value::Integer(base.modpow(&b.abs(), &m)))
+}
+
+/// The inverse of `a` modulo `m`.
+pub fn modinv(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let (a, m) = (int_arg(&args[0])?, positive_arg(&args[1])?);
+  Ok(Value::Integer(inverse(&a, &m)?))
+}
+
+/// Euclidean modulo, the result is always between `0` and `|b| - 1` unlike `%`.
+pub fn emod(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let (a, b) = (int_arg(&args[0])?, int_arg(&args[1])?);
+  if b.is_zero() {
+    return Err(domain(DomainError::DivisionByZero));
+  }
+  Ok(Value::Integer(a.mod_floor(&b.abs())))
+}
+
+/// `n!! = n (n - 2) (n - 4) ...`, with `0!! = (-1)!! = 1`.
+pub fn double_factorial(n: &BigInt) -> Result<BigInt, DomainError> {
+  if *n < -BigInt::one() {
+    return Err(DomainError::Negative(integer::to_f64(n)));
+  }
+  if n.is_negative() {
+    return Ok(BigInt::one());
+  }
+  let n = n
+    .to_u64()
+    .filter(|&n| n <= 2 * MAX_TERMS)
+    .ok_or_else(|| DomainError::TooLarge(integer::to_f64(n)))?;
+  Ok((1..=n).rev().step_by(2).fold(BigInt::one(), |acc, k| acc * k))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn int(n: i64) -> Value {
+    Value::Real(n as f64)
+  }
+
+  fn big(s: &str) -> Value {
+    Value::Integer(s.parse().unwrap())
+  }
+
+  #[test]
+  fn test_gcd_lcm() {
+    assert_eq!(gcd(&[12., 18., -30.]), Ok(6.));
+    assert_eq!(gcd(&[0., 5.]), Ok(5.));
+    assert_eq!(lcm(&[4., 6., 10.]), Ok(60.));
+    assert_eq!(lcm(&[0., 6.]), Ok(0.));
+    assert_eq!(gcd(&[4., 2.5]), Err(DomainError::NotInteger(2.5)));
+  }
+
+  #[test]
+  fn test_combinatorics() {
+    assert_eq!(ncr(&[int(5), int(2)]), Ok(big("10")));
+    assert_eq!(ncr(&[int(5), int(7)]), Ok(big("0")));
+    assert_eq!(ncr(&[int(100), int(50)]), Ok(big("100891344545564193334812497256")));
+    assert_eq!(npr(&[int(5), int(2)]), Ok(big("20")));
+    assert_eq!(
+      ncr(&[int(-5), int(2)]),
+      Err(FuncEvalError::Domain(DomainError::Negative(-5.)))
+    );
+    assert_eq!(
+      npr(&[Value::Real(5.5), int(2)]),
+      Err(FuncEvalError::Domain(DomainError::NotInteger(5.5)))
+    );
+    assert_eq!(double_factorial(&7.into()), Ok(105.into()));
+    assert_eq!(double_factorial(&8.into()), Ok(384.into()));
+    assert_eq!(double_factorial(&0.into()), Ok(1.into()));
+    assert_eq!(double_factorial(&(-1).into()), Ok(1.into()));
+    assert_eq!(double_factorial(&(-3).into()), Err(DomainError::Negative(-3.)));
+  }
+
+  #[test]
+  fn test_primes() {
+    assert_eq!(isprime(&[int(97)]), Ok(big("1")));
+    assert_eq!(isprime(&[int(91)]), Ok(big("0")));
+    assert_eq!(isprime(&[int(1)]), Ok(big("0")));
+    assert_eq!(isprime(&[big("170141183460469231731687303715884105727")]), Ok(big("1")));
+    assert_eq!(nextprime(&[int(13)]), Ok(big("17")));
+    assert_eq!(nextprime(&[int(-4)]), Ok(big("2")));
+    assert_eq!(
+      factor(&[int(360)]),
+      Ok(Value::List([2., 2., 2., 3., 3., 5.].iter().map(|&p| Value::Real(p)).collect()))
+    );
+    assert_eq!(
+      factor(&[big("18446744073709551557")]),
+      Ok(Value::List(vec![big("18446744073709551557")]))
+    );
+    assert_eq!(
+      factor(&[int(600851475143)]),
+      Ok(Value::List([71., 839., 1471., 6857.].iter().map(|&p| Value::Real(p)).collect()))
+    );
+    assert_eq!(
+      factor(&[int(0)]),
+      Err(FuncEvalError::Domain(DomainError::NotPositive(0.)))
+    );
+    assert_eq!(totient(&[int(36)]), Ok(big("12")));
+    assert_eq!(totient(&[int(1)]), Ok(big("1")));
+  }
+
+  #[test]
+  fn test_modular() {
+    assert_eq!(modpow(&[int(4), int(13), int(497)]), Ok(big("445")));
+    assert_eq!(modpow(&[int(3), int(-1), int(7)]), Ok(big("5")));
+    assert_eq!(modinv(&[int(3), int(11)]), Ok(big("4")));
+    assert_eq!(
+      modinv(&[int(6), int(9)]),
+      Err(FuncEvalError::Domain(DomainError::NoInverse(6., 9.)))
+    );
+    assert_eq!(emod(&[int(-7), int(3)]), Ok(big("2")));
+    assert_eq!(emod(&[int(7), int(-3)]), Ok(big("1")));
+    assert_eq!(
+      emod(&[int(7), int(0)]),
+      Err(FuncEvalError::Domain(DomainError::DivisionByZero))
+    );
+  }
+}
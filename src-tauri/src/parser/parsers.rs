@@ -1,10 +1,16 @@
+use super::representation::Representation;
+use chrono::{NaiveDate, NaiveDateTime};
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, alphanumeric1, multispace0};
-use nom::combinator::{map, recognize};
+use nom::bytes::complete::{tag, take_while_m_n};
+use nom::character::complete::{
+  alpha1, alphanumeric1, digit1, multispace0, multispace1, one_of, satisfy,
+};
+use nom::character::complete::i32 as integer;
+use nom::combinator::{all_consuming, consumed, map, not, opt, recognize, value};
+use nom::error::{ErrorKind, ParseError};
 use nom::multi::many0_count;
 use nom::number::complete::double;
-use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::IResult;
 
 /// Mathematical operations.
@@ -17,6 +23,7 @@ pub enum Operation {
   Mod,
   Pow,
   Fact,
+  DoubleFact,
 }
 
 /// Expression tokens.
@@ -34,16 +41,108 @@ pub enum Token {
   /// Comma: function argument separator
   Comma,
 
-  /// A number.
-  Number(f64),
+  /// A number, with the digits as typed so that the exact number modes lose none of them.
+  Number(f64, String),
+  /// An imaginary number literal such as `2i`.
+  Imaginary(f64),
+  /// A date literal such as `2026-10-18` or `2026-10-18T09:30`, and whether it has a time.
+  Date(NaiveDateTime, bool),
+  /// A duration literal such as `1h30m` or `2:45:10`, in seconds.
+  Duration(f64),
   /// A variable.
   Var(String),
   /// A function with name and number of arguments.
   Func(String, Option<usize>),
+  /// A unit with its power applied to the expression on its left, e.g. `m^2` in `3 m^2`.
+  Unit(String, i32),
+  /// Conversion of the expression on its left to a unit, e.g. `to m/s`.
+  Convert(Vec<(String, i32)>),
 }
 
 fn number(input: &str) -> IResult<&str, Token> {
-  map(double, |n| Token::Number(n))(input)
+  map(consumed(double), |(text, n): (&str, f64)| Token::Number(n, text.to_string()))(input)
+}
+
+fn imaginary(input: &str) -> IResult<&str, Token> {
+  map(
+    terminated(double, pair(one_of("ij"), not(alt((alphanumeric1, tag("_")))))),
+    Token::Imaginary,
+  )(input)
+}
+
+fn digits(n: usize) -> impl Fn(&str) -> IResult<&str, &str> {
+  move |input| take_while_m_n(n, n, |c: char| c.is_ascii_digit())(input)
+}
+
+/// The text of an ISO 8601 date with an optional time, whether or not it is a calendar date.
+pub fn date_text(input: &str) -> IResult<&str, &str> {
+  let time = tuple((tag("T"), digits(2), tag(":"), digits(2), opt(pair(tag(":"), digits(2)))));
+  terminated(
+    recognize(tuple((digits(4), tag("-"), digits(2), tag("-"), digits(2), opt(time)))),
+    not(satisfy(|c| c.is_ascii_alphanumeric())),
+  )(input)
+}
+
+/// An ISO 8601 date with an optional time, `2026-02-30` is a failure rather than a subtraction.
+fn date(input: &str) -> IResult<&str, Token> {
+  let (rest, text) = date_text(input)?;
+  let datetime = match text.len() {
+    10 => NaiveDate::parse_from_str(text, "%Y-%m-%d").map(NaiveDate::into),
+    16 => NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M"),
+    _ => NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"),
+  };
+  match datetime {
+    Ok(datetime) => Ok((rest, Token::Date(datetime, text.len() > 10))),
+    Err(_) => Err(nom::Err::Failure(ParseError::from_error_kind(input, ErrorKind::Verify))),
+  }
+}
+
+/// An unsigned decimal number such as `2` or `1.5`, signs are unary operators.
+fn unsigned(input: &str) -> IResult<&str, f64> {
+  map(recognize(pair(digit1, opt(pair(tag("."), digit1)))), |s: &str| {
+    s.parse().expect("Digits are a valid number")
+  })(input)
+}
+
+/// `h:mm` or `h:mm:ss` with optional fractional seconds, `8:75` is a failure.
+fn clock(input: &str) -> IResult<&str, Token> {
+  let seconds = recognize(pair(digits(2), opt(pair(tag("."), digit1))));
+  let (rest, (hours, _, minutes, seconds)) = terminated(
+    tuple((digit1, tag(":"), digits(2), opt(preceded(tag(":"), seconds)))),
+    not(satisfy(|c| c.is_ascii_alphanumeric() || c == ':')),
+  )(input)?;
+  let hours: f64 = hours.parse().expect("Digits are a valid number");
+  let minutes: f64 = minutes.parse().expect("Digits are a valid number");
+  let seconds: f64 = seconds.map_or(0., |s| s.parse().expect("Digits are a valid number"));
+  if minutes >= 60. || seconds >= 60. {
+    return Err(nom::Err::Failure(ParseError::from_error_kind(input, ErrorKind::Verify)));
+  }
+  Ok((rest, Token::Duration(hours * 3600. + minutes * 60. + seconds)))
+}
+
+/// Durations written with at least two of `d`, `h`, `m` and `s` in that order, e.g. `1h30m` or
+/// `3d 4h`. A single one such as `90s` or `5m` is a number with a unit, `m` being the metre.
+fn duration(input: &str) -> IResult<&str, Token> {
+  const UNITS: [(char, f64); 4] = [('d', 86400.), ('h', 3600.), ('m', 60.), ('s', 1.)];
+  let mut component = pair(
+    unsigned,
+    terminated(one_of("dhms"), not(satisfy(|c| c.is_ascii_alphabetic() || c == '_'))),
+  );
+  let (mut rest, (value, unit)) = component(input)?;
+  let mut last = UNITS.iter().position(|&(u, _)| u == unit).expect("The unit is a duration unit");
+  let (mut seconds, mut count) = (value * UNITS[last].1, 1);
+  while let Ok((next, (value, unit))) = preceded(opt(tag(" ")), &mut component)(rest) {
+    let i = UNITS.iter().position(|&(u, _)| u == unit).expect("The unit is a duration unit");
+    if i <= last {
+      break;
+    }
+    seconds += value * UNITS[i].1;
+    (rest, last, count) = (next, i, count + 1);
+  }
+  if count < 2 {
+    return Err(nom::Err::Error(ParseError::from_error_kind(input, ErrorKind::Verify)));
+  }
+  Ok((rest, Token::Duration(seconds)))
 }
 
 fn assignment(input: &str) -> IResult<&str, &str> {
@@ -57,6 +156,29 @@ pub fn starts_with_assignment(input: &str) -> IResult<&str, Token> {
   )(input)
 }
 
+/// `const name =` at the start of a constant declaration, returns the name of the constant.
+pub fn starts_with_constant_definition(input: &str) -> IResult<&str, &str> {
+  delimited(
+    pair(multispace0, pair(tag("const"), multispace1)),
+    ident,
+    pair(multispace0, assignment),
+  )(input)
+}
+
+/// `name :=` at the start of a formula, returns the name of the variable recomputed from it.
+pub fn starts_with_formula(input: &str) -> IResult<&str, &str> {
+  delimited(multispace0, ident, pair(multispace0, tag(":=")))(input)
+}
+
+/// `unit name =` at the start of a unit definition, returns the name of the unit.
+pub fn starts_with_unit_definition(input: &str) -> IResult<&str, &str> {
+  delimited(
+    pair(multispace0, pair(tag("unit"), multispace1)),
+    ident,
+    pair(multispace0, assignment),
+  )(input)
+}
+
 // Parse func( returns func
 fn func(input: &str) -> IResult<&str, Token> {
   map(terminated(ident, preceded(multispace0, tag("("))), |name| {
@@ -64,17 +186,133 @@ fn func(input: &str) -> IResult<&str, Token> {
   })(input)
 }
 
+/// `rand()`, a call without arguments is a complete operand.
+fn call0(input: &str) -> IResult<&str, Token> {
+  map(
+    terminated(ident, tuple((multispace0, tag("("), multispace0, tag(")")))),
+    |name| Token::Func(name.to_string(), Some(0)),
+  )(input)
+}
+
 fn var(input: &str) -> IResult<&str, Token> {
   map(ident, |s| Token::Var(s.to_string()))(input)
 }
 
-fn ident(input: &str) -> IResult<&str, &str> {
+fn ident_segment(input: &str) -> IResult<&str, &str> {
   recognize(pair(
     alt((alpha1, tag("_"))),
     many0_count(alt((alphanumeric1, tag("_")))),
   ))(input)
 }
 
+/// A name, possibly qualified by namespaces such as `phys.c`.
+fn ident(input: &str) -> IResult<&str, &str> {
+  recognize(pair(
+    ident_segment,
+    many0_count(pair(tag("."), ident_segment)),
+  ))(input)
+}
+
+/// A currency symbol, returns the code of the currency.
+fn currency_symbol(input: &str) -> IResult<&str, &str> {
+  alt((
+    value("USD", tag("$")),
+    value("EUR", tag("€")),
+    value("GBP", tag("£")),
+    value("JPY", tag("¥")),
+  ))(input)
+}
+
+/// An amount with a currency symbol in front of it such as `$45`, as a number and its unit.
+pub fn currency_literal(input: &str) -> IResult<&str, (Token, Token)> {
+  map(
+    delimited(
+      multispace0,
+      pair(currency_symbol, preceded(multispace0, consumed(double))),
+      multispace0,
+    ),
+    |(code, (text, amount))| {
+      (Token::Number(amount, text.to_string()), Token::Unit(code.to_string(), 1))
+    },
+  )(input)
+}
+
+/// A unit symbol with an optional integer power: `km`, `s^-2`, `€`, `°`.
+fn unit_factor(input: &str) -> IResult<&str, (String, i32)> {
+  map(
+    pair(
+      alt((ident, currency_symbol, tag("°"))),
+      opt(preceded(alt((tag("^"), tag("**"))), integer)),
+    ),
+    |(name, power)| (name.to_string(), power.unwrap_or(1)),
+  )(input)
+}
+
+/// A unit that is not a function call.
+fn unit(input: &str) -> IResult<&str, Token> {
+  map(
+    terminated(unit_factor, not(preceded(multispace0, tag("(")))),
+    |(name, power)| Token::Unit(name, power),
+  )(input)
+}
+
+/// A compound unit such as `kW h`, `m/s^2` or `kg*m/s`, `/` only applies to the next symbol.
+fn unit_expr(input: &str) -> IResult<&str, Vec<(String, i32)>> {
+  let (mut input, first) = unit_factor(input)?;
+  let mut factors = vec![first];
+  let separator = alt((
+    delimited(multispace0, alt((tag("*"), tag("/"))), multispace0),
+    multispace1,
+  ));
+  let mut next = pair(separator, unit_factor);
+  while let Ok((rest, (separator, (name, power)))) = next(input) {
+    factors.push((name, if separator == "/" { -power } else { power }));
+    input = rest;
+  }
+  Ok((input, factors))
+}
+
+/// `to unit` or `in unit`.
+fn convert(input: &str) -> IResult<&str, Token> {
+  map(
+    preceded(pair(alt((tag("to"), tag("in"))), multispace1), unit_expr),
+    Token::Convert,
+  )(input)
+}
+
+/// `hex`, `bin`, `oct`, `base N`, `roman` or `sci`, the output representation after `to`.
+pub fn representation(input: &str) -> IResult<&str, Representation> {
+  terminated(
+    alt((
+      value(Representation::Radix(16), tag("hex")),
+      value(Representation::Radix(2), tag("bin")),
+      value(Representation::Radix(8), tag("oct")),
+      map(preceded(pair(tag("base"), multispace1), digit1), |base: &str| {
+        // Out of range bases are reported when the result is checked
+        Representation::Radix(base.parse().unwrap_or(u32::MAX))
+      }),
+      value(Representation::Roman, tag("roman")),
+      value(Representation::Scientific, tag("sci")),
+    )),
+    not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+  )(input)
+}
+
+/// `to hex`-like suffix at the end of an operation, returns the operation before it and the
+/// representation.
+pub fn ends_with_representation(input: &str) -> Option<(&str, Representation)> {
+  input.rmatch_indices("to").find_map(|(i, _)| {
+    let (operation, suffix) = input.split_at(i);
+    if !operation.ends_with(char::is_whitespace) {
+      return None;
+    }
+    let to = pair(tag("to"), multispace1);
+    let (_, representation) =
+      all_consuming(delimited(to, representation, multispace0))(suffix).ok()?;
+    Some((operation.trim_end(), representation))
+  })
+}
+
 fn negpos(input: &str) -> IResult<&str, Token> {
   alt((
     map(tag("-"), |_| Token::Unary(Operation::Minus)),
@@ -108,60 +346,94 @@ fn comma(input: &str) -> IResult<&str, Token> {
 }
 
 fn fact(input: &str) -> IResult<&str, Token> {
-  map(tag("!"), |_| Token::Unary(Operation::Fact))(input)
+  alt((
+    map(tag("!!"), |_| Token::Unary(Operation::DoubleFact)),
+    map(tag("!"), |_| Token::Unary(Operation::Fact)),
+  ))(input)
 }
 
 pub fn lexpr(input: &str) -> IResult<&str, Token> {
   delimited(
     multispace0,
-    alt((number, func, var, negpos, lparen)),
+    alt((date, clock, duration, imaginary, number, call0, func, var, negpos, lparen)),
     multispace0,
   )(input)
 }
 
 pub fn after_rexpr_no_paren(input: &str) -> IResult<&str, Token> {
-  delimited(multispace0, alt((fact, binop)), multispace0)(input)
+  delimited(multispace0, alt((fact, binop, convert, unit)), multispace0)(input)
 }
 
 pub fn after_rexpr(input: &str) -> IResult<&str, Token> {
-  delimited(multispace0, alt((fact, binop, rparen)), multispace0)(input)
+  delimited(multispace0, alt((fact, binop, rparen, convert, unit)), multispace0)(input)
 }
 
 pub fn after_rexpr_comma(input: &str) -> IResult<&str, Token> {
-  delimited(multispace0, alt((fact, binop, rparen, comma)), multispace0)(input)
+  delimited(
+    multispace0,
+    alt((fact, binop, rparen, comma, convert, unit)),
+    multispace0,
+  )(input)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_date() {
+    let midnight = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(date("2026-10-18 + 1"), Ok((" + 1", Token::Date(midnight, false))));
+    assert_eq!(
+      date("2026-10-18T09:30"),
+      Ok(("", Token::Date(midnight + chrono::Duration::minutes(570), true)))
+    );
+    assert!(matches!(date("2026-02-30"), Err(nom::Err::Failure(_))));
+    assert!(matches!(date("2026-10-185"), Err(nom::Err::Error(_))));
+    assert!(matches!(date("2026-10"), Err(nom::Err::Error(_))));
+  }
+
+  #[test]
+  fn test_duration() {
+    assert_eq!(duration("1h30m"), Ok(("", Token::Duration(5400.))));
+    assert_eq!(duration("3d 4h * 2"), Ok((" * 2", Token::Duration(273600.))));
+    assert_eq!(duration("1m30.5s"), Ok(("", Token::Duration(90.5))));
+    assert!(duration("90s").is_err());
+    assert!(duration("5m").is_err());
+    assert!(duration("30m1h").is_err());
+    assert_eq!(clock("2:45:10"), Ok(("", Token::Duration(9910.))));
+    assert_eq!(clock("8:00 + 1"), Ok((" + 1", Token::Duration(28800.))));
+    assert!(matches!(clock("8:75"), Err(nom::Err::Failure(_))));
+    assert!(matches!(clock("8:5"), Err(nom::Err::Error(_))));
+  }
+
   #[test]
   fn test_number() {
-    assert_eq!(number(&"32143"), IResult::Ok(("", Token::Number(32143f64))));
-    assert_eq!(number(&"2."), IResult::Ok(("", Token::Number(2.0f64))));
+    assert_eq!(number(&"32143"), IResult::Ok(("", Token::Number(32143f64, "32143".into()))));
+    assert_eq!(number(&"2."), IResult::Ok(("", Token::Number(2.0f64, "2.".into()))));
     assert_eq!(
       number(&"32143.25"),
-      IResult::Ok(("", Token::Number(32143.25f64)))
+      IResult::Ok(("", Token::Number(32143.25f64, "32143.25".into())))
     );
     assert_eq!(
       number(&"0.125e9"),
-      IResult::Ok(("", Token::Number(0.125e9f64)))
+      IResult::Ok(("", Token::Number(0.125e9f64, "0.125e9".into())))
     );
     assert_eq!(
       number(&"20.5E-3"),
-      IResult::Ok(("", Token::Number(20.5E-3f64)))
+      IResult::Ok(("", Token::Number(20.5E-3f64, "20.5E-3".into())))
     );
     assert_eq!(
       number(&"123423e+50"),
-      IResult::Ok(("", Token::Number(123423e+50f64)))
+      IResult::Ok(("", Token::Number(123423e+50f64, "123423e+50".into())))
     );
     assert_eq!(
       number(&"123423e-50something"),
-      IResult::Ok(("something", Token::Number(123423e-50f64)))
+      IResult::Ok(("something", Token::Number(123423e-50f64, "123423e-50".into())))
     );
     assert_eq!(
       number(&"123text"),
-      IResult::Ok(("text", Token::Number(123f64)))
+      IResult::Ok(("text", Token::Number(123f64, "123".into())))
     );
     assert!(matches!(number(&""), IResult::Err(nom::Err::Error { .. })));
 
@@ -171,6 +443,32 @@ mod tests {
     ));
   }
 
+  #[test]
+  fn test_imaginary() {
+    assert_eq!(imaginary("2i"), IResult::Ok(("", Token::Imaginary(2f64))));
+    assert_eq!(imaginary("1.5j+1"), IResult::Ok(("+1", Token::Imaginary(1.5f64))));
+    assert!(matches!(imaginary("2"), IResult::Err { .. }));
+    assert!(matches!(imaginary("2in"), IResult::Err { .. }));
+    assert!(matches!(imaginary("2i_"), IResult::Err { .. }));
+  }
+
+  #[test]
+  fn test_currency() {
+    assert_eq!(
+      currency_literal("$45 * 3"),
+      IResult::Ok(("* 3", (Token::Number(45., "45".into()), Token::Unit("USD".into(), 1))))
+    );
+    assert_eq!(
+      currency_literal("€ 1.5"),
+      IResult::Ok(("", (Token::Number(1.5, "1.5".into()), Token::Unit("EUR".into(), 1))))
+    );
+    assert_eq!(
+      convert("in £"),
+      IResult::Ok(("", Token::Convert(vec![("GBP".into(), 1)])))
+    );
+    assert!(matches!(currency_literal("45"), IResult::Err { .. }));
+  }
+
   #[test]
   fn test_func_parse() {
     assert_eq!(
@@ -182,6 +480,9 @@ mod tests {
       IResult::Ok(("", Token::Func("func".to_string(), None)))
     );
 
+    assert_eq!(call0("rand( )"), IResult::Ok(("", Token::Func("rand".to_string(), Some(0)))));
+    assert!(matches!(call0("rand(1)"), IResult::Err { .. }));
+
     for &s in ["abc(", "u0(", "_034 (", "A_be45EA  ("].iter() {
       assert_eq!(
         func(s),
@@ -217,6 +518,12 @@ mod tests {
       IResult::Ok(("!", Token::Var("_abc_123".to_string())))
     );
 
+    assert_eq!(
+      var("phys.k_B*2"),
+      IResult::Ok(("*2", Token::Var("phys.k_B".to_string())))
+    );
+    assert_eq!(var("x.5"), IResult::Ok((".5", Token::Var("x".to_string()))));
+
     assert!(matches!(var("1"), IResult::Err { .. }));
     assert!(matches!(var("1.2"), IResult::Err { .. }));
     assert!(matches!(var("1a"), IResult::Err { .. }));
@@ -259,6 +566,10 @@ mod tests {
       IResult::Ok(("1", Token::Unary(Operation::Fact)))
     );
     assert!(matches!(fact("1"), IResult::Err { .. }));
+    assert_eq!(
+      fact("!!1"),
+      IResult::Ok(("1", Token::Unary(Operation::DoubleFact)))
+    );
 
     assert_eq!(comma(","), IResult::Ok(("", Token::Comma)));
     assert_eq!(comma(",1"), IResult::Ok(("1", Token::Comma)));
@@ -308,5 +619,41 @@ mod tests {
       starts_with_assignment("= 34"),
       IResult::Err { .. }
     ));
+
+    assert_eq!(
+      starts_with_unit_definition("unit furlong = 201.168 m"),
+      IResult::Ok((" 201.168 m", "furlong"))
+    );
+    assert!(matches!(
+      starts_with_unit_definition("units = 2"),
+      IResult::Err { .. }
+    ));
+    assert_eq!(starts_with_constant_definition("const g = 9.81"), IResult::Ok((" 9.81", "g")));
+    assert!(matches!(starts_with_constant_definition("constant = 2"), IResult::Err { .. }));
+    assert_eq!(starts_with_formula(" b := a * 2"), IResult::Ok((" a * 2", "b")));
+    assert!(matches!(starts_with_formula("b = 2"), IResult::Err { .. }));
+  }
+
+  #[test]
+  fn test_representation() {
+    assert_eq!(
+      ends_with_representation("255 to hex"),
+      Some(("255", Representation::Radix(16)))
+    );
+    assert_eq!(
+      ends_with_representation("x = 2^10 to base 36 "),
+      Some(("x = 2^10", Representation::Radix(36)))
+    );
+    assert_eq!(
+      ends_with_representation("1994 to roman"),
+      Some(("1994", Representation::Roman))
+    );
+    assert_eq!(ends_with_representation("5 km to mi"), None);
+    assert_eq!(ends_with_representation("5 m to hexameter"), None);
+    assert_eq!(ends_with_representation("total to"), None);
+    assert_eq!(
+      ends_with_representation("photo to sci"),
+      Some(("photo", Representation::Scientific))
+    );
   }
 }
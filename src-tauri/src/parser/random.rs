@@ -0,0 +1,115 @@
+//! The generator of the random builtins, xoshiro256** seeded with SplitMix64, so that `seed 42`
+//! gives the same numbers on every platform and version.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+  state: [u64; 4],
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Rng {
+    let mut x = seed;
+    let mut splitmix = || {
+      x = x.wrapping_add(0x9e3779b97f4a7c15);
+      let mut z = x;
+      z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+      z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+      z ^ (z >> 31)
+    };
+    Rng { state: [splitmix(), splitmix(), splitmix(), splitmix()] }
+  }
+
+  /// A generator seeded from the clock, for sessions without a `seed` command.
+  pub fn from_time() -> Rng {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    Rng::new(nanos as u64 ^ (nanos >> 64) as u64)
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    let s = &mut self.state;
+    let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+    let t = s[1] << 17;
+    s[2] ^= s[0];
+    s[3] ^= s[1];
+    s[1] ^= s[2];
+    s[0] ^= s[3];
+    s[2] ^= t;
+    s[3] = s[3].rotate_left(45);
+    result
+  }
+
+  /// A uniform number in `[0, 1)`, with the 53 bits of precision of an f64.
+  pub fn next_f64(&mut self) -> f64 {
+    (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  /// A uniform integer in `[0, n)`, rejecting the values that would bias the modulo. `None` when
+  /// the range is empty.
+  pub fn below(&mut self, n: u64) -> Option<u64> {
+    if n == 0 {
+      return None;
+    }
+    let zone = u64::MAX - u64::MAX % n;
+    loop {
+      let x = self.next_u64();
+      if x < zone {
+        return Some(x % n);
+      }
+    }
+  }
+
+  /// A standard normal number, with the Box–Muller transform.
+  pub fn normal(&mut self) -> f64 {
+    // 1 - u is in (0, 1] so the logarithm is finite
+    let u = 1. - self.next_f64();
+    let v = self.next_f64();
+    (-2. * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+  }
+
+  /// Shuffles the items in place with the Fisher–Yates algorithm.
+  pub fn shuffle<T>(&mut self, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+      let j = self.below(i as u64 + 1).expect("The range has i + 1 items") as usize;
+      items.swap(i, j);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_seed() {
+    let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+    let xs: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+    assert_eq!(xs, (0..4).map(|_| b.next_u64()).collect::<Vec<u64>>());
+    assert_ne!(Rng::new(42), Rng::new(43));
+
+    let mut rng = Rng::new(7);
+    for _ in 0..1000 {
+      let x = rng.next_f64();
+      assert!((0. ..1.).contains(&x));
+      assert!(rng.below(6).is_some_and(|n| n < 6));
+      assert!(rng.normal().is_finite());
+    }
+    assert_eq!(rng.below(0), None);
+    let mut items = [1, 2, 3, 4, 5];
+    rng.shuffle(&mut items);
+    items.sort();
+    assert_eq!(items, [1, 2, 3, 4, 5]);
+  }
+
+  #[test]
+  fn test_distribution() {
+    let mut rng = Rng::new(1);
+    let n = 10000;
+    let mean = (0..n).map(|_| rng.next_f64()).sum::<f64>() / n as f64;
+    assert!((mean - 0.5).abs() < 0.02, "{}", mean);
+    let normal: Vec<f64> = (0..n).map(|_| rng.normal()).collect();
+    let mean = normal.iter().sum::<f64>() / n as f64;
+    let var = normal.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+    assert!(mean.abs() < 0.05 && (var - 1.).abs() < 0.05, "{} {}", mean, var);
+  }
+}
@@ -0,0 +1,207 @@
+//! Exact rational arithmetic backed by big integers, used when the number mode is
+//! `NumberMode::Fraction` so that `1/3 + 1/6` is exactly `1/2`.
+use super::parsers::Operation;
+use super::value::Value;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A reduced fraction.
+///
+/// `mixed` is only a display preference, set by `mixed(x)`, to show `3/2` as `1 1/2`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fraction {
+  #[serde(with = "bigint_repr")]
+  pub numer: BigInt,
+  #[serde(with = "bigint_repr")]
+  pub denom: BigInt,
+  pub mixed: bool,
+}
+
+impl Fraction {
+  pub fn new(rational: BigRational) -> Fraction {
+    let (numer, denom) = rational.into();
+    Fraction {
+      numer,
+      denom,
+      mixed: false,
+    }
+  }
+
+  pub fn to_rational(&self) -> BigRational {
+    BigRational::new(self.numer.clone(), self.denom.clone())
+  }
+
+  /// Converts a number literal from the digits that were typed, so `0.25` is exactly `1/4` and no
+  /// digit is lost to a float.
+  pub fn from_literal(text: &str) -> Option<Fraction> {
+    let (digits, scale) = bigdecimal::BigDecimal::from_str(text).ok()?.into_bigint_and_exponent();
+    let power = num_traits::pow(BigInt::from(10), scale.unsigned_abs() as usize);
+    let rational = if scale >= 0 {
+      BigRational::new(digits, power)
+    } else {
+      BigRational::from_integer(digits * power)
+    };
+    Some(Fraction::new(rational))
+  }
+
+  /// Converts any real value to a fraction, floats are only exact when they are integers.
+  pub fn from_value(value: &Value) -> Option<Fraction> {
+    match *value {
+      Value::Fraction(ref f) => Some(f.clone()),
+      Value::Integer(ref n) => Some(Fraction::new(BigRational::from_integer(n.clone()))),
+      Value::Real(x) if x.fract() == 0. && x.abs() <= 9007199254740992. => {
+        Some(Fraction::new(BigRational::from_integer(BigInt::from(x as i64))))
+      }
+      _ => None,
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.to_rational().to_f64().unwrap_or(f64::NAN)
+  }
+
+  /// The same fraction, displayed as a mixed number.
+  pub fn to_mixed(&self) -> Fraction {
+    Fraction {
+      mixed: true,
+      ..self.clone()
+    }
+  }
+}
+
+impl fmt::Display for Fraction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.denom.is_one() {
+      return write!(f, "{}", self.numer);
+    }
+    let whole = &self.numer / &self.denom;
+    if !self.mixed || whole.is_zero() {
+      return write!(f, "{}/{}", self.numer, self.denom);
+    }
+    let rest = (&self.numer % &self.denom).abs();
+    write!(f, "{} {}/{}", whole, rest, self.denom)
+  }
+}
+
+/// Exact powers are only computed up to this many bits in the numerator or the denominator.
+const MAX_POW_BITS: u64 = 1_000_000;
+
+/// Whether the exact power `base^exp` is too large to compute, e.g. `(1/3)^1000000`. The powers
+/// of 0 and ±1 are always small.
+pub fn pow_too_large(base: &Fraction, exp: &Fraction) -> bool {
+  if !exp.denom.is_one() || base.numer.is_zero() || base.numer.abs() == base.denom {
+    return false;
+  }
+  let bits = base.numer.bits().max(base.denom.bits());
+  exp.numer.abs().to_u64().is_none_or(|exp| exp.saturating_mul(bits) > MAX_POW_BITS)
+}
+
+/// Evaluates a binary operation exactly, `None` if the result is not rational (e.g. `2^(1/2)`)
+/// and the caller has to fall back to f64.
+pub fn binary(op: Operation, left: &Fraction, right: &Fraction) -> Option<Fraction> {
+  use super::parsers::Operation::*;
+
+  let (l, r) = (left.to_rational(), right.to_rational());
+  let result = match op {
+    Plus => l + r,
+    Minus => l - r,
+    Times => l * r,
+    Div if r.is_zero() => return None,
+    Div => l / r,
+    // Same sign convention as `f64`'s `%`: the result has the sign of the dividend
+    Mod if r.is_zero() => return None,
+    Mod => &l - &r * (&l / &r).trunc(),
+    Pow if r.is_integer() && !pow_too_large(left, right) => {
+      let exp = r.to_integer().to_i32()?;
+      if exp < 0 && l.is_zero() {
+        return None;
+      }
+      l.pow(exp)
+    }
+    _ => return None,
+  };
+  Some(Fraction::new(result))
+}
+
+/// Exact factorial of a non-negative integer, `None` for everything else.
+pub fn factorial(x: &Fraction) -> Option<Fraction> {
+  if !x.denom.is_one() || x.numer.is_negative() {
+    return None;
+  }
+  let n = x.numer.to_u64().filter(|&n| n <= 10_000)?;
+  let product = (2..=n).fold(BigInt::one(), |acc, k| acc * k);
+  Some(Fraction::new(BigRational::from_integer(product)))
+}
+
+mod bigint_repr {
+  use num_bigint::BigInt;
+  use serde::{Deserialize, Deserializer, Serializer};
+  use std::str::FromStr;
+
+  pub fn serialize<S: Serializer>(n: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&n.to_string())
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BigInt::from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::parsers::Operation::*;
+  use super::*;
+
+  fn frac(numer: i64, denom: i64) -> Fraction {
+    Fraction::new(BigRational::new(numer.into(), denom.into()))
+  }
+
+  #[test]
+  fn test_binary() {
+    assert_eq!(binary(Plus, &frac(1, 3), &frac(1, 6)), Some(frac(1, 2)));
+    assert_eq!(binary(Minus, &frac(1, 3), &frac(1, 2)), Some(frac(-1, 6)));
+    assert_eq!(binary(Times, &frac(2, 3), &frac(3, 4)), Some(frac(1, 2)));
+    assert_eq!(binary(Div, &frac(1, 3), &frac(2, 1)), Some(frac(1, 6)));
+    assert_eq!(binary(Mod, &frac(7, 2), &frac(1, 1)), Some(frac(1, 2)));
+    assert_eq!(binary(Mod, &frac(-7, 2), &frac(1, 1)), Some(frac(-1, 2)));
+    assert_eq!(binary(Pow, &frac(2, 3), &frac(-2, 1)), Some(frac(9, 4)));
+    assert_eq!(binary(Div, &frac(1, 1), &frac(0, 1)), None);
+    assert_eq!(binary(Pow, &frac(2, 1), &frac(1, 2)), None);
+    assert_eq!(binary(Pow, &frac(1, 3), &frac(1_000_000, 1)), None);
+    assert!(pow_too_large(&frac(1, 3), &frac(-1_000_000, 1)));
+    assert!(!pow_too_large(&frac(2, 1), &frac(1000, 1)));
+    assert!(!pow_too_large(&frac(-1, 1), &frac(i64::MAX, 1)));
+  }
+
+  #[test]
+  fn test_conversions() {
+    assert_eq!(Fraction::from_literal("0.25"), Some(frac(1, 4)));
+    assert_eq!(Fraction::from_literal("-1.5"), Some(frac(-3, 2)));
+    assert_eq!(Fraction::from_literal("3."), Some(frac(3, 1)));
+    assert_eq!(Fraction::from_literal("1.5e2"), Some(frac(150, 1)));
+    assert_eq!(
+      Fraction::from_literal("0.1000000000000000000001").map(|f| f.to_string()),
+      Some("1000000000000000000001/10000000000000000000000".into())
+    );
+    assert_eq!(Fraction::from_value(&Value::Real(4.)), Some(frac(4, 1)));
+    assert_eq!(Fraction::from_value(&Value::Real(0.5)), None);
+    assert_eq!(frac(1, 3).to_f64(), 1. / 3.);
+    assert_eq!(factorial(&frac(5, 1)), Some(frac(120, 1)));
+    assert_eq!(factorial(&frac(1, 2)), None);
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(frac(3, 6).to_string(), "1/2");
+    assert_eq!(frac(4, 2).to_string(), "2");
+    assert_eq!(frac(3, 2).to_string(), "3/2");
+    assert_eq!(frac(3, 2).to_mixed().to_string(), "1 1/2");
+    assert_eq!(frac(-7, 3).to_mixed().to_string(), "-2 1/3");
+    assert_eq!(frac(1, 3).to_mixed().to_string(), "1/3");
+  }
+}
@@ -0,0 +1,265 @@
+//! Output representations requested with a `to hex`-like suffix, `255 to hex` is shown as `0xff`
+//! while `ans` keeps the number.
+use super::value::Value;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a result is shown, the suffix of `expr to hex|bin|oct|base N|roman|sci`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Representation {
+  /// Digits in a base from 2 to 36, e.g. 16 for `to hex`.
+  Radix(u32),
+  /// Roman numerals, for integers from 1 to 3999.
+  Roman,
+  /// Scientific notation whatever the notation of the display settings.
+  Scientific,
+}
+
+/// A result that cannot be shown in the requested representation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepresentationError {
+  /// Bases go from 2 to 36, the digits being `0-9a-z`.
+  InvalidBase(u32),
+  /// Only real numbers have digits, e.g. not `5 km` or `3+4i`.
+  NotANumber(Representation),
+  /// Roman numerals only write the integers from 1 to 3999.
+  NotRoman,
+  /// A result has a single representation, e.g. not `10 to hex to bin`.
+  Chained(Representation, Representation),
+}
+
+impl fmt::Display for RepresentationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      RepresentationError::InvalidBase(base) => {
+        write!(f, "base {} is not supported, bases go from 2 to 36", base)
+      }
+      RepresentationError::NotANumber(representation) => {
+        write!(f, "only real numbers can be converted to {}", representation)
+      }
+      RepresentationError::NotRoman => {
+        write!(f, "roman numerals only write integers from 1 to 3999")
+      }
+      RepresentationError::Chained(first, second) => {
+        write!(f, "a result is shown in one representation, not in {} then {}", first, second)
+      }
+    }
+  }
+}
+
+impl std::error::Error for RepresentationError {}
+
+impl fmt::Display for Representation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Representation::Radix(16) => write!(f, "hex"),
+      Representation::Radix(2) => write!(f, "bin"),
+      Representation::Radix(8) => write!(f, "oct"),
+      Representation::Radix(base) => write!(f, "base {}", base),
+      Representation::Roman => write!(f, "roman"),
+      Representation::Scientific => write!(f, "sci"),
+    }
+  }
+}
+
+impl FromStr for Representation {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match super::parsers::representation(s) {
+      Ok(("", representation)) => Ok(representation),
+      _ => Err(format!("Unknown representation `{}`", s)),
+    }
+  }
+}
+
+impl From<Representation> for String {
+  fn from(representation: Representation) -> String {
+    representation.to_string()
+  }
+}
+
+impl TryFrom<String> for Representation {
+  type Error = String;
+
+  fn try_from(s: String) -> Result<Self, Self::Error> {
+    s.parse()
+  }
+}
+
+impl Representation {
+  /// Checks that the value can be shown in the representation.
+  pub fn check(&self, value: &Value) -> Result<(), RepresentationError> {
+    match *self {
+      Representation::Scientific => Ok(()),
+      Representation::Radix(base) if !(2..=36).contains(&base) => {
+        Err(RepresentationError::InvalidBase(base))
+      }
+      Representation::Radix(_) => {
+        to_rational(value).map(|_| ()).ok_or(RepresentationError::NotANumber(*self))
+      }
+      Representation::Roman => roman(value).map(|_| ()),
+    }
+  }
+
+  /// Writes the digits of a real value, with at most `places` digits after the point, or its
+  /// roman numeral. Scientific notation is left to the display settings.
+  pub fn render(&self, value: &Value, places: usize) -> Result<String, RepresentationError> {
+    self.check(value)?;
+    match *self {
+      Representation::Radix(base) => {
+        let x = to_rational(value).ok_or(RepresentationError::NotANumber(*self))?;
+        Ok(radix(&x, base, places))
+      }
+      Representation::Roman => roman(value),
+      Representation::Scientific => Ok(value.to_string()),
+    }
+  }
+}
+
+/// The exact value of a real number, floats being dyadic fractions.
+fn to_rational(value: &Value) -> Option<BigRational> {
+  match *value {
+    Value::Real(x) => BigRational::from_float(x),
+    Value::Integer(ref n) => Some(BigRational::from_integer(n.clone())),
+    Value::Fraction(ref f) => Some(f.to_rational()),
+    Value::Decimal(ref d) => {
+      let (digits, scale) = d.decimal.as_bigint_and_exponent();
+      let ten = BigInt::from(10);
+      Some(match scale {
+        scale if scale >= 0 => BigRational::new(digits, num_traits::pow(ten, scale as usize)),
+        scale => {
+          BigRational::from_integer(digits * num_traits::pow(ten, scale.unsigned_abs() as usize))
+        }
+      })
+    }
+    _ => None,
+  }
+}
+
+/// `0xff.8`, digits that do not fit in the places end with `…`.
+fn radix(x: &BigRational, base: u32, places: usize) -> String {
+  let prefix = match base {
+    16 => "0x",
+    8 => "0o",
+    2 => "0b",
+    _ => "",
+  };
+  let sign = if x.is_negative() { "-" } else { "" };
+  let x = x.abs();
+  let integer = x.to_integer();
+  let mut s = format!("{}{}{}", sign, prefix, integer.to_str_radix(base));
+  let mut fraction = x.fract();
+  if !fraction.is_zero() && places > 0 {
+    s.push('.');
+    let base_rational = BigRational::from_integer(BigInt::from(base));
+    for _ in 0..places {
+      fraction *= &base_rational;
+      let digit = fraction.to_integer();
+      fraction -= BigRational::from_integer(digit.clone());
+      let digit = digit.to_u32().expect("Digits are smaller than the base");
+      s.push(std::char::from_digit(digit, base).expect("Digits are smaller than the base"));
+      if fraction.is_zero() {
+        break;
+      }
+    }
+  }
+  if !fraction.is_zero() {
+    s.push('…');
+  }
+  if !prefix.is_empty() || base == 10 {
+    return s;
+  }
+  format!("{} (base {})", s, base)
+}
+
+const NUMERALS: [(u32, &str); 13] = [
+  (1000, "M"),
+  (900, "CM"),
+  (500, "D"),
+  (400, "CD"),
+  (100, "C"),
+  (90, "XC"),
+  (50, "L"),
+  (40, "XL"),
+  (10, "X"),
+  (9, "IX"),
+  (5, "V"),
+  (4, "IV"),
+  (1, "I"),
+];
+
+fn roman(value: &Value) -> Result<String, RepresentationError> {
+  let mut n = to_rational(value)
+    .filter(BigRational::is_integer)
+    .and_then(|x| x.to_integer().to_u32())
+    .filter(|n| (1..=3999).contains(n))
+    .ok_or(RepresentationError::NotRoman)?;
+  let mut s = String::new();
+  for &(step, numeral) in NUMERALS.iter() {
+    let (count, rest) = n.div_rem(&step);
+    s.push_str(&numeral.repeat(count as usize));
+    n = rest;
+  }
+  Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::decimal::Decimal;
+  use super::super::rational::Fraction;
+  use super::*;
+
+  #[test]
+  fn test_radix() {
+    let render =
+      |x: f64, representation: Representation| representation.render(&Value::Real(x), 10);
+    assert_eq!(render(255., Representation::Radix(16)), Ok("0xff".into()));
+    assert_eq!(render(-10., Representation::Radix(2)), Ok("-0b1010".into()));
+    assert_eq!(render(8.5, Representation::Radix(8)), Ok("0o10.4".into()));
+    assert_eq!(render(255.5, Representation::Radix(16)), Ok("0xff.8".into()));
+    assert_eq!(render(0.1, Representation::Radix(2)), Ok("0b0.0001100110…".into()));
+    assert_eq!(render(1295., Representation::Radix(36)), Ok("zz (base 36)".into()));
+    assert_eq!(
+      Representation::Radix(3).render(&Value::Fraction(Fraction::from_literal("0.5").unwrap()), 4),
+      Ok("0.1111… (base 3)".into())
+    );
+    assert_eq!(
+      Representation::Radix(16).render(&Value::Integer("18446744073709551616".parse().unwrap()), 0),
+      Ok("0x10000000000000000".into())
+    );
+    let decimal = Value::Decimal(Decimal::exact("2.75".parse().unwrap()));
+    assert_eq!(Representation::Radix(2).render(&decimal, 10), Ok("0b10.11".into()));
+    assert_eq!(render(1., Representation::Radix(37)), Err(RepresentationError::InvalidBase(37)));
+    assert_eq!(
+      render(f64::NAN, Representation::Radix(16)),
+      Err(RepresentationError::NotANumber(Representation::Radix(16)))
+    );
+  }
+
+  #[test]
+  fn test_roman() {
+    let render = |x: f64| Representation::Roman.render(&Value::Real(x), 0);
+    assert_eq!(render(1994.), Ok("MCMXCIV".into()));
+    assert_eq!(render(3999.), Ok("MMMCMXCIX".into()));
+    assert_eq!(render(4.), Ok("IV".into()));
+    assert_eq!(render(0.), Err(RepresentationError::NotRoman));
+    assert_eq!(render(2.5), Err(RepresentationError::NotRoman));
+    assert_eq!(render(4000.), Err(RepresentationError::NotRoman));
+  }
+
+  #[test]
+  fn test_parse() {
+    for s in ["hex", "bin", "oct", "base 36", "roman", "sci"] {
+      assert_eq!(s.parse::<Representation>().map(|r| r.to_string()), Ok(s.into()));
+    }
+    assert_eq!("base 16".parse(), Ok(Representation::Radix(16)));
+    assert!("hexadecimal".parse::<Representation>().is_err());
+  }
+}
@@ -0,0 +1,279 @@
+//! Rounding to a number of digits or to a multiple, e.g. `round(3.14159, 2)` or
+//! `mround(17, 5)`, and the small numeric helpers that go with it (`clamp`, `lerp`, `nthroot`...).
+use super::extra_math::DomainError;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive};
+
+/// The rounding builtins, all of them take the number of digits as an optional second argument.
+pub const MODES: [(&str, RoundingMode); 7] = [
+  // Half away from zero, `round(2.5)` is 3 and `round(-2.5)` is -3
+  ("round", RoundingMode::HalfUp),
+  // Banker's rounding, half to even, `roundeven(2.5)` is 2
+  ("roundeven", RoundingMode::HalfEven),
+  // Away from zero whatever the sign, `roundup(-2.1)` is -3
+  ("roundup", RoundingMode::Up),
+  // Toward zero whatever the sign, `rounddown(-2.9)` is -2
+  ("rounddown", RoundingMode::Down),
+  ("trunc", RoundingMode::Down),
+  ("floor", RoundingMode::Floor),
+  ("ceil", RoundingMode::Ceiling),
+];
+
+/// Digits are limited to the exponents of an f64.
+const MAX_DIGITS: f64 = 308.;
+
+/// Above 2^52 every f64 is an integer, there is nothing left to round.
+const MAX_FRACTIONAL: f64 = 4503599627370496.;
+
+/// Removes the binary noise of a scaled number, so that `1.005 * 100` is 100.5 and not
+/// 100.49999999999999 before it is rounded.
+fn clean(x: f64) -> f64 {
+  format!("{:.14e}", x).parse().unwrap_or(x)
+}
+
+fn digits(d: f64) -> Result<i32, DomainError> {
+  if d.fract() != 0. {
+    return Err(DomainError::NotInteger(d));
+  }
+  if d.abs() > MAX_DIGITS {
+    return Err(DomainError::OutOfRange(d, -MAX_DIGITS, MAX_DIGITS));
+  }
+  Ok(d as i32)
+}
+
+fn round_float(x: f64, mode: RoundingMode) -> f64 {
+  match mode {
+    RoundingMode::Up if x.fract() != 0. => x.trunc() + x.signum(),
+    RoundingMode::Up | RoundingMode::Down => x.trunc(),
+    RoundingMode::Floor => x.floor(),
+    RoundingMode::Ceiling => x.ceil(),
+    RoundingMode::HalfUp => x.round(),
+    RoundingMode::HalfDown if x.fract().abs() == 0.5 => x.trunc(),
+    RoundingMode::HalfDown => x.round(),
+    RoundingMode::HalfEven => x.round_ties_even(),
+  }
+}
+
+/// Rounds `x` to `digits` digits after the point, or before it when `digits` is negative.
+pub fn round_to(x: f64, digits: i32, mode: RoundingMode) -> f64 {
+  if !x.is_finite() {
+    return x;
+  }
+  if digits == 0 {
+    // Adding 0 turns `-0` into `0`
+    return round_float(x, mode) + 0.;
+  }
+  let scale = 10f64.powi(digits.abs());
+  let y = if digits > 0 { x * scale } else { x / scale };
+  if y.abs() >= MAX_FRACTIONAL {
+    return x;
+  }
+  let y = round_float(clean(y), mode);
+  (if digits > 0 { y / scale } else { y * scale }) + 0.
+}
+
+/// The f64 implementation of a rounding builtin, `round(x)` or `round(x, digits)`.
+pub fn rounder(mode: RoundingMode) -> impl Fn(&[f64]) -> Result<f64, DomainError> {
+  move |xs: &[f64]| match *xs {
+    [x] => Ok(round_to(x, 0, mode)),
+    [x, d] => Ok(round_to(x, digits(d)?, mode)),
+    _ => unreachable!("Rounding builtins take one or two arguments"),
+  }
+}
+
+/// The exact decimal implementation of a rounding builtin, `None` when the digits are invalid.
+///
+/// Numbers that already have fewer digits are kept as they are, `round(2.5, 3)` is not `2.500`.
+pub fn round_decimal(xs: &[BigDecimal], mode: RoundingMode) -> Option<BigDecimal> {
+  let (x, digits) = match xs {
+    [x] => (x, 0),
+    [x, d] if d.is_integer() => (x, d.to_i64().filter(|d| d.abs() <= MAX_DIGITS as i64)?),
+    _ => return None,
+  };
+  if x.as_bigint_and_exponent().1 <= digits {
+    return Some(x.clone());
+  }
+  let rounded = x.with_scale_round(digits, mode);
+  // A negative scale would be displayed as `1.2E+3`
+  Some(if digits < 0 { rounded.with_scale(0) } else { rounded })
+}
+
+fn round_rational(x: &BigRational, mode: RoundingMode) -> BigRational {
+  let half = BigRational::new(1.into(), 2.into());
+  let is_half = x.fract().abs() == half;
+  match mode {
+    RoundingMode::Up if !x.is_integer() => x.trunc() + x.signum(),
+    RoundingMode::Up | RoundingMode::Down => x.trunc(),
+    RoundingMode::Floor => x.floor(),
+    RoundingMode::Ceiling => x.ceil(),
+    RoundingMode::HalfUp => x.round(),
+    RoundingMode::HalfDown if is_half => x.trunc(),
+    RoundingMode::HalfEven if is_half && x.trunc().to_integer().is_even() => x.trunc(),
+    RoundingMode::HalfEven if is_half => x.trunc() + x.signum(),
+    RoundingMode::HalfDown | RoundingMode::HalfEven => x.round(),
+  }
+}
+
+/// The exact rational implementation of a rounding builtin, `None` when the digits are invalid.
+pub fn round_fraction(xs: &[BigRational], mode: RoundingMode) -> Option<BigRational> {
+  let (x, digits) = match xs {
+    [x] => return Some(round_rational(x, mode)),
+    [x, d] if d.is_integer() => (x, d.to_integer().to_i32().filter(|d| d.abs() <= 308)?),
+    _ => return None,
+  };
+  let scale =
+    BigRational::from_integer(num_traits::pow(BigInt::from(10), digits.unsigned_abs() as usize));
+  Some(if digits >= 0 {
+    round_rational(&(x * &scale), mode) / scale
+  } else {
+    round_rational(&(x / &scale), mode) * scale
+  })
+}
+
+fn to_multiple(x: f64, step: f64) -> f64 {
+  let q = x / step;
+  let q = if q.abs() < MAX_FRACTIONAL { clean(q).round() } else { q };
+  // `3 * 0.1` is 0.30000000000000004, the noise of the product is removed as well
+  clean(q * step) + 0.
+}
+
+/// `roundto(x, step)`, the multiple of `step` the closest to `x`, e.g. `roundto(7.3, 0.25)` is
+/// 7.25. The sign of the step does not matter.
+pub fn roundto(xs: &[f64]) -> Result<f64, DomainError> {
+  let (x, step) = (xs[0], xs[1]);
+  if step == 0. {
+    return Err(DomainError::DivisionByZero);
+  }
+  Ok(to_multiple(x, step.abs()))
+}
+
+/// `mround(x, multiple)`, the spreadsheet function: `x` and the multiple must have the same sign
+/// and a multiple of 0 gives 0.
+pub fn mround(xs: &[f64]) -> Result<f64, DomainError> {
+  let (x, multiple) = (xs[0], xs[1]);
+  if multiple == 0. {
+    return Ok(0.);
+  }
+  if x != 0. && x.signum() != multiple.signum() {
+    return Err(DomainError::SignMismatch(x, multiple));
+  }
+  Ok(to_multiple(x, multiple))
+}
+
+/// `clamp(x, lo, hi)`, `x` limited to `[lo, hi]`.
+pub fn clamp(xs: &[f64]) -> Result<f64, DomainError> {
+  let (x, lo, hi) = (xs[0], xs[1], xs[2]);
+  // A NaN bound is also rejected, it would make `f64::clamp` panic
+  if lo.partial_cmp(&hi).is_none_or(|order| order.is_gt()) {
+    return Err(DomainError::OutOfRange(lo, f64::NEG_INFINITY, hi));
+  }
+  Ok(x.clamp(lo, hi))
+}
+
+/// `lerp(a, b, t)`, the linear interpolation from `a` at `t = 0` to `b` at `t = 1`.
+pub fn lerp(xs: &[f64]) -> f64 {
+  let (a, b, t) = (xs[0], xs[1], xs[2]);
+  a + (b - a) * t
+}
+
+/// `hypot(x, y, ...)`, the euclidean norm without the overflow of the squares.
+pub fn hypot(xs: &[f64]) -> f64 {
+  xs.iter().fold(0., |norm, &x| norm.hypot(x))
+}
+
+/// `nthroot(x, n)`, the real `n`-th root, defined for negative numbers when `n` is odd:
+/// `nthroot(-8, 3)` is -2 while `(-8)^(1/3)` is NaN.
+pub fn nthroot(xs: &[f64]) -> Result<f64, DomainError> {
+  let (x, n) = (xs[0], xs[1]);
+  if n.fract() != 0. {
+    return Err(DomainError::NotInteger(n));
+  }
+  if n == 0. {
+    return Err(DomainError::DivisionByZero);
+  }
+  let odd = n % 2. != 0.;
+  if x < 0. && !odd {
+    return Err(DomainError::Negative(x));
+  }
+  let root = x.abs().powf(1. / n).copysign(x);
+  // `27^(1/3)` is 3.0000000000000004, exact roots are given exactly
+  let rounded = root.round();
+  Ok(if n.abs() <= i32::MAX as f64 && rounded.powi(n as i32) == x { rounded } else { root })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_round_to() {
+    assert_eq!(round_to(1.23456, 2, RoundingMode::HalfUp), 1.23);
+    assert_eq!(round_to(1.005, 2, RoundingMode::HalfUp), 1.01);
+    assert_eq!(round_to(1234.5, -2, RoundingMode::HalfUp), 1200.);
+    assert_eq!(round_to(0.29, 2, RoundingMode::Floor), 0.29);
+    assert_eq!(round_to(2.5, 0, RoundingMode::HalfEven), 2.);
+    assert_eq!(round_to(0.125, 2, RoundingMode::HalfEven), 0.12);
+    assert_eq!(round_to(-2.1, 0, RoundingMode::Up), -3.);
+    assert_eq!(round_to(-2.9, 0, RoundingMode::Down), -2.);
+    assert_eq!(round_to(-0.4, 0, RoundingMode::HalfUp).to_string(), "0");
+    assert_eq!(round_to(1e300, 10, RoundingMode::HalfUp), 1e300);
+    assert!(round_to(f64::NAN, 2, RoundingMode::HalfUp).is_nan());
+    assert_eq!(rounder(RoundingMode::HalfUp)(&[1., 0.5]), Err(DomainError::NotInteger(0.5)));
+  }
+
+  #[test]
+  fn test_round_exact() {
+    let decimal = |s: &str| s.parse::<BigDecimal>().unwrap();
+    let round = |x: &str, d: &str, mode| {
+      round_decimal(&[decimal(x), decimal(d)], mode).map(|d| d.to_string())
+    };
+    assert_eq!(round("2.675", "2", RoundingMode::HalfUp), Some("2.68".into()));
+    assert_eq!(round("2.665", "2", RoundingMode::HalfEven), Some("2.66".into()));
+    assert_eq!(round("1250", "-2", RoundingMode::HalfEven), Some("1200".into()));
+    assert_eq!(round("2.5", "3", RoundingMode::HalfUp), Some("2.5".into()));
+    assert_eq!(round("2.5", "0.5", RoundingMode::HalfUp), None);
+
+    let fraction = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+    let round = |x, mode| round_fraction(&[x], mode);
+    assert_eq!(round(fraction(5, 2), RoundingMode::HalfEven), Some(fraction(2, 1)));
+    assert_eq!(round(fraction(7, 2), RoundingMode::HalfEven), Some(fraction(4, 1)));
+    assert_eq!(round(fraction(-5, 2), RoundingMode::HalfDown), Some(fraction(-2, 1)));
+    assert_eq!(round(fraction(-7, 3), RoundingMode::Up), Some(fraction(-3, 1)));
+    assert_eq!(
+      round_fraction(&[fraction(1, 3), fraction(2, 1)], RoundingMode::HalfUp),
+      Some(fraction(33, 100))
+    );
+  }
+
+  #[test]
+  fn test_multiples() {
+    assert_eq!(roundto(&[7.3, 0.25]), Ok(7.25));
+    assert_eq!(roundto(&[0.29, 0.1]), Ok(0.3));
+    assert_eq!(roundto(&[-7.5, -5.]), Ok(-10.));
+    assert_eq!(roundto(&[1., 0.]), Err(DomainError::DivisionByZero));
+    assert_eq!(mround(&[17., 5.]), Ok(15.));
+    assert_eq!(mround(&[-17.5, -5.]), Ok(-20.));
+    assert_eq!(mround(&[3., 0.]), Ok(0.));
+    assert_eq!(mround(&[-7., 2.]), Err(DomainError::SignMismatch(-7., 2.)));
+  }
+
+  #[test]
+  fn test_helpers() {
+    assert_eq!(clamp(&[5., 0., 3.]), Ok(3.));
+    assert_eq!(clamp(&[-1., 0., 3.]), Ok(0.));
+    assert!(clamp(&[1., 3., 0.]).is_err());
+    assert!(clamp(&[1., f64::NAN, 0.]).is_err());
+    assert_eq!(lerp(&[10., 20., 0.25]), 12.5);
+    assert_eq!(hypot(&[3., 4.]), 5.);
+    assert_eq!(hypot(&[2., 3., 6.]), 7.);
+    assert_eq!(hypot(&[-2.]), 2.);
+    assert_eq!(nthroot(&[27., 3.]), Ok(3.));
+    assert_eq!(nthroot(&[-32., 5.]), Ok(-2.));
+    assert_eq!(nthroot(&[16., -4.]), Ok(0.5));
+    assert_eq!(nthroot(&[-16., 4.]), Err(DomainError::Negative(-16.)));
+    assert_eq!(nthroot(&[8., 1.5]), Err(DomainError::NotInteger(1.5)));
+  }
+}
@@ -0,0 +1,34 @@
+//! The layers of a context. Each layer holds the names defined at one level: the builtins, the
+//! loaded libraries and the variables of the workspace. A name is looked up from the top layer
+//! down, and definitions go to the top layer.
+use super::context::{
+  ComplexFunc, ContextHashMap, DecimalFunc, FractionFunc, GuardedFunc, Overloads, ValueFunc,
+};
+use super::signatures::Signature;
+use super::units::Unit;
+use super::value::Value;
+
+/// The variables, constants, functions and units defined in one layer of a context.
+#[derive(Clone, Default)]
+pub struct Scope<'a> {
+  pub(super) vars: ContextHashMap<String, Value>,
+  /// Read-only values, `pi` and `e` and the user constants declared with `const`.
+  pub(super) constants: ContextHashMap<String, Value>,
+  /// The expressions of the variables defined with `:=`, their values are in `vars`.
+  pub(super) formulas: ContextHashMap<String, String>,
+  pub(super) funcs: ContextHashMap<String, Overloads<GuardedFunc<'a>>>,
+  pub(super) complex_funcs: ContextHashMap<String, ComplexFunc<'a>>,
+  pub(super) value_funcs: ContextHashMap<String, Overloads<ValueFunc<'a>>>,
+  pub(super) decimal_funcs: ContextHashMap<String, DecimalFunc<'a>>,
+  pub(super) fraction_funcs: ContextHashMap<String, FractionFunc<'a>>,
+  /// The documentation of the functions, for `help` and the tooltips of the input.
+  pub(super) signatures: ContextHashMap<String, Overloads<Signature>>,
+  pub(super) units: ContextHashMap<String, Unit>,
+}
+
+impl<'a> Scope<'a> {
+  /// Whether the layer implements a function, it hides the implementations of the layers below.
+  pub fn defines_func(&self, name: &str) -> bool {
+    self.funcs.contains_key(name) || self.value_funcs.contains_key(name)
+  }
+}
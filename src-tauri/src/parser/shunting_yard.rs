@@ -76,10 +76,12 @@ fn prec_assoc(token: &Token) -> (u32, Associativity) {
     },
     Unary(op) => match op {
       Plus | Minus => (3, NA),
-      Fact => (5, NA),
+      Fact | DoubleFact => (5, NA),
       _ => unimplemented!(),
     },
-    Var(_) | Number(_) | Func(..) | LParen | RParen | Comma => (0, NA),
+    Unit(..) => (5, NA),
+    Var(_) | Number(..) | Imaginary(_) | Date(..) | Duration(_) | Func(..) | LParen | RParen
+    | Comma | Convert(_) => (0, NA),
   }
 }
 
@@ -97,8 +99,17 @@ pub fn to_rpn(input: &[Token]) -> Result<Vec<Token>, RPNError> {
   for (index, token) in input.iter().enumerate() {
     let token = token.clone();
     match token {
-      Number(_) | Var(_) => output.push(token),
-      Unary(_) => stack.push((index, token)),
+      Number(..) | Imaginary(_) | Date(..) | Duration(_) | Var(_) | Func(_, Some(0)) => {
+        output.push(token)
+      }
+      Unary(_) | Unit(..) => stack.push((index, token)),
+      // A conversion applies to everything on its left up to the enclosing parenthesis
+      Convert(_) => {
+        while let Some((_, Unary(_) | Binary(_) | Unit(..))) = stack.last() {
+          output.push(stack.pop().unwrap().1);
+        }
+        output.push(token);
+      }
       Binary(_) => {
         let pa1 = prec_assoc(&token);
         while !stack.is_empty() {
@@ -163,7 +174,7 @@ pub fn to_rpn(input: &[Token]) -> Result<Vec<Token>, RPNError> {
 
   while let Some((index, token)) = stack.pop() {
     match token {
-      Unary(_) | Binary(_) => output.push(token),
+      Unary(_) | Binary(_) | Unit(..) => output.push(token),
       LParen | Func(..) => return Err(RPNError::MismatchedLParen(index)),
       _ => panic!("Unexpected token on stack."),
     }
@@ -173,8 +184,8 @@ pub fn to_rpn(input: &[Token]) -> Result<Vec<Token>, RPNError> {
   let mut n_operands = 0isize;
   for (index, token) in output.iter().enumerate() {
     match *token {
-      Var(_) | Number(_) => n_operands += 1,
-      Unary(_) => (),
+      Var(_) | Number(..) | Imaginary(_) | Date(..) | Duration(_) => n_operands += 1,
+      Unary(_) | Unit(..) | Convert(_) => (),
       Binary(_) => n_operands -= 1,
       Func(_, Some(n_args)) => n_operands -= n_args as isize - 1,
       _ => panic!("Nothing else should be here"),
@@ -198,38 +209,42 @@ mod tests {
   use super::super::parsers::Token::*;
   use super::*;
 
+  fn num(x: f64) -> Token {
+    Token::Number(x, x.to_string())
+  }
+
   #[test]
   fn test_to_rpn() {
-    assert_eq!(to_rpn(&[Number(1.)]), Ok(vec![Number(1.)]));
+    assert_eq!(to_rpn(&[num(1.)]), Ok(vec![num(1.)]));
     assert_eq!(
-      to_rpn(&[Number(1.), Binary(Plus), Number(2.)]),
-      Ok(vec![Number(1.), Number(2.), Binary(Plus)])
+      to_rpn(&[num(1.), Binary(Plus), num(2.)]),
+      Ok(vec![num(1.), num(2.), Binary(Plus)])
     );
     assert_eq!(
-      to_rpn(&[Unary(Minus), Number(1.), Binary(Pow), Number(2.)]),
-      Ok(vec![Number(1.), Number(2.), Binary(Pow), Unary(Minus)])
+      to_rpn(&[Unary(Minus), num(1.), Binary(Pow), num(2.)]),
+      Ok(vec![num(1.), num(2.), Binary(Pow), Unary(Minus)])
     );
     assert_eq!(
-      to_rpn(&[Number(1.), Unary(Fact), Binary(Pow), Number(2.)]),
-      Ok(vec![Number(1.), Unary(Fact), Number(2.), Binary(Pow)])
+      to_rpn(&[num(1.), Unary(Fact), Binary(Pow), num(2.)]),
+      Ok(vec![num(1.), Unary(Fact), num(2.), Binary(Pow)])
     );
     assert_eq!(
       to_rpn(&[
-        Number(1.),
+        num(1.),
         Unary(Fact),
         Binary(Div),
         LParen,
-        Number(2.),
+        num(2.),
         Binary(Plus),
-        Number(3.),
+        num(3.),
         RParen,
         Unary(Fact)
       ]),
       Ok(vec![
-        Number(1.),
+        num(1.),
         Unary(Fact),
-        Number(2.),
-        Number(3.),
+        num(2.),
+        num(3.),
         Binary(Plus),
         Unary(Fact),
         Binary(Div)
@@ -237,16 +252,16 @@ mod tests {
     );
     assert_eq!(
       to_rpn(&[
-        Number(3.),
+        num(3.),
         Binary(Minus),
-        Number(1.),
+        num(1.),
         Binary(Times),
-        Number(2.)
+        num(2.)
       ]),
       Ok(vec![
-        Number(3.),
-        Number(1.),
-        Number(2.),
+        num(3.),
+        num(1.),
+        num(2.),
         Binary(Times),
         Binary(Minus)
       ])
@@ -254,32 +269,32 @@ mod tests {
     assert_eq!(
       to_rpn(&[
         LParen,
-        Number(3.),
+        num(3.),
         Binary(Minus),
-        Number(1.),
+        num(1.),
         RParen,
         Binary(Times),
-        Number(2.)
+        num(2.)
       ]),
       Ok(vec![
-        Number(3.),
-        Number(1.),
+        num(3.),
+        num(1.),
         Binary(Minus),
-        Number(2.),
+        num(2.),
         Binary(Times)
       ])
     );
     assert_eq!(
       to_rpn(&[
-        Number(1.),
+        num(1.),
         Binary(Minus),
         Unary(Minus),
         Unary(Minus),
-        Number(2.)
+        num(2.)
       ]),
       Ok(vec![
-        Number(1.),
-        Number(2.),
+        num(1.),
+        num(2.),
         Unary(Minus),
         Unary(Minus),
         Binary(Minus)
@@ -289,23 +304,41 @@ mod tests {
       to_rpn(&[Var("x".into()), Binary(Plus), Var("y".into())]),
       Ok(vec![Var("x".into()), Var("y".into()), Binary(Plus)])
     );
+    assert_eq!(
+      to_rpn(&[
+        num(3.),
+        Unit("kW".into(), 1),
+        Binary(Times),
+        num(2.),
+        Unit("h".into(), 1),
+        Convert(vec![("kWh".into(), 1)])
+      ]),
+      Ok(vec![
+        num(3.),
+        Unit("kW".into(), 1),
+        num(2.),
+        Unit("h".into(), 1),
+        Binary(Times),
+        Convert(vec![("kWh".into(), 1)])
+      ])
+    );
 
     assert_eq!(
       to_rpn(&[
         Func("max".into(), None),
         Func("sin".into(), None),
-        Number(1f64),
+        num(1.),
         RParen,
         Comma,
         Func("cos".into(), None),
-        Number(2f64),
+        num(2.),
         RParen,
         RParen
       ]),
       Ok(vec![
-        Number(1f64),
+        num(1.),
         Func("sin".into(), Some(1)),
-        Number(2f64),
+        num(2.),
         Func("cos".into(), Some(1)),
         Func("max".into(), Some(2))
       ])
@@ -317,7 +350,7 @@ mod tests {
       Err(RPNError::NotEnoughOperands(0))
     );
     assert_eq!(
-      to_rpn(&[Var("x".into()), Number(1.)]),
+      to_rpn(&[Var("x".into()), num(1.)]),
       Err(RPNError::TooManyOperands)
     );
     assert_eq!(to_rpn(&[LParen]), Err(RPNError::MismatchedLParen(0)));
@@ -0,0 +1,272 @@
+//! The documentation of the functions, shown by the `help` command and in the tooltips of the
+//! input, e.g. `round(x, digits?)` with its description and an example.
+use super::context::Arity;
+use serde::Serialize;
+use std::fmt;
+
+/// The parameters, domain, description and example of a function taking some numbers of
+/// arguments.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Signature {
+  pub name: String,
+  /// Parameter names, optional ones end with `?` and the last one ends with `...` when it can be
+  /// repeated, e.g. `["x", "digits?"]` or `["rate", "v1..."]`.
+  pub params: Vec<String>,
+  pub arity: Arity,
+  /// The arguments the function is defined for, `None` when it is defined everywhere.
+  pub domain: Option<String>,
+  pub description: String,
+  /// A call and its result, e.g. `round(3.14159, 2) = 3.14`.
+  pub example: Option<String>,
+}
+
+impl Signature {
+  /// A documented signature, the arity is given by the parameters.
+  pub fn new(
+    name: &str,
+    params: &[&str],
+    domain: Option<&str>,
+    description: &str,
+    example: Option<&str>,
+  ) -> Signature {
+    let optional = params.iter().filter(|param| param.ends_with('?')).count();
+    let variadic = params.last().is_some_and(|param| param.ends_with("..."));
+    let required = params.len() - optional;
+    Signature {
+      name: name.into(),
+      params: params.iter().map(|param| param.to_string()).collect(),
+      arity: Arity { min: required, max: if variadic { None } else { Some(params.len()) } },
+      domain: domain.map(Into::into),
+      description: description.into(),
+      example: example.map(Into::into),
+    }
+  }
+
+  /// The signature of an undocumented function, e.g. `f(x, y)` for a user function of two
+  /// arguments.
+  pub fn generic(name: &str, arity: Arity) -> Signature {
+    let count = arity.max.unwrap_or(arity.min.max(1));
+    let mut params: Vec<String> = (0..count)
+      .map(|i| match count {
+        1..=3 => ["x", "y", "z"][i].to_string(),
+        _ => format!("x{}", i + 1),
+      })
+      .enumerate()
+      .map(|(i, param)| if i < arity.min || arity.max.is_none() { param } else { param + "?" })
+      .collect();
+    if let (None, Some(last)) = (arity.max, params.last_mut()) {
+      last.push_str("...");
+    }
+    Signature {
+      name: name.into(),
+      params,
+      arity,
+      domain: None,
+      description: String::new(),
+      example: None,
+    }
+  }
+
+  /// The call with the parameter names, e.g. `round(x, digits?)`.
+  pub fn usage(&self) -> String {
+    format!("{}({})", self.name, self.params.join(", "))
+  }
+}
+
+impl fmt::Display for Signature {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.usage())?;
+    if !self.description.is_empty() {
+      write!(f, ": {}", self.description)?;
+    }
+    if let Some(ref domain) = self.domain {
+      write!(f, "\nDomain: {}", domain)?;
+    }
+    if let Some(ref example) = self.example {
+      write!(f, "\nExample: {}", example)?;
+    }
+    Ok(())
+  }
+}
+
+type Entry =
+  (&'static str, &'static [&'static str], Option<&'static str>, &'static str, &'static str);
+
+/// Name, parameters, domain, description and example of the builtins.
+#[rustfmt::skip]
+const BUILTINS: &[Entry] = &[
+  ("sqrt", &["x"], Some("x ≥ 0, any number in complex mode"), "Square root", "sqrt(16) = 4"),
+  ("cbrt", &["x"], None, "Cube root, negative for negative numbers", "cbrt(-27) = -3"),
+  ("nthroot", &["x", "n"], Some("n a non-zero integer, x ≥ 0 when n is even"), "Real n-th root", "nthroot(-32, 5) = -2"),
+  ("exp", &["x"], None, "e raised to the power x", "exp(1) ≈ 2.718282"),
+  ("ln", &["x"], Some("x > 0, any number in complex mode"), "Natural logarithm", "ln(10) ≈ 2.302585"),
+  ("log10", &["x"], Some("x > 0, any number in complex mode"), "Base 10 logarithm", "log10(1000) = 3"),
+  ("log2", &["x"], Some("x > 0, any number in complex mode"), "Base 2 logarithm", "log2(1024) = 10"),
+  ("logn", &["base", "x"], Some("base > 0 and x > 0"), "Logarithm of x in the base", "logn(2, 8) = 3"),
+  ("abs", &["x"], None, "Absolute value, the modulus of a complex number", "abs(-3) = 3"),
+  ("signum", &["x"], None, "Sign of x, 1 for positive numbers and -1 for negative ones", "signum(-2.5) = -1"),
+  ("sin", &["x"], None, "Sine of an angle in the angle mode", "sin(pi / 2) = 1"),
+  ("cos", &["x"], None, "Cosine of an angle in the angle mode", "cos(0) = 1"),
+  ("tan", &["x"], None, "Tangent of an angle in the angle mode", "tan(pi / 4) ≈ 1"),
+  ("asin", &["x"], Some("-1 ≤ x ≤ 1, any number in complex mode"), "Inverse sine, in the angle mode", "asin(1) ≈ 1.570796"),
+  ("acos", &["x"], Some("-1 ≤ x ≤ 1, any number in complex mode"), "Inverse cosine, in the angle mode", "acos(1) = 0"),
+  ("atan", &["x"], None, "Inverse tangent, in the angle mode", "atan(1) ≈ 0.785398"),
+  ("atan2", &["y", "x"], None, "Angle of the point (x, y) from the x axis, in the angle mode", "atan2(1, 1) ≈ 0.785398"),
+  ("sinh", &["x"], None, "Hyperbolic sine", "sinh(0) = 0"),
+  ("cosh", &["x"], None, "Hyperbolic cosine", "cosh(0) = 1"),
+  ("tanh", &["x"], None, "Hyperbolic tangent", "tanh(0) = 0"),
+  ("asinh", &["x"], None, "Inverse hyperbolic sine", "asinh(0) = 0"),
+  ("acosh", &["x"], Some("x ≥ 1, any number in complex mode"), "Inverse hyperbolic cosine", "acosh(1) = 0"),
+  ("atanh", &["x"], Some("-1 < x < 1, any number in complex mode"), "Inverse hyperbolic tangent", "atanh(0) = 0"),
+  ("round", &["x", "digits?"], Some("digits an integer"), "Rounds half away from zero, to digits places after the point or before it when negative", "round(3.14159, 2) = 3.14"),
+  ("roundeven", &["x", "digits?"], Some("digits an integer"), "Banker's rounding, half to the even neighbour", "roundeven(2.5) = 2"),
+  ("roundup", &["x", "digits?"], Some("digits an integer"), "Rounds away from zero", "roundup(-2.1) = -3"),
+  ("rounddown", &["x", "digits?"], Some("digits an integer"), "Rounds toward zero", "rounddown(-2.9) = -2"),
+  ("trunc", &["x", "digits?"], Some("digits an integer"), "Drops the digits after the point, rounding toward zero", "trunc(2.567, 1) = 2.5"),
+  ("floor", &["x", "digits?"], Some("digits an integer"), "Rounds toward negative infinity", "floor(-2.5) = -3"),
+  ("ceil", &["x", "digits?"], Some("digits an integer"), "Rounds toward positive infinity", "ceil(2.1) = 3"),
+  ("fract", &["x"], None, "Fractional part, with the sign of x", "fract(3.75) = 0.75"),
+  ("roundto", &["x", "step"], Some("step ≠ 0"), "Closest multiple of the step", "roundto(7.3, 0.25) = 7.25"),
+  ("mround", &["x", "multiple"], Some("x and multiple of the same sign"), "Closest multiple, like the spreadsheet function", "mround(17, 5) = 15"),
+  ("clamp", &["x", "lo", "hi"], Some("lo ≤ hi"), "x limited to the interval [lo, hi]", "clamp(12, 0, 10) = 10"),
+  ("lerp", &["a", "b", "t"], None, "Linear interpolation from a at t = 0 to b at t = 1", "lerp(10, 20, 0.25) = 12.5"),
+  ("hypot", &["x..."], None, "Euclidean norm, the square root of the sum of the squares", "hypot(3, 4) = 5"),
+  ("erf", &["x"], None, "Error function", "erf(0) = 0"),
+  ("erfc", &["x"], None, "Complementary error function, 1 - erf(x)", "erfc(0) = 1"),
+  ("gamma", &["x"], Some("x not 0 or a negative integer"), "Gamma function, (x - 1)! for integers", "gamma(5) = 24"),
+  ("lgamma", &["x"], Some("x not 0 or a negative integer"), "Natural logarithm of the absolute value of the gamma function", "lgamma(10) ≈ 12.801827"),
+  ("digamma", &["x"], Some("x not 0 or a negative integer"), "Logarithmic derivative of the gamma function", "digamma(1) ≈ -0.577216"),
+  ("zeta", &["s"], Some("s ≠ 1"), "Riemann zeta function", "zeta(2) ≈ 1.644934"),
+  ("beta", &["a", "b"], Some("a and b not 0 or negative integers"), "Beta function, gamma(a) gamma(b) / gamma(a + b)", "beta(2, 3) ≈ 0.083333"),
+  ("max", &["x..."], None, "Largest argument", "max(3, 7, 5) = 7"),
+  ("min", &["x..."], None, "Smallest argument", "min(3, 7, 5) = 3"),
+  ("avg", &["x..."], None, "Arithmetic mean", "avg(1, 2, 3, 4) = 2.5"),
+  ("sum", &["x..."], None, "Sum of the arguments", "sum(1, 2, 3) = 6"),
+  ("sumsq", &["x..."], None, "Sum of the squares of the arguments", "sumsq(1, 2, 3) = 14"),
+  ("median", &["x..."], None, "Middle value, the mean of the two middle ones for an even count", "median(3, 1, 4, 2) = 2.5"),
+  ("mode", &["x..."], None, "Most frequent value, the smallest one on ties", "mode(1, 2, 2, 3) = 2"),
+  ("range", &["x..."], None, "Difference between the largest and the smallest values", "range(3, 9, 4) = 6"),
+  ("var", &["x1", "x2..."], None, "Sample variance", "var(2, 4, 4, 4, 5, 5, 7, 9) ≈ 4.571429"),
+  ("varp", &["x..."], None, "Population variance", "varp(2, 4, 4, 4, 5, 5, 7, 9) = 4"),
+  ("stdev", &["x1", "x2..."], None, "Sample standard deviation", "stdev(2, 4, 4, 4, 5, 5, 7, 9) ≈ 2.13809"),
+  ("stdevp", &["x..."], None, "Population standard deviation", "stdevp(2, 4, 4, 4, 5, 5, 7, 9) = 2"),
+  ("percentile", &["p", "x..."], Some("0 ≤ p ≤ 100"), "The p-th percentile, interpolated between the values", "percentile(50, 1, 2, 3, 4) = 2.5"),
+  ("quartiles", &["x..."], None, "First, second and third quartiles as a list", "quartiles(1, 2, 3, 4, 5) = [2, 3, 4]"),
+  ("iqr", &["x..."], None, "Interquartile range, the third quartile minus the first", "iqr(1, 2, 3, 4, 5) = 2"),
+  ("geomean", &["x..."], Some("x > 0"), "Geometric mean", "geomean(2, 8) = 4"),
+  ("harmmean", &["x..."], Some("x > 0"), "Harmonic mean", "harmmean(1, 4, 4) = 2"),
+  ("skew", &["x..."], None, "Population skewness", "skew(1, 2, 3) = 0"),
+  ("kurtosis", &["x..."], None, "Population excess kurtosis, 0 for a normal distribution", "kurtosis(1, 2, 3, 4) = -1.36"),
+  ("normpdf", &["x", "mu?", "sigma?"], Some("sigma > 0"), "Density of the normal distribution, the standard one by default", "normpdf(0) ≈ 0.398942"),
+  ("normcdf", &["x", "mu?", "sigma?"], Some("sigma > 0"), "Cumulative distribution of the normal distribution", "normcdf(1.96) ≈ 0.975"),
+  ("invnorm", &["p", "mu?", "sigma?"], Some("0 < p < 1 and sigma > 0"), "Quantile of the normal distribution", "invnorm(0.975) ≈ 1.959964"),
+  ("binompdf", &["n", "p", "k"], Some("n a natural number and 0 ≤ p ≤ 1"), "Probability of exactly k successes in n trials", "binompdf(10, 0.5, 5) ≈ 0.246094"),
+  ("binomcdf", &["n", "p", "k"], Some("n a natural number and 0 ≤ p ≤ 1"), "Probability of at most k successes in n trials", "binomcdf(10, 0.5, 5) ≈ 0.623047"),
+  ("poissonpdf", &["lambda", "k"], Some("lambda ≥ 0 and k a natural number"), "Probability of exactly k events", "poissonpdf(2, 3) ≈ 0.180447"),
+  ("poissoncdf", &["lambda", "k"], Some("lambda ≥ 0 and k a natural number"), "Probability of at most k events", "poissoncdf(2, 3) ≈ 0.857123"),
+  ("tpdf", &["x", "df"], Some("df > 0"), "Density of Student's t distribution", "tpdf(0, 1) ≈ 0.31831"),
+  ("tcdf", &["x", "df"], Some("df > 0"), "Cumulative distribution of Student's t distribution", "tcdf(0, 5) = 0.5"),
+  ("invt", &["p", "df"], Some("0 < p < 1 and df > 0"), "Quantile of Student's t distribution", "invt(0.975, 10) ≈ 2.228139"),
+  ("chi2pdf", &["x", "df"], Some("df > 0"), "Density of the chi-squared distribution", "chi2pdf(1, 2) ≈ 0.303265"),
+  ("chi2cdf", &["x", "df"], Some("df > 0"), "Cumulative distribution of the chi-squared distribution", "chi2cdf(3.841459, 1) ≈ 0.95"),
+  ("invchi2", &["p", "df"], Some("0 ≤ p < 1 and df > 0"), "Quantile of the chi-squared distribution", "invchi2(0.95, 1) ≈ 3.841459"),
+  ("exppdf", &["x", "lambda"], Some("lambda > 0"), "Density of the exponential distribution with rate lambda", "exppdf(1, 2) ≈ 0.270671"),
+  ("expcdf", &["x", "lambda"], Some("lambda > 0"), "Cumulative distribution of the exponential distribution", "expcdf(1, 2) ≈ 0.864665"),
+  ("invexp", &["p", "lambda"], Some("0 ≤ p < 1 and lambda > 0"), "Quantile of the exponential distribution", "invexp(0.5, 2) ≈ 0.346574"),
+  ("fv", &["rate", "nper", "pmt", "pv?", "type?"], Some("type 0 for payments at the end of the periods, 1 at the start"), "Future value of an investment", "fv(0.05, 10, -100) ≈ 1257.789254"),
+  ("pv", &["rate", "nper", "pmt", "fv?", "type?"], Some("type 0 for payments at the end of the periods, 1 at the start"), "Present value of an investment", "pv(0.05, 10, -100) ≈ 772.173493"),
+  ("pmt", &["rate", "nper", "pv", "fv?", "type?"], Some("type 0 for payments at the end of the periods, 1 at the start"), "Payment per period of a loan", "pmt(0.05 / 12, 360, 200000) ≈ -1073.643246"),
+  ("nper", &["rate", "pmt", "pv", "fv?", "type?"], Some("type 0 for payments at the end of the periods, 1 at the start"), "Number of periods of an investment", "nper(0.01, -100, 1000) ≈ 10.588644"),
+  ("rate", &["nper", "pmt", "pv", "fv?", "type?", "guess?"], Some("type 0 for payments at the end of the periods, 1 at the start"), "Interest rate per period", "rate(10, -100, 800) ≈ 0.042775"),
+  ("npv", &["rate", "v1..."], None, "Net present value of cash flows at the end of each period", "npv(0.1, 100, 100) ≈ 173.553719"),
+  ("irr", &["v0", "v1..."], Some("cash flows with at least one change of sign"), "Internal rate of return", "irr(-100, 60, 60) ≈ 0.130662"),
+  ("amortize", &["principal", "rate", "periods"], None, "Schedule of a loan repaid by equal payments, as a table", "amortize(1000, 0.01, 12)"),
+  ("gcd", &["n..."], Some("integers"), "Greatest common divisor", "gcd(12, 18) = 6"),
+  ("lcm", &["n..."], Some("integers"), "Least common multiple", "lcm(4, 6) = 12"),
+  ("nCr", &["n", "k"], Some("natural numbers"), "Number of ways to choose k elements out of n", "nCr(5, 2) = 10"),
+  ("nPr", &["n", "k"], Some("natural numbers"), "Number of ordered arrangements of k elements out of n", "nPr(5, 2) = 20"),
+  ("isprime", &["n"], Some("an integer"), "1 if n is prime, 0 otherwise", "isprime(97) = 1"),
+  ("nextprime", &["n"], Some("an integer"), "Smallest prime greater than n", "nextprime(100) = 101"),
+  ("factor", &["n"], Some("a positive integer"), "Prime factors as a list", "factor(12) = [2, 2, 3]"),
+  ("totient", &["n"], Some("a positive integer"), "Euler's totient, the number of integers up to n coprime with it", "totient(36) = 12"),
+  ("modpow", &["a", "b", "m"], Some("integers and m ≠ 0"), "a^b mod m, negative exponents use the modular inverse", "modpow(4, 13, 497) = 445"),
+  ("modinv", &["a", "m"], Some("a and m coprime integers"), "Inverse of a modulo m", "modinv(3, 11) = 4"),
+  ("emod", &["a", "b"], Some("b ≠ 0"), "Euclidean modulo, between 0 and |b| - 1 unlike %", "emod(-7, 3) = 2"),
+  ("re", &["z"], None, "Real part of a complex number", "re(3+4i) = 3"),
+  ("im", &["z"], None, "Imaginary part of a complex number", "im(3+4i) = 4"),
+  ("arg", &["z"], None, "Angle of a complex number from the real axis, in radians", "arg(-1) ≈ 3.141593"),
+  ("conj", &["z"], None, "Complex conjugate", "conj(3+4i) = 3-4i"),
+  ("polar", &["z"], None, "Modulus and angle of a complex number as a list", "polar(3+4i) ≈ [5, 0.927295]"),
+  ("rect", &["r", "theta"], None, "Complex number from its modulus and angle in radians", "rect(2, 0) = 2"),
+  ("date", &["year", "month", "day", "hour?", "minute?", "second?"], Some("a day of the calendar"), "Date, with a time when one is given", "date(2024, 2, 29) = 2024-02-29"),
+  ("weekday", &["date"], None, "Day of the week, from 1 for Monday to 7 for Sunday", "weekday(date(2024, 1, 1)) = 1"),
+  ("weeknum", &["date"], None, "ISO 8601 week number", "weeknum(date(2024, 12, 30)) = 1"),
+  ("eomonth", &["date", "months?"], Some("months an integer"), "Last day of the month, months after the date", "eomonth(date(2024, 1, 15), 1) = 2024-02-29"),
+  ("workdays", &["start", "end"], None, "Days from Monday to Friday between the dates, both included, without the holidays", "workdays(date(2024, 1, 1), date(2024, 1, 31)) = 23"),
+  ("mixed", &["x"], None, "A fraction shown as a mixed number, in fraction mode", "mixed(7/2) = 3 1/2"),
+  ("rand", &[], None, "Uniform random number from 0 to 1, 1 excluded", "rand()"),
+  ("randn", &[], None, "Random number with the standard normal distribution", "randn()"),
+  ("randint", &["a", "b"], Some("integers"), "Uniform random integer from a to b included", "randint(1, 6)"),
+  ("choose", &["x..."], None, "One of the arguments, or of the items of a list, at random", "choose(1, 2, 3)"),
+  ("shuffle", &["x..."], None, "The arguments, or the items of a list, in a random order", "shuffle(1, 2, 3)"),
+];
+
+/// The signatures of the builtins.
+pub fn builtins() -> impl Iterator<Item = Signature> {
+  BUILTINS.iter().map(|&(name, params, domain, description, example)| {
+    Signature::new(name, params, domain, description, Some(example))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::context::{Context, NumberMode};
+  use super::super::expr::eval_value_str_with_context;
+  use super::*;
+
+  #[test]
+  fn test_examples() {
+    let mut context = Context::new();
+    // Complex numbers for `re(3+4i)` and fractions for `mixed(7/2)`
+    context.set_complex_mode(true);
+    context.set_number_mode(NumberMode::Fraction);
+    for signature in builtins() {
+      let example = signature.example.expect("Builtins have an example");
+      let (call, expected, exact) = match example.split_once(" = ") {
+        Some((call, expected)) => (call, Some(expected), true),
+        None => match example.split_once(" ≈ ") {
+          Some((call, expected)) => (call, Some(expected), false),
+          None => (example.as_str(), None, true),
+        },
+      };
+      let value = eval_value_str_with_context(call, &context).map(|(_, value)| value);
+      let value = value.unwrap_or_else(|e| panic!("{}: {}", example, e));
+      match expected.map(|expected| (expected, expected.parse::<f64>())) {
+        Some((_, Ok(expected))) => {
+          let x = value.as_real().unwrap_or_else(|| panic!("{}: {}", example, value));
+          let tolerance = if exact { 1e-12 } else { 1e-5 } * expected.abs().max(1.);
+          assert!((x - expected).abs() <= tolerance, "{}: {}", example, x);
+        }
+        Some((expected, Err(_))) if exact => assert_eq!(value.to_string(), expected, "{}", example),
+        _ => {}
+      }
+    }
+  }
+
+  #[test]
+  fn test_signature() {
+    let round = Signature::new("round", &["x", "digits?"], None, "Rounds", Some("round(2.5) = 3"));
+    assert_eq!(round.arity, Arity { min: 1, max: Some(2) });
+    assert_eq!(round.to_string(), "round(x, digits?): Rounds\nExample: round(2.5) = 3");
+    let var = Signature::new("var", &["x1", "x2..."], None, "", None);
+    assert_eq!(var.arity, Arity { min: 2, max: None });
+    assert_eq!(Signature::new("rand", &[], None, "", None).usage(), "rand()");
+
+    let usage = |min, max| Signature::generic("f", Arity { min, max }).usage();
+    assert_eq!(usage(1, Some(1)), "f(x)");
+    assert_eq!(usage(1, Some(3)), "f(x, y?, z?)");
+    assert_eq!(usage(4, Some(4)), "f(x1, x2, x3, x4)");
+    assert_eq!(usage(0, None), "f(x...)");
+    assert_eq!(usage(2, None), "f(x, y...)");
+  }
+}
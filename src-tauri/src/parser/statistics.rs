@@ -0,0 +1,218 @@
+//! Descriptive statistics over the arguments of a function, e.g. `median(1, 5, 2)`.
+use super::context::FuncEvalError;
+use super::extra_math::DomainError;
+use super::value::Value;
+
+fn non_empty(xs: &[f64], min: usize) -> Result<(), FuncEvalError> {
+  if xs.len() < min {
+    Err(FuncEvalError::TooFewArguments)
+  } else {
+    Ok(())
+  }
+}
+
+fn sorted(xs: &[f64]) -> Vec<f64> {
+  let mut xs = xs.to_vec();
+  xs.sort_by(|a, b| a.total_cmp(b));
+  xs
+}
+
+fn mean(xs: &[f64]) -> f64 {
+  sum(xs) / xs.len() as f64
+}
+
+/// `n`-th central moment.
+fn moment(xs: &[f64], n: i32) -> f64 {
+  let m = mean(xs);
+  xs.iter().map(|x| (x - m).powi(n)).sum::<f64>() / xs.len() as f64
+}
+
+pub fn sum(xs: &[f64]) -> f64 {
+  xs.iter().sum()
+}
+
+pub fn sumsq(xs: &[f64]) -> f64 {
+  xs.iter().map(|x| x * x).sum()
+}
+
+pub fn range(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  let xs = sorted(xs);
+  Ok(xs[xs.len() - 1] - xs[0])
+}
+
+pub fn median(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  Ok(quantile(&sorted(xs), 0.5))
+}
+
+/// The most frequent value, the smallest one when several are as frequent.
+pub fn mode(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  let xs = sorted(xs);
+  let (mut best, mut best_count) = (xs[0], 0);
+  for run in xs.chunk_by(|a, b| a == b) {
+    if run.len() > best_count {
+      best = run[0];
+      best_count = run.len();
+    }
+  }
+  Ok(best)
+}
+
+/// Sample variance, divided by `n - 1`.
+pub fn var(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 2)?;
+  Ok(moment(xs, 2) * xs.len() as f64 / (xs.len() - 1) as f64)
+}
+
+/// Population variance, divided by `n`.
+pub fn varp(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  Ok(moment(xs, 2))
+}
+
+pub fn stdev(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  var(xs).map(f64::sqrt)
+}
+
+pub fn stdevp(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  varp(xs).map(f64::sqrt)
+}
+
+/// Linearly interpolated quantile of sorted values, `q` between 0 and 1.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+  let rank = q * (sorted.len() - 1) as f64;
+  let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+  sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// `percentile(p, x1, x2, ...)` with `p` between 0 and 100.
+pub fn percentile(args: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(args, 2)?;
+  let p = args[0];
+  if !(0. ..=100.).contains(&p) {
+    return Err(DomainError::OutOfRange(p, 0., 100.).into());
+  }
+  Ok(quantile(&sorted(&args[1..]), p / 100.))
+}
+
+/// The first, second and third quartiles as a list.
+pub fn quartiles(args: &[Value]) -> Result<Value, FuncEvalError> {
+  let xs = reals(args)?;
+  non_empty(&xs, 1)?;
+  let xs = sorted(&xs);
+  Ok(Value::List(
+    [0.25, 0.5, 0.75]
+      .iter()
+      .map(|&q| Value::Real(quantile(&xs, q)))
+      .collect(),
+  ))
+}
+
+fn reals(args: &[Value]) -> Result<Vec<f64>, FuncEvalError> {
+  args
+    .iter()
+    .map(|arg| match *arg {
+      Value::List(_) | Value::Table(_) => Err(FuncEvalError::ListArgument),
+      ref arg => arg.as_real().ok_or(FuncEvalError::ComplexArgument),
+    })
+    .collect()
+}
+
+/// Interquartile range.
+pub fn iqr(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  let xs = sorted(xs);
+  Ok(quantile(&xs, 0.75) - quantile(&xs, 0.25))
+}
+
+fn positive(xs: &[f64]) -> Result<(), FuncEvalError> {
+  match xs.iter().find(|&&x| x <= 0.) {
+    Some(&x) => Err(DomainError::NotPositive(x).into()),
+    None => Ok(()),
+  }
+}
+
+pub fn geomean(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  positive(xs)?;
+  Ok((xs.iter().map(|x| x.ln()).sum::<f64>() / xs.len() as f64).exp())
+}
+
+pub fn harmmean(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  positive(xs)?;
+  Ok(xs.len() as f64 / xs.iter().map(|x| 1. / x).sum::<f64>())
+}
+
+fn standardized_moment(xs: &[f64], n: i32) -> Result<f64, FuncEvalError> {
+  non_empty(xs, 1)?;
+  let variance = moment(xs, 2);
+  if variance == 0. {
+    return Err(DomainError::DivisionByZero.into());
+  }
+  Ok(moment(xs, n) / variance.powf(n as f64 / 2.))
+}
+
+/// Population skewness.
+pub fn skew(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  standardized_moment(xs, 3)
+}
+
+/// Population excess kurtosis, 0 for a normal distribution.
+pub fn kurtosis(xs: &[f64]) -> Result<f64, FuncEvalError> {
+  standardized_moment(xs, 4).map(|k| k - 3.)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DATA: [f64; 8] = [2., 4., 4., 4., 5., 5., 7., 9.];
+
+  #[test]
+  fn test_central_tendency() {
+    assert_eq!(sum(&DATA), 40.);
+    assert_eq!(sumsq(&DATA), 232.);
+    assert_eq!(median(&DATA), Ok(4.5));
+    assert_eq!(median(&[3., 1., 2.]), Ok(2.));
+    assert_eq!(mode(&DATA), Ok(4.));
+    assert_eq!(mode(&[3., 1., 3., 1.]), Ok(1.));
+    assert_eq!(range(&DATA), Ok(7.));
+    assert_eq!(geomean(&[2., 8.]), Ok(4.));
+    assert_eq!(harmmean(&[1., 4., 4.]), Ok(2.));
+    assert_eq!(geomean(&[2., -8.]), Err(FuncEvalError::Domain(DomainError::NotPositive(-8.))));
+    assert_eq!(median(&[]), Err(FuncEvalError::TooFewArguments));
+  }
+
+  #[test]
+  fn test_dispersion() {
+    assert_eq!(varp(&DATA), Ok(4.));
+    assert_eq!(stdevp(&DATA), Ok(2.));
+    assert_eq!(var(&DATA), Ok(32. / 7.));
+    assert_eq!(var(&[1.]), Err(FuncEvalError::TooFewArguments));
+    assert_eq!(percentile(&[50., 1., 2., 3., 4.]), Ok(2.5));
+    assert_eq!(percentile(&[90., 1., 2., 3., 4., 5.]), Ok(4.6));
+    assert_eq!(
+      percentile(&[101., 1.]),
+      Err(FuncEvalError::Domain(DomainError::OutOfRange(101., 0., 100.)))
+    );
+    assert_eq!(iqr(&[1., 2., 3., 4., 5.]), Ok(2.));
+    assert_eq!(
+      quartiles(&[Value::Real(1.), Value::Real(2.), Value::Real(3.), Value::Real(4.), Value::Real(5.)]),
+      Ok(Value::List(vec![Value::Real(2.), Value::Real(3.), Value::Real(4.)]))
+    );
+  }
+
+  #[test]
+  fn test_shape() {
+    assert_eq!(skew(&[1., 2., 3.]), Ok(0.));
+    assert!((skew(&[1., 1., 1., 10.]).unwrap() - 1.1547005383792515).abs() < 1e-12);
+    assert_eq!(kurtosis(&[1., -1., 1., -1.]), Ok(-2.));
+    assert_eq!(
+      skew(&[2., 2.]),
+      Err(FuncEvalError::Domain(DomainError::DivisionByZero))
+    );
+  }
+}
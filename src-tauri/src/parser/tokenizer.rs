@@ -13,6 +13,8 @@ pub enum ParserError {
   MissingRParen(i32),
   /// Missing operator or function argument at the end of the expression.
   MissingArgument,
+  /// A date literal that is not a calendar date, e.g. `2026-02-30`.
+  InvalidDate(String),
 }
 
 impl fmt::Display for ParserError {
@@ -26,6 +28,7 @@ impl fmt::Display for ParserError {
         if i == 1 { "is" } else { "es" }
       ),
       ParserError::MissingArgument => write!(f, "Missing argument at the end of expression."),
+      ParserError::InvalidDate(ref date) => write!(f, "Invalid date `{}`.", date),
     }
   }
 }
@@ -58,6 +61,15 @@ pub fn tokenize<S: AsRef<str>>(input: S) -> Result<Vec<Token>, ParserError> {
   let mut s = input;
 
   while !s.is_empty() {
+    // `$45` is read as the amount followed by its currency
+    if let (LExpr, Ok((rest, (amount, currency)))) = (state, currency_literal(s)) {
+      res.push(amount);
+      res.push(currency);
+      state = AfterRExpr;
+      s = rest;
+      continue;
+    }
+
     let r = match (state, paren_stack.last()) {
       (LExpr, _) => lexpr(s),
       (AfterRExpr, None) => after_rexpr_no_paren(s),
@@ -71,13 +83,21 @@ pub fn tokenize<S: AsRef<str>>(input: S) -> Result<Vec<Token>, ParserError> {
           Token::LParen => {
             paren_stack.push(ParentState::Subexpr);
           }
+          // `rand()` is complete, there are no arguments to wait for
+          Token::Func(_, Some(0)) => {
+            state = AfterRExpr;
+          }
           Token::Func(..) => {
             paren_stack.push(ParentState::Func);
           }
           Token::RParen => {
             paren_stack.pop().expect("The paren_stack is empty!");
           }
-          Token::Var(_) | Token::Number(_) => {
+          Token::Var(_)
+          | Token::Number(..)
+          | Token::Imaginary(_)
+          | Token::Date(..)
+          | Token::Duration(_) => {
             state = AfterRExpr;
           }
           Token::Binary(_) | Token::Comma => {
@@ -101,6 +121,13 @@ pub fn tokenize<S: AsRef<str>>(input: S) -> Result<Vec<Token>, ParserError> {
             ErrorKind::Float => {
               return Err(ParserError::MissingArgument);
             }
+            // A date literal fails only once recognized, when it is not a calendar date
+            ErrorKind::Verify => {
+              return Err(match date_text(e.input) {
+                Ok((_, date)) => ParserError::InvalidDate(date.to_string()),
+                Err(_) => ParserError::UnexpectedToken(input.len() - s.len()),
+              });
+            }
             _ => {
               return Err(ParserError::UnexpectedToken(input.len() - s.len()));
             }
@@ -121,6 +148,10 @@ pub fn tokenize<S: AsRef<str>>(input: S) -> Result<Vec<Token>, ParserError> {
 mod tests {
   use super::*;
 
+  fn num(x: f64) -> Token {
+    Token::Number(x, x.to_string())
+  }
+
   #[test]
   fn test_tokenize() {
     use super::Operation::*;
@@ -131,11 +162,11 @@ mod tests {
     assert_eq!(
       tokenize("2+4-6"),
       Ok(vec![
-        Number(2f64),
+        num(2.),
         Binary(Plus),
-        Number(4f64),
+        num(4.),
         Binary(Minus),
-        Number(6f64)
+        num(6.)
       ])
     );
 
@@ -144,9 +175,9 @@ mod tests {
       Ok(vec![
         Unary(Minus),
         LParen,
-        Number(5f64),
+        num(5.),
         Binary(Plus),
-        Number(4f64),
+        num(4.),
         RParen
       ])
     );
@@ -154,12 +185,12 @@ mod tests {
     assert_eq!(
       tokenize("-4+(1-+3)"),
       Ok(vec![
-        Number(-4f64),
+        num(-4.),
         Binary(Plus),
         LParen,
-        Number(1f64),
+        num(1.),
         Binary(Minus),
-        Number(3f64),
+        Number(3., "+3".into()),
         RParen
       ])
     );
@@ -167,13 +198,13 @@ mod tests {
     assert_eq!(
       tokenize("3*4^9/ 8!"),
       Ok(vec![
-        Number(3f64),
+        num(3.),
         Binary(Times),
-        Number(4f64),
+        num(4.),
         Binary(Pow),
-        Number(9f64),
+        num(9.),
         Binary(Div),
-        Number(8f64),
+        num(8.),
         Unary(Fact)
       ])
     );
@@ -181,11 +212,11 @@ mod tests {
     assert_eq!(
       tokenize("-2^ ab0 *12 - C_0"),
       Ok(vec![
-        Number(-2f64),
+        num(-2.),
         Binary(Pow),
         Var("ab0".into()),
         Binary(Times),
-        Number(12f64),
+        num(12.),
         Binary(Minus),
         Var("C_0".into()),
       ])
@@ -198,11 +229,11 @@ mod tests {
         Func("sin".into(), None),
         Var("pi".into()),
         Binary(Times),
-        Number(3f64),
+        num(3.),
         RParen,
         Binary(Pow),
         Func("cos".into(), None),
-        Number(2f64),
+        num(2.),
         RParen,
         Binary(Div),
         Func("Func2".into(), None),
@@ -221,9 +252,77 @@ mod tests {
       ])
     );
 
+    assert_eq!(
+      tokenize("3+4i"),
+      Ok(vec![num(3.), Binary(Plus), Imaginary(4f64)])
+    );
+
     assert_eq!(
       tokenize("2 %   3"),
-      Ok(vec![Number(2f64), Binary(Mod), Number(3f64)])
+      Ok(vec![num(2.), Binary(Mod), num(3.)])
+    );
+
+    assert_eq!(
+      tokenize("5 km + 300m^2 in mi/h"),
+      Ok(vec![
+        num(5.),
+        Unit("km".into(), 1),
+        Binary(Plus),
+        num(300.),
+        Unit("m".into(), 2),
+        Convert(vec![("mi".into(), 1), ("h".into(), -1)])
+      ])
+    );
+
+    assert_eq!(
+      tokenize("f(3 kW h to J, 2 inch)"),
+      Ok(vec![
+        Func("f".into(), None),
+        num(3.),
+        Unit("kW".into(), 1),
+        Unit("h".into(), 1),
+        Convert(vec![("J".into(), 1)]),
+        Comma,
+        num(2.),
+        Unit("inch".into(), 1),
+        RParen,
+      ])
+    );
+
+    assert_eq!(
+      tokenize("$45 * 3 in GBP"),
+      Ok(vec![
+        num(45.),
+        Unit("USD".into(), 1),
+        Binary(Times),
+        num(3.),
+        Convert(vec![("GBP".into(), 1)])
+      ])
+    );
+
+    assert_eq!(
+      tokenize("2026-12-25 - 2026-10-18"),
+      Ok(vec![
+        Date(chrono::NaiveDate::from_ymd_opt(2026, 12, 25).unwrap().into(), false),
+        Binary(Minus),
+        Date(chrono::NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().into(), false),
+      ])
+    );
+    assert_eq!(tokenize("2026-13-01"), Err(ParserError::InvalidDate("2026-13-01".into())));
+    assert_eq!(
+      tokenize("1 + 2026-02-30T10:00+1"),
+      Err(ParserError::InvalidDate("2026-02-30T10:00".into()))
+    );
+    assert_eq!(tokenize("8:75"), Err(ParserError::UnexpectedToken(0)));
+
+    assert_eq!(
+      tokenize("8:00 + 7h45m in h"),
+      Ok(vec![
+        Duration(28800f64),
+        Binary(Plus),
+        Duration(27900f64),
+        Convert(vec![("h".into(), 1)])
+      ])
     );
 
     assert_eq!(tokenize("!2"), Err(ParserError::UnexpectedToken(0)));
@@ -0,0 +1,687 @@
+//! Physical quantities: numbers with a unit, e.g. `5 km`, checked by dimensional analysis.
+//!
+//! A quantity keeps its magnitude in its own unit so that `5 km + 300 m` is shown as `5.3 km`, the
+//! unit knows how to convert to SI base units. Temperatures with an offset (`degC`, `degF`) are
+//! only converted as absolute temperatures by `to`/`in`, in arithmetic they behave as differences.
+//!
+//! Currencies are units of their own dimension, their factor is the exchange rate to the US dollar
+//! set by the user, and quantities in a currency carry the date of the oldest rate they depend on.
+use super::value::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Symbols of the SI base units and of the base currency, in the order of the exponents of a `Dimension`.
+const BASE_UNITS: [&str; 8] = ["m", "kg", "s", "A", "K", "mol", "cd", "USD"];
+
+/// Exponents of the base units, e.g. `[1, 0, -1, 0, 0, 0, 0, 0]` for a speed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dimension(pub [i8; 8]);
+
+impl Dimension {
+  pub fn is_dimensionless(&self) -> bool {
+    self.0.iter().all(|&e| e == 0)
+  }
+
+  fn mul(self, other: Dimension) -> Dimension {
+    let mut exps = self.0;
+    for (e, o) in exps.iter_mut().zip(other.0.iter()) {
+      *e += o;
+    }
+    Dimension(exps)
+  }
+
+  fn powi(self, n: i32) -> Dimension {
+    Dimension(self.0.map(|e| e * n as i8))
+  }
+}
+
+impl fmt::Display for Dimension {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.is_dimensionless() {
+      return write!(f, "dimensionless");
+    }
+    let symbols: Vec<(String, i32)> = BASE_UNITS
+      .iter()
+      .zip(self.0.iter())
+      .filter(|&(_, &e)| e != 0)
+      .map(|(&symbol, &e)| (symbol.to_string(), e as i32))
+      .collect();
+    write_symbols(f, &symbols)
+  }
+}
+
+/// A unit, possibly compound like `kW h` or `m/s^2`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unit {
+  /// The named units and their powers, e.g. `[("m", 1), ("s", -2)]`.
+  pub symbols: Vec<(String, i32)>,
+  /// The value of one unit in SI base units.
+  pub factor: f64,
+  /// Added before scaling to SI, `x degC` is `(x + 273.15) K`. Only single units have an offset.
+  pub offset: f64,
+  pub dimension: Dimension,
+  /// When the exchange rate of a currency was set, as a Unix timestamp.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub updated: Option<i64>,
+  /// Seconds shown as `h:mm:ss`, set by duration literals such as `1h30m` or `2:45:10`.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub clock: bool,
+}
+
+impl Unit {
+  pub fn new<S: Into<String>>(symbol: S, factor: f64, dimension: Dimension) -> Unit {
+    Unit {
+      symbols: vec![(symbol.into(), 1)],
+      factor,
+      offset: 0.,
+      dimension,
+      updated: None,
+      clock: false,
+    }
+  }
+
+  /// The unit of duration literals, seconds shown as `h:mm:ss`.
+  pub fn duration() -> Unit {
+    Unit {
+      clock: true,
+      ..Unit::new("s", 1., Dimension(TIME))
+    }
+  }
+
+  /// A currency worth `rate` US dollars, with the time the rate was set.
+  pub fn currency<S: Into<String>>(code: S, rate: f64, updated: i64) -> Unit {
+    Unit {
+      updated: Some(updated),
+      ..Unit::new(code, rate, Dimension(CURRENCY))
+    }
+  }
+
+  /// Defines a new unit from its value, e.g. `furlong` from `201.168 m`.
+  pub fn define<S: Into<String>>(symbol: S, value: &Value) -> Result<Unit, UnitError> {
+    match *value {
+      Value::Quantity(ref q) => Ok(Unit {
+        updated: q.unit.updated,
+        ..Unit::new(symbol, q.to_si(), q.unit.dimension)
+      }),
+      ref value => value
+        .as_real()
+        .map(|x| Unit::new(symbol, x, Dimension::default()))
+        .ok_or(UnitError::NotReal),
+    }
+  }
+
+  fn mul(&self, other: &Unit) -> Unit {
+    let mut symbols = self.symbols.clone();
+    for (symbol, power) in other.symbols.iter() {
+      match symbols.iter_mut().find(|(s, _)| s == symbol) {
+        Some((_, p)) => *p += power,
+        None => symbols.push((symbol.clone(), *power)),
+      }
+    }
+    symbols.retain(|&(_, p)| p != 0);
+    Unit {
+      symbols,
+      factor: self.factor * other.factor,
+      offset: 0.,
+      dimension: self.dimension.mul(other.dimension),
+      updated: oldest(self.updated, other.updated),
+      // `1h30m * 3` is still a duration, `1h30m * 3 kW` is not
+      clock: (self.clock && other.symbols.is_empty()) || (other.clock && self.symbols.is_empty()),
+    }
+  }
+
+  pub fn powi(&self, n: i32) -> Unit {
+    if n == 1 {
+      return self.clone();
+    }
+    Unit {
+      symbols: self.symbols.iter().map(|(s, p)| (s.clone(), p * n)).collect(),
+      factor: self.factor.powi(n),
+      offset: 0.,
+      dimension: self.dimension.powi(n),
+      updated: self.updated,
+      clock: false,
+    }
+  }
+
+  /// Raises the unit to a fractional power, e.g. `(m^2)^0.5`, if every power stays an integer.
+  fn powf(&self, x: f64) -> Option<Unit> {
+    let integer = |p: i32| {
+      let r = p as f64 * x;
+      if r.fract() == 0. {
+        Some(r as i32)
+      } else {
+        None
+      }
+    };
+    let mut exps = [0i8; 8];
+    for (e, &d) in exps.iter_mut().zip(self.dimension.0.iter()) {
+      *e = integer(d as i32)? as i8;
+    }
+    Some(Unit {
+      symbols: self
+        .symbols
+        .iter()
+        .map(|(s, p)| integer(*p).map(|p| (s.clone(), p)))
+        .collect::<Option<_>>()?,
+      factor: self.factor.powf(x),
+      offset: 0.,
+      dimension: Dimension(exps),
+      updated: self.updated,
+      clock: false,
+    })
+  }
+}
+
+/// The older of two rate timestamps.
+fn oldest(a: Option<i64>, b: Option<i64>) -> Option<i64> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.min(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+impl fmt::Display for Unit {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write_symbols(f, &self.symbols)
+  }
+}
+
+/// Writes `kg m/s^2`, `1/s` or `J/(kg K)`.
+fn write_symbols(f: &mut fmt::Formatter, symbols: &[(String, i32)]) -> fmt::Result {
+  let join = |positive: bool| {
+    symbols
+      .iter()
+      .filter(|&&(_, p)| (p > 0) == positive)
+      .map(|(s, p)| match p.abs() {
+        1 => s.clone(),
+        p => format!("{}^{}", s, p),
+      })
+      .collect::<Vec<String>>()
+  };
+  let (numerator, denominator) = (join(true), join(false));
+  if numerator.is_empty() {
+    write!(f, "1")?;
+  } else {
+    write!(f, "{}", numerator.join(" "))?;
+  }
+  match denominator.len() {
+    0 => Ok(()),
+    1 => write!(f, "/{}", denominator[0]),
+    _ => write!(f, "/({})", denominator.join(" ")),
+  }
+}
+
+/// A number with a unit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+  /// The magnitude in `unit`.
+  #[serde(rename = "quantity", deserialize_with = "super::value::deserialize_real")]
+  pub value: f64,
+  pub unit: Unit,
+}
+
+impl Quantity {
+  /// The magnitude in SI base units.
+  pub fn to_si(&self) -> f64 {
+    (self.value + self.unit.offset) * self.unit.factor
+  }
+}
+
+impl Quantity {
+  /// Writes the quantity with its magnitude already formatted, e.g. with the display settings.
+  pub fn format_with(&self, value: &str) -> String {
+    if self.unit.clock {
+      return self.to_string();
+    }
+    let mut s = format!("{} {}", value, self.unit);
+    if let Some(updated) = self.unit.updated.and_then(|t| chrono::DateTime::from_timestamp(t, 0)) {
+      s.push_str(&format!(" (rates of {})", updated.format("%Y-%m-%d")));
+    }
+    s
+  }
+}
+
+impl fmt::Display for Quantity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.unit.clock {
+      return write_clock(f, self.to_si());
+    }
+    write!(f, "{}", self.format_with(&self.value.to_string()))
+  }
+}
+
+/// Writes a number of seconds as `h:mm:ss`, rounded to the millisecond, e.g. `-2:45:10.5`.
+fn write_clock(f: &mut fmt::Formatter, seconds: f64) -> fmt::Result {
+  if !seconds.is_finite() {
+    return write!(f, "{} s", seconds);
+  }
+  let millis = (seconds.abs() * 1000.).round();
+  if seconds < 0. && millis > 0. {
+    write!(f, "-")?;
+  }
+  let (hours, minutes) = ((millis / 3_600_000.).floor(), (millis / 60_000.).floor() % 60.);
+  let seconds = millis % 60_000. / 1000.;
+  if seconds.fract() == 0. {
+    write!(f, "{}:{:02}:{:02}", hours, minutes, seconds)
+  } else {
+    let seconds = format!("{:06.3}", seconds);
+    write!(f, "{}:{:02}:{}", hours, minutes, seconds.trim_end_matches('0'))
+  }
+}
+
+/// An error in a computation with units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitError {
+  UnknownUnit(String),
+  /// Quantities of different dimensions are added, compared or converted, e.g. `m + s`.
+  DimensionMismatch(Dimension, Dimension),
+  /// A unit raised to a power that leaves a fractional power, e.g. `m^0.5`.
+  InvalidPower(f64),
+  /// Units only apply to real numbers, not to complex numbers or lists.
+  NotReal,
+}
+
+impl fmt::Display for UnitError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      UnitError::UnknownUnit(ref name) => write!(f, "Unknown unit `{}`", name),
+      UnitError::DimensionMismatch(left, right) => {
+        write!(f, "Incompatible dimensions {} and {}", left, right)
+      }
+      UnitError::InvalidPower(x) => write!(f, "Cannot raise a unit to the power {}", x),
+      UnitError::NotReal => write!(f, "Units only apply to real numbers"),
+    }
+  }
+}
+
+impl std::error::Error for UnitError {}
+
+/// Builds a compound unit from its symbols and powers, e.g. `[("m", 1), ("s", -1)]`.
+pub fn compound<F>(factors: &[(String, i32)], lookup: F) -> Result<Unit, UnitError>
+where
+  F: Fn(&str) -> Option<Unit>,
+{
+  let mut unit: Option<Unit> = None;
+  for (name, power) in factors.iter() {
+    let factor = lookup(name)
+      .ok_or_else(|| UnitError::UnknownUnit(name.clone()))?
+      .powi(*power);
+    unit = Some(match unit {
+      Some(unit) => unit.mul(&factor),
+      None => factor,
+    });
+  }
+  unit.ok_or_else(|| UnitError::UnknownUnit(String::new()))
+}
+
+/// Builds a quantity, a dimensionless result such as `km/m` becomes a plain number.
+fn quantity(value: f64, unit: Unit) -> Value {
+  if unit.dimension.is_dimensionless() {
+    Value::Real(value * unit.factor)
+  } else {
+    Value::Quantity(Quantity { value, unit })
+  }
+}
+
+fn real(value: &Value) -> Result<f64, UnitError> {
+  value.as_real().ok_or(UnitError::NotReal)
+}
+
+fn dimension(value: &Value) -> Dimension {
+  match *value {
+    Value::Quantity(ref q) => q.unit.dimension,
+    _ => Dimension::default(),
+  }
+}
+
+/// Applies a unit to a value, `20 degC` keeps the offset of the unit.
+pub fn apply(value: &Value, unit: Unit) -> Result<Value, UnitError> {
+  match *value {
+    Value::Quantity(ref q) => Ok(quantity(q.value, q.unit.mul(&unit))),
+    ref value => Ok(quantity(real(value)?, unit)),
+  }
+}
+
+/// Rounds away the float noise left by adding and subtracting the offsets of temperature scales,
+/// so that `20 degC to degF` is 68. The noise is below the 14th significant digit of the largest
+/// of the offset and the value.
+fn drop_offset_noise(value: f64, offset: f64) -> f64 {
+  let digits = 13 - value.abs().max(offset).log10().floor() as i32;
+  if !(1..=22).contains(&digits) {
+    return value;
+  }
+  // 10^digits is exact up to 10^22, dividing by it rounds correctly
+  let scale = 10f64.powi(digits);
+  (value * scale).round() / scale
+}
+
+/// Converts a quantity to another unit of the same dimension, e.g. `60 mph to m/s`.
+pub fn convert(value: &Value, unit: Unit) -> Result<Value, UnitError> {
+  match *value {
+    Value::Quantity(ref q) if q.unit.dimension == unit.dimension => {
+      let mut value = q.to_si() / unit.factor - unit.offset;
+      if q.unit.offset != 0. || unit.offset != 0. {
+        let offset = (q.unit.offset * q.unit.factor / unit.factor).abs().max(unit.offset.abs());
+        value = drop_offset_noise(value, offset);
+      }
+      let unit = Unit {
+        updated: oldest(q.unit.updated, unit.updated),
+        ..unit
+      };
+      Ok(Value::Quantity(Quantity { value, unit }))
+    }
+    ref value => Err(UnitError::DimensionMismatch(dimension(value), unit.dimension)),
+  }
+}
+
+/// Evaluates a binary operation where at least one operand is a quantity.
+pub fn binary(op: super::Operation, left: &Value, right: &Value) -> Result<Value, UnitError> {
+  use super::Operation::*;
+
+  let one = |value: &Value| match *value {
+    Value::Quantity(ref q) => Ok(q.clone()),
+    ref value => Ok(Quantity {
+      value: real(value)?,
+      unit: Unit {
+        symbols: vec![],
+        factor: 1.,
+        offset: 0.,
+        dimension: Dimension::default(),
+        updated: None,
+        clock: false,
+      },
+    }),
+  };
+  let (l, r) = (one(left)?, one(right)?);
+  match op {
+    Plus | Minus | Mod => {
+      if l.unit.dimension != r.unit.dimension {
+        return Err(UnitError::DimensionMismatch(l.unit.dimension, r.unit.dimension));
+      }
+      // The right operand is a difference, offsets do not apply
+      let difference = r.value * r.unit.factor / l.unit.factor;
+      let value = match op {
+        Plus => l.value + difference,
+        Minus => l.value - difference,
+        _ => l.value % difference,
+      };
+      let unit = Unit {
+        updated: oldest(l.unit.updated, r.unit.updated),
+        ..l.unit
+      };
+      Ok(Value::Quantity(Quantity { value, unit }))
+    }
+    Times => Ok(quantity(l.value * r.value, l.unit.mul(&r.unit))),
+    Div => Ok(quantity(l.value / r.value, l.unit.mul(&r.unit.powi(-1)))),
+    Pow if !r.unit.dimension.is_dimensionless() => Err(UnitError::DimensionMismatch(
+      Dimension::default(),
+      r.unit.dimension,
+    )),
+    Pow => {
+      let exp = r.value * r.unit.factor;
+      let unit = if exp.fract() == 0. && exp.abs() <= i32::MAX as f64 {
+        Some(l.unit.powi(exp as i32))
+      } else {
+        l.unit.powf(exp)
+      };
+      unit
+        .map(|unit| quantity(l.value.powf(exp), unit))
+        .ok_or(UnitError::InvalidPower(exp))
+    }
+    _ => Err(UnitError::NotReal),
+  }
+}
+
+/// SI prefixes, `u` stands for micro.
+const PREFIXES: [(&str, f64); 20] = [
+  ("Y", 1e24),
+  ("Z", 1e21),
+  ("E", 1e18),
+  ("P", 1e15),
+  ("T", 1e12),
+  ("G", 1e9),
+  ("M", 1e6),
+  ("k", 1e3),
+  ("h", 1e2),
+  ("da", 1e1),
+  ("d", 1e-1),
+  ("c", 1e-2),
+  ("m", 1e-3),
+  ("u", 1e-6),
+  ("µ", 1e-6),
+  ("n", 1e-9),
+  ("p", 1e-12),
+  ("f", 1e-15),
+  ("a", 1e-18),
+  ("z", 1e-21),
+];
+
+/// A built-in unit: symbol, value in SI base units, dimension and whether it takes SI prefixes.
+type UnitDef = (&'static str, f64, [i8; 8], bool);
+
+const LENGTH: [i8; 8] = [1, 0, 0, 0, 0, 0, 0, 0];
+const MASS: [i8; 8] = [0, 1, 0, 0, 0, 0, 0, 0];
+const TIME: [i8; 8] = [0, 0, 1, 0, 0, 0, 0, 0];
+const AREA: [i8; 8] = [2, 0, 0, 0, 0, 0, 0, 0];
+const VOLUME: [i8; 8] = [3, 0, 0, 0, 0, 0, 0, 0];
+const SPEED: [i8; 8] = [1, 0, -1, 0, 0, 0, 0, 0];
+const FORCE: [i8; 8] = [1, 1, -2, 0, 0, 0, 0, 0];
+const ENERGY: [i8; 8] = [2, 1, -2, 0, 0, 0, 0, 0];
+const POWER: [i8; 8] = [2, 1, -3, 0, 0, 0, 0, 0];
+const PRESSURE: [i8; 8] = [-1, 1, -2, 0, 0, 0, 0, 0];
+const CURRENCY: [i8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+const UNITS: [UnitDef; 52] = [
+  ("m", 1., LENGTH, true),
+  ("inch", 0.0254, LENGTH, false),
+  ("ft", 0.3048, LENGTH, false),
+  ("yd", 0.9144, LENGTH, false),
+  ("mi", 1609.344, LENGTH, false),
+  ("nmi", 1852., LENGTH, false),
+  ("au", 149597870700., LENGTH, false),
+  ("ly", 9460730472580800., LENGTH, false),
+  ("pc", 3.085_677_581_491_367e16, LENGTH, true),
+  ("angstrom", 1e-10, LENGTH, false),
+  ("g", 1e-3, MASS, true),
+  ("t", 1e3, MASS, false),
+  ("lb", 0.45359237, MASS, false),
+  ("oz", 0.028349523125, MASS, false),
+  ("st", 6.35029318, MASS, false),
+  ("s", 1., TIME, true),
+  ("min", 60., TIME, false),
+  ("h", 3600., TIME, false),
+  ("day", 86400., TIME, false),
+  ("week", 604800., TIME, false),
+  ("month", 2629800., TIME, false),
+  ("yr", 31557600., TIME, false),
+  ("A", 1., [0, 0, 0, 1, 0, 0, 0, 0], true),
+  ("K", 1., [0, 0, 0, 0, 1, 0, 0, 0], true),
+  ("mol", 1., [0, 0, 0, 0, 0, 1, 0, 0], true),
+  ("cd", 1., [0, 0, 0, 0, 0, 0, 1, 0], true),
+  ("ha", 1e4, AREA, false),
+  ("acre", 4046.8564224, AREA, false),
+  ("L", 1e-3, VOLUME, true),
+  ("gal", 3.785411784e-3, VOLUME, false),
+  ("floz", 2.95735295625e-5, VOLUME, false),
+  ("mph", 0.44704, SPEED, false),
+  ("kn", 1852. / 3600., SPEED, false),
+  ("Hz", 1., [0, 0, -1, 0, 0, 0, 0, 0], true),
+  ("N", 1., FORCE, true),
+  ("lbf", 4.4482216152605, FORCE, false),
+  ("J", 1., ENERGY, true),
+  ("Wh", 3600., ENERGY, true),
+  ("cal", 4.184, ENERGY, true),
+  ("eV", 1.602176634e-19, ENERGY, true),
+  ("BTU", 1055.05585262, ENERGY, false),
+  ("W", 1., POWER, true),
+  ("hp", 745.699_871_582_270_2, POWER, false),
+  ("Pa", 1., PRESSURE, true),
+  ("bar", 1e5, PRESSURE, true),
+  ("atm", 101325., PRESSURE, false),
+  ("psi", 6894.757293168361, PRESSURE, false),
+  ("mmHg", 133.322387415, PRESSURE, false),
+  ("C", 1., [0, 0, 1, 1, 0, 0, 0, 0], true),
+  ("V", 1., [2, 1, -3, -1, 0, 0, 0, 0], true),
+  ("ohm", 1., [2, 1, -3, -2, 0, 0, 0, 0], true),
+  // The other currencies are only known once their rate is set
+  ("USD", 1., CURRENCY, false),
+];
+
+/// Temperature scales with an offset: symbol, size of a degree in kelvin and offset in degrees.
+const TEMPERATURES: [(&str, f64, f64); 3] = [
+  ("degC", 1., 273.15),
+  ("degF", 5. / 9., 459.67),
+  ("degR", 5. / 9., 0.),
+];
+
+/// Other names of built-in units, so that `today + 90 days` reads naturally.
+const ALIASES: [(&str, &str); 12] = [
+  ("seconds", "s"),
+  ("minute", "min"),
+  ("minutes", "min"),
+  ("hour", "h"),
+  ("hours", "h"),
+  ("days", "day"),
+  ("weeks", "week"),
+  ("months", "month"),
+  ("year", "yr"),
+  ("years", "yr"),
+  ("feet", "ft"),
+  ("miles", "mi"),
+];
+
+/// Angles in radians, the suffixes of `30°`, `45deg`, `1rad` or `100grad`.
+const ANGLES: [(&str, f64); 4] = [
+  ("rad", 1.),
+  ("deg", std::f64::consts::PI / 180.),
+  ("°", std::f64::consts::PI / 180.),
+  ("grad", std::f64::consts::PI / 200.),
+];
+
+/// The value in radians of an angle unit.
+pub fn angle(name: &str) -> Option<f64> {
+  ANGLES.iter().find(|a| a.0 == name).map(|a| a.1)
+}
+
+/// Looks up a built-in unit by symbol, with an optional SI prefix (`km`, `mW`, `kWh`, ...).
+pub fn lookup(name: &str) -> Option<Unit> {
+  if let Some(radians) = angle(name) {
+    return Some(Unit::new(name, radians, Dimension::default()));
+  }
+  if let Some(&(_, symbol)) = ALIASES.iter().find(|a| a.0 == name) {
+    return lookup(symbol);
+  }
+  if let Some(&(symbol, factor, offset)) = TEMPERATURES.iter().find(|t| t.0 == name) {
+    return Some(Unit {
+      offset,
+      ..Unit::new(symbol, factor, Dimension([0, 0, 0, 0, 1, 0, 0, 0]))
+    });
+  }
+  if let Some(&(symbol, factor, dimension, _)) = UNITS.iter().find(|u| u.0 == name) {
+    return Some(Unit::new(symbol, factor, Dimension(dimension)));
+  }
+  PREFIXES.iter().find_map(|&(prefix, scale)| {
+    let base = name.strip_prefix(prefix)?;
+    let &(_, factor, dimension, _) = UNITS.iter().find(|u| u.0 == base && u.3)?;
+    Some(Unit::new(name, scale * factor, Dimension(dimension)))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::Operation::*;
+  use super::*;
+
+  fn q(value: f64, name: &str) -> Value {
+    apply(&Value::Real(value), lookup(name).unwrap()).unwrap()
+  }
+
+  fn magnitude(value: Result<Value, UnitError>) -> f64 {
+    match value {
+      Ok(Value::Quantity(q)) => q.value,
+      value => panic!("Expected a quantity, got {:?}", value),
+    }
+  }
+
+  #[test]
+  fn test_lookup() {
+    assert_eq!(lookup("km").unwrap().factor, 1000.);
+    assert_eq!(lookup("kg").unwrap().factor, 1.);
+    assert_eq!(lookup("kWh").unwrap().factor, 3.6e6);
+    assert_eq!(lookup("ft").unwrap().factor, 0.3048);
+    assert_eq!(lookup("mph").unwrap().dimension, Dimension(SPEED));
+    assert_eq!(lookup("kft"), None);
+    assert_eq!(lookup("days"), lookup("day"));
+    assert_eq!(lookup("°").map(|u| u.factor), Some(std::f64::consts::PI / 180.));
+    assert_eq!(lookup("foo"), None);
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    let sum = binary(Plus, &q(5., "km"), &q(300., "m"));
+    assert_eq!(magnitude(sum.clone()), 5.3);
+    assert!((magnitude(convert(&sum.unwrap(), lookup("mi").unwrap())) - 3.293267).abs() < 1e-6);
+    assert_eq!(
+      binary(Plus, &q(1., "m"), &q(1., "s")),
+      Err(UnitError::DimensionMismatch(Dimension(LENGTH), Dimension(TIME)))
+    );
+    assert_eq!(binary(Div, &q(5., "km"), &q(500., "m")), Ok(Value::Real(10.)));
+    let energy = binary(Times, &q(3., "kW"), &q(2., "h")).unwrap();
+    assert_eq!(energy.to_string(), "6 kW h");
+    assert_eq!(magnitude(convert(&energy, lookup("kWh").unwrap())), 6.);
+    let area = binary(Pow, &q(3., "m"), &Value::Real(2.)).unwrap();
+    assert_eq!(area.to_string(), "9 m^2");
+    assert_eq!(binary(Pow, &area, &Value::Real(0.5)).unwrap().to_string(), "3 m");
+    assert_eq!(binary(Pow, &q(1., "m"), &Value::Real(0.5)), Err(UnitError::InvalidPower(0.5)));
+    let speed = binary(Div, &q(100., "m"), &q(9.58, "s")).unwrap();
+    assert_eq!(speed.to_string(), format!("{} m/s", 100. / 9.58));
+  }
+
+  #[test]
+  fn test_temperatures() {
+    let degf = convert(&q(20., "degC"), lookup("degF").unwrap()).unwrap();
+    assert_eq!(degf.to_string(), "68 degF");
+    assert_eq!(magnitude(convert(&q(-40., "degF"), lookup("degC").unwrap())), -40.);
+    assert_eq!(magnitude(convert(&q(32., "degF"), lookup("degC").unwrap())), 0.);
+    assert_eq!(magnitude(convert(&q(100., "degC"), lookup("degF").unwrap())), 212.);
+    assert_eq!(magnitude(convert(&q(0., "degC"), lookup("K").unwrap())), 273.15);
+    assert_eq!(magnitude(convert(&q(36.6, "degC"), lookup("degF").unwrap())), 97.88);
+    // Differences do not use the offset
+    assert_eq!(magnitude(binary(Plus, &q(20., "degC"), &q(5., "K"))), 25.);
+  }
+
+  #[test]
+  fn test_currencies() {
+    // 2026-10-18 and 2026-09-01
+    let eur = Unit::currency("EUR", 1.08, 1_792_281_600);
+    let gbp = Unit::currency("GBP", 1.25, 1_788_220_800);
+    let price = apply(&Value::Real(120.), lookup("USD").unwrap()).unwrap();
+    let euros = convert(&price, eur.clone()).unwrap();
+    assert!((magnitude(Ok(euros.clone())) - 111.11111111111111).abs() < 1e-9);
+    assert!(euros.to_string().ends_with(" EUR (rates of 2026-10-18)"));
+    let total = binary(Plus, &euros, &apply(&Value::Real(10.), gbp).unwrap()).unwrap();
+    assert!(total.to_string().ends_with(" EUR (rates of 2026-09-01)"));
+    assert_eq!(
+      binary(Plus, &price, &q(1., "m")),
+      Err(UnitError::DimensionMismatch(Dimension(CURRENCY), Dimension(LENGTH)))
+    );
+    assert_eq!(binary(Div, &euros, &convert(&price, eur).unwrap()), Ok(Value::Real(1.)));
+  }
+
+  #[test]
+  fn test_display() {
+    let unit = lookup("J").unwrap().mul(&lookup("kg").unwrap().mul(&lookup("K").unwrap()).powi(-1));
+    assert_eq!(unit.to_string(), "J/(kg K)");
+    assert_eq!(lookup("s").unwrap().powi(-1).to_string(), "1/s");
+    assert_eq!(Dimension(FORCE).to_string(), "m kg/s^2");
+
+    let duration = |seconds: f64| Quantity {
+      value: seconds,
+      unit: Unit::duration(),
+    };
+    assert_eq!(duration(5400.).to_string(), "1:30:00");
+    assert_eq!(duration(-9910.5).to_string(), "-2:45:10.5");
+    assert_eq!(duration(90000.).to_string(), "25:00:00");
+  }
+}
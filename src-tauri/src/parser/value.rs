@@ -0,0 +1,293 @@
+use super::dates::Date;
+use super::decimal::Decimal;
+use super::integer;
+use super::rational::Fraction;
+use super::units::Quantity;
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// The result of evaluating an expression.
+///
+/// Values serialise untagged so a plain real number still reaches the UI as a JSON number, a
+/// complex number as `{ "re": .., "im": .. }`, a decimal as `{ "decimal": "..", "approximate": ..
+/// }`, a fraction as `{ "numer": "..", "denom": "..", "mixed": .. }`, a big integer as
+/// `{ "integer": ".." }`, a list as an array, a table as `{ "columns": [..], "rows": [[..]] }`, a
+/// quantity as `{ "quantity": .., "unit": { "symbols": [["km", 1]], .. } }` and a date as
+/// `{ "date": "2026-10-18T00:00:00", "time": false }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+  /// A real number.
+  Real(#[serde(deserialize_with = "deserialize_real")] f64),
+  /// A list of values, e.g. the `[r, theta]` pair returned by `polar`.
+  ///
+  /// Declared before `Complex` since serde would also accept `[re, im]` arrays as a complex.
+  List(Vec<Value>),
+  /// A complex number with a non-zero imaginary part.
+  Complex(#[serde(with = "complex_repr")] Complex64),
+  /// An arbitrary-precision decimal, only produced in decimal mode.
+  Decimal(Decimal),
+  /// An exact fraction, only produced in fraction mode.
+  Fraction(Fraction),
+  /// An integer too large to be exactly represented as an f64, e.g. `52!`.
+  Integer(#[serde(with = "integer_repr")] BigInt),
+  /// Rows of values under named columns, e.g. the schedule returned by `amortize`.
+  Table(Table),
+  /// A number with a unit, e.g. `5.3 km`.
+  Quantity(Quantity),
+  /// A calendar date, e.g. `2026-10-18` or `now`.
+  Date(Date),
+}
+
+/// A table shown as such in the history, every row has one value per column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+  pub columns: Vec<String>,
+  pub rows: Vec<Vec<Value>>,
+}
+
+impl Value {
+  /// Builds a value from a complex number, collapsing it to a real when the imaginary part is 0.
+  pub fn from_complex(z: Complex64) -> Value {
+    if z.im == 0. {
+      Value::Real(z.re)
+    } else {
+      Value::Complex(z)
+    }
+  }
+
+  /// Returns the number if the value is real, decimals are converted to the nearest f64.
+  pub fn as_real(&self) -> Option<f64> {
+    match *self {
+      Value::Real(x) => Some(x),
+      Value::Decimal(ref d) => Some(d.to_f64()),
+      Value::Fraction(ref f) => Some(f.to_f64()),
+      Value::Integer(ref n) => Some(integer::to_f64(n)),
+      _ => None,
+    }
+  }
+
+  /// Returns the value as a complex number, `None` for lists.
+  pub fn as_complex(&self) -> Option<Complex64> {
+    match *self {
+      Value::Real(x) => Some(Complex64::new(x, 0.)),
+      Value::Decimal(ref d) => Some(Complex64::new(d.to_f64(), 0.)),
+      Value::Fraction(ref f) => Some(Complex64::new(f.to_f64(), 0.)),
+      Value::Integer(ref n) => Some(Complex64::new(integer::to_f64(n), 0.)),
+      Value::Complex(z) => Some(z),
+      Value::List(_) | Value::Table(_) | Value::Quantity(_) | Value::Date(_) => None,
+    }
+  }
+}
+
+impl From<f64> for Value {
+  fn from(x: f64) -> Value {
+    Value::Real(x)
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Value::Real(x) => write!(f, "{}", x),
+      Value::Complex(z) => {
+        if z.re == 0. {
+          write!(f, "{}i", z.im)
+        } else if z.im < 0. {
+          write!(f, "{}-{}i", z.re, -z.im)
+        } else {
+          write!(f, "{}+{}i", z.re, z.im)
+        }
+      }
+      Value::Decimal(ref d) => {
+        if d.approximate {
+          write!(f, "≈")?;
+        }
+        write!(f, "{}", d.decimal)
+      }
+      Value::Fraction(ref fraction) => write!(f, "{}", fraction),
+      Value::Integer(ref n) => write!(f, "{}", n),
+      Value::List(ref xs) => {
+        write!(f, "[")?;
+        for (i, x) in xs.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}", x)?;
+        }
+        write!(f, "]")
+      }
+      Value::Quantity(ref q) => write!(f, "{}", q),
+      Value::Date(ref d) => write!(f, "{}", d),
+      Value::Table(ref table) => {
+        write!(f, "{}", table.columns.join(" | "))?;
+        for row in table.rows.iter() {
+          writeln!(f)?;
+          for (i, x) in row.iter().enumerate() {
+            if i > 0 {
+              write!(f, " | ")?;
+            }
+            write!(f, "{}", x)?;
+          }
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// serde_json writes non-finite floats as `null`, read them back as NaN instead of failing.
+pub(super) fn deserialize_real<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+  Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+mod integer_repr {
+  use num_bigint::BigInt;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+  use std::str::FromStr;
+
+  #[derive(Serialize, Deserialize)]
+  struct Repr {
+    integer: String,
+  }
+
+  pub fn serialize<S: Serializer>(n: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+    Repr {
+      integer: n.to_string(),
+    }
+    .serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+    let repr = Repr::deserialize(deserializer)?;
+    BigInt::from_str(&repr.integer).map_err(serde::de::Error::custom)
+  }
+}
+
+mod complex_repr {
+  use num_complex::Complex64;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  #[derive(Serialize, Deserialize)]
+  struct Repr {
+    re: f64,
+    im: f64,
+  }
+
+  pub fn serialize<S: Serializer>(z: &Complex64, serializer: S) -> Result<S::Ok, S::Error> {
+    Repr { re: z.re, im: z.im }.serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Complex64, D::Error> {
+    let repr = Repr::deserialize(deserializer)?;
+    Ok(Complex64::new(repr.re, repr.im))
+  }
+}
+
+/// Serialises a value to the text stored in the database for non-real results.
+pub fn to_json(value: &Value) -> String {
+  serde_json::to_string(value).expect("Values are always serialisable")
+}
+
+/// Parses a value previously written with `to_json`.
+pub fn from_json(s: &str) -> Result<Value, serde_json::Error> {
+  serde_json::from_str(s)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::units::{Dimension, Unit};
+  use chrono::NaiveDate;
+  use super::*;
+
+  #[test]
+  fn test_display() {
+    assert_eq!(Value::Real(2.5).to_string(), "2.5");
+    assert_eq!(Value::Complex(Complex64::new(3., 4.)).to_string(), "3+4i");
+    assert_eq!(Value::Complex(Complex64::new(1., -1.)).to_string(), "1-1i");
+    assert_eq!(Value::Complex(Complex64::new(0., 2.)).to_string(), "2i");
+    assert_eq!(
+      Value::List(vec![Value::Real(1.), Value::Real(2.)]).to_string(),
+      "[1, 2]"
+    );
+    let third = Decimal {
+      decimal: "0.3333".parse().unwrap(),
+      approximate: true,
+    };
+    assert_eq!(Value::Decimal(third).to_string(), "≈0.3333");
+    let half = Fraction::from_literal("1.5").unwrap();
+    assert_eq!(Value::Fraction(half.clone()).to_string(), "3/2");
+    assert_eq!(Value::Fraction(half.to_mixed()).to_string(), "1 1/2");
+    let table = Table {
+      columns: vec!["n".into(), "n²".into()],
+      rows: vec![vec![Value::Real(1.), Value::Real(1.)], vec![Value::Real(2.), Value::Real(4.)]],
+    };
+    assert_eq!(Value::Table(table).to_string(), "n | n²\n1 | 1\n2 | 4");
+    let speed = Value::Quantity(Quantity {
+      value: 60.,
+      unit: Unit {
+        symbols: vec![("km".into(), 1), ("h".into(), -1)],
+        factor: 1000. / 3600.,
+        offset: 0.,
+        dimension: Dimension([1, 0, -1, 0, 0, 0, 0, 0]),
+        updated: None,
+        clock: false,
+      },
+    });
+    assert_eq!(speed.to_string(), "60 km/h");
+  }
+
+  #[test]
+  fn test_json_roundtrip() {
+    let values = vec![
+      Value::Real(1.5),
+      Value::Complex(Complex64::new(0., 1.)),
+      Value::List(vec![Value::Real(5.), Value::Real(0.9272952180016122)]),
+      Value::Decimal(Decimal::exact("0.30".parse().unwrap())),
+      Value::Fraction(Fraction::from_literal("-0.125").unwrap()),
+      Value::Integer("-123456789012345678901234567890".parse().unwrap()),
+      Value::Table(Table {
+        columns: vec!["x".into()],
+        rows: vec![vec![Value::Real(1.)], vec![Value::Complex(Complex64::new(0., 1.))]],
+      }),
+      Value::Quantity(Quantity {
+        value: 20.,
+        unit: Unit {
+          offset: 273.15,
+          ..Unit::new("degC", 1., Dimension([0, 0, 0, 0, 1, 0, 0, 0]))
+        },
+      }),
+      Value::Date(Date {
+        datetime: NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_hms_opt(9, 30, 0).unwrap(),
+        time: true,
+        zone: Some("Asia/Tokyo".into()),
+      }),
+    ];
+    for v in values {
+      assert_eq!(from_json(&to_json(&v)).unwrap(), v);
+    }
+    assert_eq!(to_json(&Value::Complex(Complex64::new(3., 4.))), r#"{"re":3.0,"im":4.0}"#);
+    assert_eq!(
+      to_json(&Value::Decimal(Decimal::exact("0.3".parse().unwrap()))),
+      r#"{"decimal":"0.3","approximate":false}"#
+    );
+    assert_eq!(
+      to_json(&Value::Fraction(Fraction::from_literal("0.5").unwrap())),
+      r#"{"numer":"1","denom":"2","mixed":false}"#
+    );
+    assert_eq!(
+      to_json(&Value::Integer("12345678901234567890".parse().unwrap())),
+      r#"{"integer":"12345678901234567890"}"#
+    );
+    assert_eq!(
+      to_json(&Value::Date(Date::new(
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().into(),
+        false
+      ))),
+      r#"{"date":"2026-10-18T00:00:00","time":false}"#
+    );
+    assert!(matches!(from_json("null"), Ok(Value::Real(x)) if x.is_nan()));
+  }
+}
@@ -2,4 +2,4 @@ pub mod system_commands;
 mod system_service;
 
 pub use system_service::get_config;
-use system_service::Config;
+pub use system_service::Config;
@@ -1,6 +1,10 @@
+use super::super::parser::{
+  AngleMode, Context, DecimalOptions, FormatOptions, NumberMode, Rounding,
+};
 use super::Config;
+use chrono::NaiveDate;
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{State, Window};
 
 /// Returns the dark_mode preference
 ///
@@ -28,3 +32,172 @@ pub fn set_system_theme(config: State<Mutex<Config>>, dark_mode: bool) {
   let mut config = config.lock().unwrap();
   config.update_dark_mode(dark_mode);
 }
+
+/// Returns whether complex mode is enabled
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passses it to the function for us).
+///
+/// Returns:
+///
+/// Whether or not results without a real value are complex numbers.
+#[tauri::command]
+pub fn get_complex_mode(config: State<Mutex<Config>>) -> bool {
+  config.lock().unwrap().complex_mode
+}
+
+/// Enables or disables complex mode in the preferences and in the parser context
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `complex_mode`: bool - This is the value that will be passed to the command.
+#[tauri::command]
+pub fn set_complex_mode(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  complex_mode: bool,
+) {
+  config.lock().unwrap().update_complex_mode(complex_mode);
+  parser_context
+    .lock()
+    .unwrap()
+    .set_complex_mode(complex_mode);
+}
+
+/// Returns the number mode, `float` or `decimal`
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passses it to the function for us).
+#[tauri::command]
+pub fn get_number_mode(config: State<Mutex<Config>>) -> NumberMode {
+  config.lock().unwrap().number_mode
+}
+
+/// Switches between f64 and exact decimal arithmetic in the preferences and in the parser context
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `number_mode`: NumberMode - This is the value that will be passed to the command.
+#[tauri::command]
+pub fn set_number_mode(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  number_mode: NumberMode,
+) {
+  config.lock().unwrap().update_number_mode(number_mode);
+  parser_context.lock().unwrap().set_number_mode(number_mode);
+}
+
+/// Updates the precision and rounding mode of decimal results
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `precision`: Significant digits kept by decimal results.
+/// * `rounding`: How decimal results are rounded to `precision`.
+#[tauri::command]
+pub fn set_decimal_options(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  precision: u64,
+  rounding: Rounding,
+) {
+  let options = DecimalOptions { precision, rounding };
+  config.lock().unwrap().update_decimal_options(options);
+  parser_context.lock().unwrap().set_decimal_options(options);
+}
+
+/// Returns the angle mode, `radians`, `degrees` or `gradians`
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passses it to the function for us).
+#[tauri::command]
+pub fn get_angle_mode(config: State<Mutex<Config>>) -> AngleMode {
+  config.lock().unwrap().angle_mode
+}
+
+/// Switches the unit of the angles of trigonometric functions in the preferences and in the parser
+/// context
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `angle_mode`: AngleMode - This is the value that will be passed to the command.
+#[tauri::command]
+pub fn set_angle_mode(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  angle_mode: AngleMode,
+) {
+  config.lock().unwrap().update_angle_mode(angle_mode);
+  parser_context.lock().unwrap().set_angle_mode(angle_mode);
+}
+
+/// Returns the holidays skipped by `workdays`
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passses it to the function for us).
+#[tauri::command]
+pub fn get_holidays(config: State<Mutex<Config>>) -> Vec<NaiveDate> {
+  config.lock().unwrap().holidays.clone()
+}
+
+/// Replaces the holidays skipped by `workdays` in the preferences and in the parser context
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `holidays`: The holidays as `YYYY-MM-DD` dates.
+#[tauri::command]
+pub fn set_holidays(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  holidays: Vec<NaiveDate>,
+) {
+  config.lock().unwrap().update_holidays(holidays.clone());
+  parser_context.lock().unwrap().set_holidays(holidays);
+}
+
+/// Returns the display settings of results
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passses it to the function for us).
+#[tauri::command]
+pub fn get_format_options(config: State<Mutex<Config>>) -> FormatOptions {
+  config.lock().unwrap().format.clone()
+}
+
+/// Updates the display settings of results in the preferences, and whether the parser context
+/// rounds away float noise, then lets the history reformat its results
+///
+/// Arguments:
+///
+/// * `config`: This is the global preference state (tauri passes it to the function for us).
+/// * `parser_context`: The math context used to evaluate operations.
+/// * `window`: The window notified with a `format_changed` event.
+/// * `options`: FormatOptions - This is the value that will be passed to the command.
+#[tauri::command]
+pub fn set_format_options(
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  window: Window,
+  options: FormatOptions,
+) {
+  parser_context.lock().unwrap().set_snap(options.snap);
+  config.lock().unwrap().update_format(options);
+  window
+    .emit("format_changed", ())
+    .expect("Could not emit format_changed");
+}
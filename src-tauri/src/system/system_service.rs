@@ -1,3 +1,7 @@
+use super::super::parser::{
+  AngleMode, Context, DecimalOptions, FormatOptions, NumberMode, Rounding,
+};
+use chrono::NaiveDate;
 use confy::{load, store};
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +33,31 @@ pub fn get_config() -> Config {
 pub struct Config {
   /// `dark_mode`: Whether or not the user wants to use dark mode.
   pub dark_mode: bool,
+  /// `complex_mode`: Whether results without a real value (e.g. `sqrt(-1)`) are complex numbers.
+  #[serde(default)]
+  pub complex_mode: bool,
+  /// `number_mode`: Whether numbers are f64 floats or exact decimals.
+  #[serde(default)]
+  pub number_mode: NumberMode,
+  /// `decimal_precision`: Significant digits kept by decimal results.
+  #[serde(default = "default_decimal_precision")]
+  pub decimal_precision: u64,
+  /// `decimal_rounding`: How decimal results are rounded to `decimal_precision`.
+  #[serde(default)]
+  pub decimal_rounding: Rounding,
+  /// `angle_mode`: Whether trigonometric functions work in radians, degrees or gradians.
+  #[serde(default)]
+  pub angle_mode: AngleMode,
+  /// `holidays`: Days skipped by `workdays`, on top of the weekends.
+  #[serde(default)]
+  pub holidays: Vec<NaiveDate>,
+  /// `format`: How results are displayed, e.g. the notation and the decimal mark.
+  #[serde(default)]
+  pub format: FormatOptions,
+}
+
+fn default_decimal_precision() -> u64 {
+  DecimalOptions::default().precision
 }
 
 impl Default for Config {
@@ -45,6 +74,13 @@ impl Default for Config {
 
     Self {
       dark_mode: dark_mode,
+      complex_mode: false,
+      number_mode: NumberMode::default(),
+      decimal_precision: default_decimal_precision(),
+      decimal_rounding: Rounding::default(),
+      angle_mode: AngleMode::default(),
+      holidays: vec![],
+      format: FormatOptions::default(),
     }
   }
 }
@@ -59,4 +95,87 @@ impl Config {
     self.dark_mode = dark_mode;
     store("com.josephchotard.calculator", self).unwrap();
   }
+
+  /// It updates the complex mode value in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `complex_mode`: bool
+  pub fn update_complex_mode(&mut self, complex_mode: bool) {
+    self.complex_mode = complex_mode;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// It updates the number mode value in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `number_mode`: NumberMode
+  pub fn update_number_mode(&mut self, number_mode: NumberMode) {
+    self.number_mode = number_mode;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// It updates the decimal precision and rounding in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `options`: DecimalOptions
+  pub fn update_decimal_options(&mut self, options: DecimalOptions) {
+    self.decimal_precision = options.precision;
+    self.decimal_rounding = options.rounding;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// It updates the angle mode in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `angle_mode`: AngleMode
+  pub fn update_angle_mode(&mut self, angle_mode: AngleMode) {
+    self.angle_mode = angle_mode;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// It updates the holidays in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `holidays`: Vec<NaiveDate>
+  pub fn update_holidays(&mut self, holidays: Vec<NaiveDate>) {
+    self.holidays = holidays;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// It updates the display settings of results in the config and then stores the preferences
+  ///
+  /// Arguments:
+  ///
+  /// * `format`: FormatOptions
+  pub fn update_format(&mut self, format: FormatOptions) {
+    self.format = format;
+    store("com.josephchotard.calculator", self).unwrap();
+  }
+
+  /// The decimal options stored in the preferences
+  pub fn decimal_options(&self) -> DecimalOptions {
+    DecimalOptions {
+      precision: self.decimal_precision,
+      rounding: self.decimal_rounding,
+    }
+  }
+
+  /// Applies the evaluation preferences to the parser context
+  ///
+  /// Arguments:
+  ///
+  /// * `context`: The math context used to evaluate operations.
+  pub fn configure_context(&self, context: &mut Context) {
+    context.set_complex_mode(self.complex_mode);
+    context.set_number_mode(self.number_mode);
+    context.set_decimal_options(self.decimal_options());
+    context.set_angle_mode(self.angle_mode);
+    context.set_holidays(self.holidays.clone());
+    context.set_snap(self.format.snap);
+  }
 }
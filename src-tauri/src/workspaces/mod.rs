@@ -0,0 +1,4 @@
+pub mod workspaces_commands;
+mod workspaces_service;
+
+pub use workspaces_service::current_workspace;
@@ -0,0 +1,122 @@
+use super::super::parser::Context;
+use super::super::system::Config;
+use super::workspaces_service::{
+  create_workspace, delete_workspace, get_workspaces, rename_workspace, switch_workspace, Workspace,
+};
+use rusqlite::Connection;
+use std::sync::Mutex;
+use tauri::{State, Window};
+
+/// Returns the workspaces
+///
+/// Arguments:
+///
+/// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+///
+/// Returns:
+///
+/// The workspaces in the order they were created, one of them is active.
+#[tauri::command]
+pub fn get_workspaces_command(conn: State<Mutex<Connection>>) -> Result<Vec<Workspace>, String> {
+  get_workspaces(&conn.lock().unwrap()).map_err(|e| e.to_string())
+}
+
+/// Creates an empty workspace
+///
+/// Arguments:
+///
+/// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+/// * `name`: The name of the workspace, e.g. "Taxes 2026".
+///
+/// Returns:
+///
+/// The new workspace, or an error if the name is empty or already taken.
+#[tauri::command]
+pub fn create_workspace_command(
+  conn: State<Mutex<Connection>>,
+  name: &str,
+) -> Result<Workspace, String> {
+  create_workspace(&conn.lock().unwrap(), name)
+}
+
+/// Renames a workspace
+///
+/// Arguments:
+///
+/// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+/// * `id`: The id of the workspace.
+/// * `name`: The new name.
+///
+/// Returns:
+///
+/// The renamed workspace, or an error if the name is empty or already taken.
+#[tauri::command]
+pub fn rename_workspace_command(
+  conn: State<Mutex<Connection>>,
+  id: i64,
+  name: &str,
+) -> Result<Workspace, String> {
+  rename_workspace(&conn.lock().unwrap(), id, name)
+}
+
+/// Makes a workspace active, its history and variables replace those of the previous one
+///
+/// Arguments:
+///
+/// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+/// * `config`: The preferences, applied to the context of the workspace.
+/// * `parser_context`: The math context, rebuilt from the variables of the workspace.
+/// * `window`: The window notified with a `workspace_changed` event.
+/// * `id`: The id of the workspace.
+///
+/// Returns:
+///
+/// The active workspace.
+#[tauri::command]
+pub fn switch_workspace_command(
+  conn: State<Mutex<Connection>>,
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  window: Window,
+  id: i64,
+) -> Result<Workspace, String> {
+  let workspace = switch_workspace(
+    &conn.lock().unwrap(),
+    id,
+    &mut parser_context.lock().unwrap(),
+    &config.lock().unwrap(),
+  )?;
+  window.emit("workspace_changed", &workspace).expect("Could not emit workspace_changed");
+  Ok(workspace)
+}
+
+/// Deletes a workspace with its history and variables
+///
+/// Arguments:
+///
+/// * `conn`: State<Mutex<Connection>> - this is the connection to the database.
+/// * `config`: The preferences, applied to the context of the next workspace.
+/// * `parser_context`: The math context, rebuilt if the active workspace is deleted.
+/// * `window`: The window notified with a `workspace_changed` event.
+/// * `id`: The id of the workspace.
+///
+/// Returns:
+///
+/// The active workspace, or an error for the last workspace.
+#[tauri::command]
+pub fn delete_workspace_command(
+  conn: State<Mutex<Connection>>,
+  config: State<Mutex<Config>>,
+  parser_context: State<Mutex<Context>>,
+  window: Window,
+  id: i64,
+) -> Result<Workspace, String> {
+  let workspace = delete_workspace(
+    &conn.lock().unwrap(),
+    id,
+    &mut parser_context.lock().unwrap(),
+    &config.lock().unwrap(),
+  )?;
+  window.emit("workspace_changed", &workspace).expect("Could not emit workspace_changed");
+  Ok(workspace)
+}
@@ -0,0 +1,251 @@
+use super::super::operations::create_context_from_db;
+use super::super::parser::Context;
+use super::super::system::Config;
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+/// A named set of operations and variables, e.g. "Taxes 2026", the units and rates are shared.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+  pub id: i64,
+  pub name: String,
+  /// Whether the operations are evaluated and stored in this workspace.
+  pub active: bool,
+}
+
+/// The id of the active workspace, the first one if none is marked as active.
+pub fn current_workspace(conn: &Connection) -> i64 {
+  conn
+    .query_row("SELECT id FROM workspaces ORDER BY active DESC, id ASC LIMIT 1;", [], |r| r.get(0))
+    .unwrap_or(1)
+}
+
+/// Returns the workspaces in the order they were created
+///
+/// Arguments:
+///
+/// * `conn`: The connection to the database.
+///
+/// Returns:
+///
+/// The workspaces, one of them is active.
+pub fn get_workspaces(conn: &Connection) -> Result<Vec<Workspace>> {
+  let active = current_workspace(conn);
+  let mut stmt = conn.prepare("SELECT id, name FROM workspaces ORDER BY id ASC;")?;
+  let rows = stmt.query_map(params![], |row| {
+    let id = row.get(0)?;
+    Ok(Workspace { id, name: row.get(1)?, active: id == active })
+  })?;
+  rows.collect()
+}
+
+fn get_workspace(conn: &Connection, id: i64) -> std::result::Result<Workspace, String> {
+  get_workspaces(conn)
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .find(|workspace| workspace.id == id)
+    .ok_or_else(|| format!("Unknown workspace {}", id))
+}
+
+/// The name of a workspace without the surrounding spaces, it cannot be empty or taken.
+fn check_name<'n>(
+  conn: &Connection,
+  name: &'n str,
+  id: Option<i64>,
+) -> std::result::Result<&'n str, String> {
+  let name = name.trim();
+  if name.is_empty() {
+    return Err("The name of a workspace cannot be empty".to_string());
+  }
+  let taken: bool = conn
+    .query_row(
+      "SELECT EXISTS (SELECT id FROM workspaces WHERE name = ?1 AND id IS NOT ?2);",
+      params![name, id],
+      |r| r.get(0),
+    )
+    .map_err(|e| e.to_string())?;
+  if taken {
+    return Err(format!("There is already a workspace named `{}`", name));
+  }
+  Ok(name)
+}
+
+/// Creates an empty workspace, the active workspace does not change
+///
+/// Arguments:
+///
+/// * `conn`: The connection to the database.
+/// * `name`: The name of the new workspace.
+///
+/// Returns:
+///
+/// The new workspace or an error if the name is empty or taken.
+pub fn create_workspace(conn: &Connection, name: &str) -> std::result::Result<Workspace, String> {
+  let name = check_name(conn, name, None)?;
+  conn
+    .execute("INSERT INTO workspaces (name) VALUES (?1);", params![name])
+    .map_err(|e| e.to_string())?;
+  get_workspace(conn, conn.last_insert_rowid())
+}
+
+/// Renames a workspace
+///
+/// Arguments:
+///
+/// * `conn`: The connection to the database.
+/// * `id`: The id of the workspace.
+/// * `name`: The new name.
+///
+/// Returns:
+///
+/// The renamed workspace or an error if the name is empty or taken.
+pub fn rename_workspace(
+  conn: &Connection,
+  id: i64,
+  name: &str,
+) -> std::result::Result<Workspace, String> {
+  get_workspace(conn, id)?;
+  let name = check_name(conn, name, Some(id))?;
+  conn
+    .execute("UPDATE workspaces SET name = ?1 WHERE id = ?2;", params![name, id])
+    .map_err(|e| e.to_string())?;
+  get_workspace(conn, id)
+}
+
+/// Makes a workspace active and rebuilds the context from its variables
+///
+/// Arguments:
+///
+/// * `conn`: The connection to the database.
+/// * `id`: The id of the workspace.
+/// * `context`: The math context, replaced by the one of the workspace.
+/// * `config`: The preferences, applied to the new context.
+///
+/// Returns:
+///
+/// The active workspace.
+pub fn switch_workspace(
+  conn: &Connection,
+  id: i64,
+  context: &mut Context,
+  config: &Config,
+) -> std::result::Result<Workspace, String> {
+  get_workspace(conn, id)?;
+  conn
+    .execute("UPDATE workspaces SET active = (id = ?1);", params![id])
+    .map_err(|e| e.to_string())?;
+  *context = create_context_from_db(conn);
+  config.configure_context(context);
+  get_workspace(conn, id)
+}
+
+/// Deletes a workspace with its operations and variables, the first remaining workspace becomes
+/// active if it was the active one
+///
+/// Arguments:
+///
+/// * `conn`: The connection to the database.
+/// * `id`: The id of the workspace.
+/// * `context`: The math context, rebuilt when the active workspace changes.
+/// * `config`: The preferences, applied to the new context.
+///
+/// Returns:
+///
+/// The active workspace, or an error for the last workspace.
+pub fn delete_workspace(
+  conn: &Connection,
+  id: i64,
+  context: &mut Context,
+  config: &Config,
+) -> std::result::Result<Workspace, String> {
+  let workspaces = get_workspaces(conn).map_err(|e| e.to_string())?;
+  let deleted = workspaces
+    .iter()
+    .find(|workspace| workspace.id == id)
+    .ok_or_else(|| format!("Unknown workspace {}", id))?;
+  if workspaces.len() == 1 {
+    return Err("The last workspace cannot be deleted".to_string());
+  }
+  let delete = || -> Result<()> {
+    let transaction = conn.unchecked_transaction()?;
+    transaction.execute("DELETE FROM operations WHERE workspace = ?1;", params![id])?;
+    transaction.execute("DELETE FROM variables WHERE workspace = ?1;", params![id])?;
+    transaction.execute("DELETE FROM workspaces WHERE id = ?1;", params![id])?;
+    transaction.commit()
+  };
+  delete().map_err(|e| e.to_string())?;
+  if deleted.active {
+    switch_workspace(conn, current_workspace(conn), context, config)
+  } else {
+    get_workspace(conn, current_workspace(conn))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::super::parser::{builtin, ContextProvider};
+  use super::*;
+
+  fn create_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("Could not create in-memory database");
+    conn
+      .execute_batch(
+        "CREATE TABLE workspaces (id integer NOT NULL, name text NOT NULL UNIQUE, active integer NOT NULL DEFAULT 0, PRIMARY KEY (id));
+        INSERT INTO workspaces (id, name, active) VALUES (1, 'Default', 1);
+        CREATE TABLE operations (id integer NOT NULL, workspace integer NOT NULL DEFAULT 1, operation text NOT NULL, result num DEFAULT NULL, representation text DEFAULT NULL, note text DEFAULT NULL, PRIMARY KEY (id));
+        CREATE TABLE variables (workspace integer NOT NULL DEFAULT 1, name text NOT NULL,value num DEFAULT NULL, constant integer NOT NULL DEFAULT 0, formula text DEFAULT NULL, PRIMARY KEY (workspace, name));
+        CREATE TABLE units (name text NOT NULL, value num NOT NULL, PRIMARY KEY (name));
+        CREATE TABLE rates (code text NOT NULL, rate num NOT NULL, updated integer NOT NULL, PRIMARY KEY (code));
+        INSERT INTO operations (operation, result) VALUES ('x = 1', 1);
+        INSERT INTO variables (name, value) VALUES ('x', 1);",
+      )
+      .expect("Could not create tables");
+    conn
+  }
+
+  #[test]
+  fn test_workspaces() {
+    let conn = create_db();
+    let config = Config::default();
+    let mut context = create_context_from_db(&conn);
+
+    let taxes = create_workspace(&conn, " Taxes 2026 ").unwrap();
+    assert_eq!(taxes, Workspace { id: 2, name: "Taxes 2026".into(), active: false });
+    assert_eq!(
+      create_workspace(&conn, "Default").map(|w| w.id),
+      Err("There is already a workspace named `Default`".into())
+    );
+    assert!(create_workspace(&conn, "  ").is_err());
+    assert_eq!(current_workspace(&conn), 1);
+
+    // The variables of the other workspaces are not visible
+    let active = switch_workspace(&conn, taxes.id, &mut context, &config).unwrap();
+    assert!(active.active);
+    assert_eq!(current_workspace(&conn), 2);
+    assert_eq!(context.get_var("x"), None);
+    assert_eq!(context.get_var("pi"), Some(std::f64::consts::PI));
+    conn
+      .execute("INSERT INTO variables (workspace, name, value) VALUES (2, 'x', 2);", params![])
+      .expect("Could not insert variable");
+    assert_eq!(create_context_from_db(&conn).get_var("x"), Some(2.));
+
+    let renamed = rename_workspace(&conn, 1, "Project A").unwrap();
+    assert_eq!(renamed, Workspace { id: 1, name: "Project A".into(), active: false });
+    assert!(rename_workspace(&conn, 2, "Project A").is_err());
+    assert!(rename_workspace(&conn, 2, "Taxes 2026").is_ok());
+    assert!(rename_workspace(&conn, 3, "Project B").is_err());
+
+    // Deleting the active workspace switches to the first one
+    let active = delete_workspace(&conn, 2, &mut context, &config).unwrap();
+    assert_eq!(active, Workspace { id: 1, name: "Project A".into(), active: true });
+    assert_eq!(context.get_var("x"), Some(1.));
+    let count: usize = conn
+      .query_row("SELECT COUNT(*) FROM variables WHERE workspace = 2;", [], |r| r.get(0))
+      .expect("Could not count variables");
+    assert_eq!(count, 0);
+    assert_eq!(
+      delete_workspace(&conn, 1, &mut builtin(), &config),
+      Err("The last workspace cannot be deleted".into())
+    );
+  }
+}
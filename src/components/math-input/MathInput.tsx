@@ -1,12 +1,42 @@
 import { tauri } from "@tauri-apps/api"
 import { FC, useContext, useEffect, useRef, useState } from "react"
-import { Operation } from "../operation-history"
+import { FormattedValue, Operation } from "../operation-history"
 import { Box } from "../system/box/Box"
 import { Text } from "../typography"
 import { CurrentOperationContext } from "./CurrentOperationContext"
 import * as styles from "./MathInput.css"
 
 
+/* Mirrors the `Signature` of the parser, the documentation of a function taking some numbers of arguments. */
+export interface Signature {
+  name: string,
+  /* Optional parameters end with `?` and a repeated last one with `...`. */
+  params: string[],
+  arity: { min: number, max: number | null },
+  domain: string | null,
+  description: string,
+  example: string | null
+}
+
+/* The function whose arguments are being typed, e.g. `round` in `2 * round(3.14`. */
+const currentFunction = (input: string): string | undefined => {
+  let depth = 0
+  for (let i = input.length - 1; i >= 0; i--) {
+    if (input[i] === ")") {
+      depth++
+    } else if (input[i] === "(") {
+      if (depth === 0) {
+        return /([A-Za-z_][A-Za-z0-9_]*)$/.exec(input.slice(0, i))?.[1]
+      }
+      depth--
+    }
+  }
+  return undefined
+}
+
+const formatSignature = (signature: Signature): string =>
+  `${signature.name}(${signature.params.join(", ")})${signature.description ? `: ${signature.description}` : ""}`
+
 const useFocus = () => {
   const htmlElRef = useRef<HTMLInputElement>(null)
   const setFocus = () => {
@@ -20,9 +50,10 @@ const MathInput: FC = () => {
   const { operation, setOperation } = useContext(CurrentOperationContext)
   const [response, setResponse] = useState("")
   const [inputRef, setFocus] = useFocus()
+  const [signatures, setSignatures] = useState<Signature[]>([])
 
   const handleOnChange = (event: React.ChangeEvent<HTMLInputElement>) => {
-    let input = event.target.value.toLowerCase()
+    let input = event.target.value
 
     /* Allow the user to start a new equation without having to type in `ans` first. */
     if (["+", "-", "*", "/"].includes(input)) {
@@ -34,11 +65,11 @@ const MathInput: FC = () => {
 
   const onEquationUpdated = (input: string) => {
     if (input.length > 0) {
-      tauri.invoke<number>("get_result_command", {
+      tauri.invoke<FormattedValue>("get_result_command", {
         input: input
       })
         .then((result) => {
-          setResponse(result?.toString() ?? "Infinity")
+          setResponse(result.formatted)
         })
         .catch((error) => {
           if (error === "command") {
@@ -69,6 +100,16 @@ const MathInput: FC = () => {
     onEquationUpdated(operation)
   }, [operation])
 
+  useEffect(() => {
+    tauri.invoke<Signature[]>("get_signatures_command").then(setSignatures)
+  }, [])
+
+  const name = currentFunction(operation)
+  const tooltip = signatures
+    .filter((signature) => signature.name === name)
+    .map(formatSignature)
+    .join("\n")
+
   const handleKeyDown = (event: React.KeyboardEvent<HTMLInputElement>) => {
     if (event.key === "Enter") {
       event.preventDefault()
@@ -97,6 +138,7 @@ const MathInput: FC = () => {
         value={operation}
         onChange={handleOnChange}
         onKeyDown={handleKeyDown}
+        title={tooltip || undefined}
       />
       {response && (
         <Text
@@ -1,3 +1,4 @@
 export * from './CurrentOperationContext'
 export { default } from './MathInput'
+export type { Signature } from './MathInput'
 
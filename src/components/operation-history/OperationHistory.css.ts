@@ -1,4 +1,4 @@
-import { style } from "@vanilla-extract/css"
+import { globalStyle, style } from "@vanilla-extract/css"
 import { sprinkles } from "../../styles"
 
 export const historyContainer = style([
@@ -36,4 +36,36 @@ export const result = style([
     font: "inherit",
     cursor: "pointer",
   }
-])
\ No newline at end of file
+])
+/* `help` entries span several lines, the signature, the domain and an example. */
+export const note = style([
+  sprinkles({
+    color: {
+      lightMode: 'yellow900',
+      darkMode: 'yellow200',
+    }
+  }),
+  {
+    whiteSpace: "pre-line",
+  }
+])
+
+export const table = style([
+  sprinkles({
+    color: {
+      lightMode: 'yellow900',
+      darkMode: 'yellow200',
+    }
+  }),
+  {
+    borderCollapse: "collapse",
+    fontSize: "0.875rem",
+    fontVariantNumeric: "tabular-nums",
+    textAlign: "right",
+    marginTop: "0.5rem",
+  }
+])
+
+globalStyle(`${table} th, ${table} td`, {
+  padding: "0.125rem 0.75rem",
+})
@@ -2,10 +2,28 @@ import { createRef, FC, useContext, useEffect } from "react"
 import { CurrentOperationContext } from "../math-input"
 import { Box } from "../system/box/Box"
 import { Text } from "../typography"
-import { OperationHistoryContext } from "./"
+import { formatValue, isTable, OperationHistoryContext, Table, Value } from "./"
 import * as styles from "./OperationHistory.css"
 
 
+/* Tables, e.g. the schedule returned by `amortize`, are shown as such rather than as a single line. */
+const ResultTable: FC<{ table: Table }> = ({ table }) => (
+  <table className={styles.table}>
+    <thead>
+      <tr>
+        {table.columns.map((column, index) => <th key={index}>{column}</th>)}
+      </tr>
+    </thead>
+    <tbody>
+      {table.rows.map((row, rowIndex) => (
+        <tr key={rowIndex}>
+          {row.map((cell, index) => <td key={index}>{formatValue(cell)}</td>)}
+        </tr>
+      ))}
+    </tbody>
+  </table>
+)
+
 const OperationHistory: FC = () => {
   const { history } = useContext(OperationHistoryContext)
   const { setOperation } = useContext(CurrentOperationContext)
@@ -15,12 +33,16 @@ const OperationHistory: FC = () => {
     elementRef.current?.scrollIntoView()
   }, [history])
 
-  const handleResultClick = (event: React.MouseEvent<HTMLButtonElement>, result: number) => {
+  const handleResultClick = (event: React.MouseEvent<HTMLButtonElement>, result: Value) => {
     /* If the user double clicked the result append the result to the current operation. */
     if (event.detail == 2) {
       event.preventDefault()
       event.stopPropagation()
-      setOperation(operation => `${operation}${result}`)
+      /* Fractions are inserted as a parenthesised division, so `3/` followed by `1/2` stays `3/(1/2)`. */
+      const text = typeof result === "object" && "numer" in result && result.denom !== "1"
+        ? `(${result.numer}/${result.denom})`
+        : formatValue(result).replace("≈", "")
+      setOperation(operation => `${operation}${text}`)
     }
   }
 
@@ -42,10 +64,21 @@ const OperationHistory: FC = () => {
           }}>
             {operation.operation}
           </Text>
-          <Text size='small' >
-            {"➛"} <button className={styles.result} onClick={(e) => handleResultClick(e, operation.result)}>{operation.result ?? Infinity}
-            </button>
-          </Text>
+          {operation.note || operation.result === null ? (
+            <Text size='small' className={styles.note}>{operation.note}</Text>
+          ) : isTable(operation.result) ? (
+            <ResultTable table={operation.result} />
+          ) : (
+            <Text size='small' >
+              {"➛"} <button className={styles.result} onClick={(e) => handleResultClick(e, operation.result)}>{operation.formatted}
+              </button>
+            </Text>
+          )}
+          {operation.updated?.map((variable) => (
+            <Text size='small' className={styles.note} key={variable.name}>
+              {variable.name} {"➛"} {variable.value?.formatted ?? variable.error}
+            </Text>
+          ))}
         </Box>
       ))}
       <div ref={elementRef} />
@@ -2,9 +2,146 @@ import { tauri } from "@tauri-apps/api"
 import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import { createContext, FC, useEffect, useState } from "react"
 
+/* Mirrors the untagged `Value` enum of the parser: a real, a list, a complex, a decimal, a fraction, a big integer, a table, a quantity or a date. */
+export type Value =
+  | number
+  | Value[]
+  | { re: number, im: number }
+  | { decimal: string, approximate: boolean }
+  | { numer: string, denom: string, mixed: boolean }
+  | { integer: string }
+  | Table
+  | Quantity
+  | DateValue
+
+export interface Table {
+  columns: string[],
+  rows: Value[][]
+}
+
+/* A number with a unit, `symbols` lists the named units and their powers, e.g. `[["m", 1], ["s", -2]]`.
+   Amounts of money carry the Unix time of the oldest exchange rate used in `updated`,
+   durations such as `1h30m` are seconds with `clock` set. */
+export interface Quantity {
+  quantity: number,
+  unit: { symbols: [string, number][], updated?: number, clock?: boolean }
+}
+
+/* A wall-clock time as `YYYY-MM-DDTHH:MM:SS`, `time` is false for plain dates and `zone` is absent for the local time zone. */
+export interface DateValue {
+  date: string,
+  time: boolean,
+  zone?: string
+}
+
+export const isTable = (value: Value): value is Table =>
+  typeof value === "object" && value !== null && "columns" in value
+
+/* Units are shown as `kg m/s^2`, `1/s` or `J/(kg K)`, like the parser does. */
+const formatUnit = (symbols: [string, number][]): string => {
+  const join = (positive: boolean) => symbols
+    .filter(([, power]) => (power > 0) === positive)
+    .map(([symbol, power]) => Math.abs(power) === 1 ? symbol : `${symbol}^${Math.abs(power)}`)
+  const numerator = join(true)
+  const denominator = join(false)
+  const unit = numerator.length > 0 ? numerator.join(" ") : "1"
+  if (denominator.length === 0) {
+    return unit
+  }
+  return denominator.length === 1 ? `${unit}/${denominator[0]}` : `${unit}/(${denominator.join(" ")})`
+}
+
+/* Durations are shown as `h:mm:ss` to the millisecond, like the parser does. */
+const formatClock = (seconds: number): string => {
+  const millis = Math.round(Math.abs(seconds) * 1000)
+  const hours = Math.floor(millis / 3_600_000)
+  const minutes = Math.floor(millis / 60_000) % 60
+  const secs = (millis % 60_000) / 1000
+  const sign = seconds < 0 && millis > 0 ? "-" : ""
+  const pad = (n: number) => n < 10 ? `0${n}` : `${n}`
+  return `${sign}${hours}:${pad(minutes)}:${pad(secs)}`
+}
+
+/* Fractions are shown reduced (`7/2`) or, when requested with `mixed(x)`, as mixed numbers (`3 1/2`). */
+const formatFraction = (numer: bigint, denom: bigint, mixed: boolean): string => {
+  if (denom === 1n) {
+    return numer.toString()
+  }
+  const whole = numer / denom
+  if (!mixed || whole === 0n) {
+    return `${numer}/${denom}`
+  }
+  const rest = numer % denom
+  return `${whole} ${rest < 0n ? -rest : rest}/${denom}`
+}
+
 export interface Operation {
   operation: string,
-  result: number
+  /* The result, null for a note. */
+  result: Value | null,
+  /* The representation requested with a `to hex`-like suffix, e.g. "hex" or "base 36". */
+  representation?: string,
+  /* The result as displayed with the format preferences. */
+  formatted: string,
+  /* The text written by a command such as `help sin`, shown instead of the result. */
+  note?: string,
+  /* The formula variables computed again after the operation, received with `variables_changed`. */
+  updated?: ChangedVariable[]
+}
+
+/* A live preview result, formatted by the backend like the history. */
+export interface FormattedValue {
+  result: Value,
+  formatted: string
+}
+
+/* A variable defined with `:=` computed again after a variable it reads changed, with its new value or the error of its formula. */
+export interface ChangedVariable {
+  name: string,
+  value: FormattedValue | null,
+  error: string | null
+}
+
+export const formatValue = (value: Value): string => {
+  if (value === null || value === undefined) {
+    return "Infinity"
+  }
+  if (typeof value === "number") {
+    return value.toString()
+  }
+  if (Array.isArray(value)) {
+    return `[${value.map(formatValue).join(", ")}]`
+  }
+  if (isTable(value)) {
+    return `${value.rows.length} × ${value.columns.length} table`
+  }
+  if ("quantity" in value) {
+    if (value.unit.clock) {
+      return formatClock(value.quantity)
+    }
+    const rates = value.unit.updated === undefined
+      ? ""
+      : ` (rates of ${new Date(value.unit.updated * 1000).toISOString().slice(0, 10)})`
+    return `${formatValue(value.quantity)} ${formatUnit(value.unit.symbols)}${rates}`
+  }
+  if ("date" in value) {
+    const [day, time] = value.date.split("T")
+    const clock = time.endsWith(":00") ? time.slice(0, 5) : time
+    return `${value.time ? `${day} ${clock}` : day}${value.zone ? ` ${value.zone}` : ""}`
+  }
+  if ("integer" in value) {
+    return value.integer
+  }
+  if ("numer" in value) {
+    return formatFraction(BigInt(value.numer), BigInt(value.denom), value.mixed)
+  }
+  if ("decimal" in value) {
+    return `${value.approximate ? "≈" : ""}${value.decimal}`
+  }
+  if (value.re === 0) {
+    return `${value.im}i`
+  }
+  return `${value.re}${value.im < 0 ? "-" : "+"}${Math.abs(value.im)}i`
 }
 
 interface OperationHistoryContextValues {
@@ -28,12 +165,19 @@ export const OperationHistoryProvider: FC<OperationHistoryProviderProps> = ({ ch
     setHistory(history => [...history, operation])
   }
 
-  useEffect(() => {
+  const fetchHistory = () => {
     tauri.invoke<Operation[]>('get_operation_history_command').then((history) => {
       setHistory(history)
     })
+  }
+
+  useEffect(() => {
+    fetchHistory()
     let unlisten_clearHistory: UnlistenFn
     let unlisten_addToHistory: UnlistenFn
+    let unlisten_formatChanged: UnlistenFn
+    let unlisten_workspaceChanged: UnlistenFn
+    let unlisten_variablesChanged: UnlistenFn
     let isCancelled = false;
     (async () => {
       unlisten_clearHistory = await listen('history_cleared', (history) => {
@@ -42,9 +186,27 @@ export const OperationHistoryProvider: FC<OperationHistoryProviderProps> = ({ ch
       unlisten_addToHistory = await listen<Operation>('add_to_history', (event) => {
         addToHistory(event.payload)
       })
+      /* The results are formatted by the backend, fetch them again with the new settings. */
+      unlisten_formatChanged = await listen('format_changed', () => {
+        fetchHistory()
+      })
+      /* Each workspace has its own history. */
+      unlisten_workspaceChanged = await listen('workspace_changed', () => {
+        fetchHistory()
+      })
+      /* Sent right after `add_to_history`, the changes are shown under the operation causing them. */
+      unlisten_variablesChanged = await listen<ChangedVariable[]>('variables_changed', (event) => {
+        setHistory(history => history.length === 0 ? history : [
+          ...history.slice(0, -1),
+          { ...history[history.length - 1], updated: event.payload }
+        ])
+      })
       if (isCancelled) {
         unlisten_clearHistory?.()
         unlisten_addToHistory?.()
+        unlisten_formatChanged?.()
+        unlisten_workspaceChanged?.()
+        unlisten_variablesChanged?.()
       }
     })()
 
@@ -52,6 +214,9 @@ export const OperationHistoryProvider: FC<OperationHistoryProviderProps> = ({ ch
       isCancelled = true
       unlisten_clearHistory?.()
       unlisten_addToHistory?.()
+      unlisten_formatChanged?.()
+      unlisten_workspaceChanged?.()
+      unlisten_variablesChanged?.()
     }
   }, [])
 
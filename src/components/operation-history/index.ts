@@ -1,4 +1,4 @@
 export { default as OperationHistory } from './OperationHistory'
-export { OperationHistoryContext, OperationHistoryProvider } from './OperationHistoryContext'
-export type { Operation } from './OperationHistoryContext'
+export { formatValue, isTable, OperationHistoryContext, OperationHistoryProvider } from './OperationHistoryContext'
+export type { FormattedValue, Operation, Quantity, Table, Value } from './OperationHistoryContext'
 
@@ -0,0 +1,37 @@
+import { style } from "@vanilla-extract/css"
+import { sprinkles, theme } from "../../styles"
+import { textColours } from "../../styles/theme"
+
+export const root = style({
+  display: 'flex',
+  alignItems: 'center',
+  gap: theme.spacing.xsmall,
+})
+
+export const select = style([
+  sprinkles({
+    color: textColours.strong,
+    background: {
+      lightMode: 'grey100',
+      darkMode: 'grey800',
+    },
+  }),
+  {
+    padding: theme.spacing.xsmall,
+    border: 'none',
+    borderRadius: theme.border.radius.small,
+    outline: 'none',
+  }
+])
+
+export const button = style([
+  sprinkles({
+    color: textColours.strong,
+  }),
+  {
+    padding: theme.spacing.xsmall,
+    border: 'none',
+    background: 'transparent',
+    cursor: 'pointer',
+  }
+])
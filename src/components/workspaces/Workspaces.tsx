@@ -0,0 +1,117 @@
+import { tauri } from "@tauri-apps/api"
+import { listen, UnlistenFn } from "@tauri-apps/api/event"
+import { FC, useEffect, useState } from "react"
+import * as styles from "./Workspaces.css"
+
+/* Mirrors the `Workspace` of the backend, a named history with its own variables. */
+export interface Workspace {
+  id: number,
+  name: string,
+  active: boolean
+}
+
+/* The option of the select creating a workspace, the ids of the database are positive. */
+const NEW_WORKSPACE = -1
+
+const Workspaces: FC = () => {
+  const [workspaces, setWorkspaces] = useState<Workspace[]>([])
+  const active = workspaces.find((workspace) => workspace.active)
+
+  const fetchWorkspaces = () => {
+    tauri.invoke<Workspace[]>('get_workspaces_command')
+      .then(setWorkspaces)
+      .catch((err) => console.error(err))
+  }
+
+  useEffect(() => {
+    fetchWorkspaces()
+    let unlisten: UnlistenFn
+    let isCancelled = false;
+    (async () => {
+      unlisten = await listen('workspace_changed', () => {
+        fetchWorkspaces()
+      })
+      if (isCancelled) {
+        unlisten?.()
+      }
+    })()
+
+    return () => {
+      isCancelled = true
+      unlisten?.()
+    }
+  }, [])
+
+  /* The history and the variables are reloaded by the listeners of `workspace_changed`. */
+  const switchTo = (id: number) => {
+    tauri.invoke<Workspace>('switch_workspace_command', { id })
+      .catch((err) => window.alert(err))
+  }
+
+  const create = () => {
+    const name = window.prompt("Name of the new workspace")
+    if (name === null) {
+      return
+    }
+    tauri.invoke<Workspace>('create_workspace_command', { name })
+      .then((workspace) => switchTo(workspace.id))
+      .catch((err) => window.alert(err))
+  }
+
+  const rename = () => {
+    if (!active) {
+      return
+    }
+    const name = window.prompt("New name of the workspace", active.name)
+    if (name === null) {
+      return
+    }
+    tauri.invoke<Workspace>('rename_workspace_command', { id: active.id, name })
+      .then(fetchWorkspaces)
+      .catch((err) => window.alert(err))
+  }
+
+  const remove = () => {
+    if (!active || !window.confirm(`Delete ${active.name} with its history and variables?`)) {
+      return
+    }
+    tauri.invoke<Workspace>('delete_workspace_command', { id: active.id })
+      .catch((err) => window.alert(err))
+  }
+
+  const handleOnChange = (event: React.ChangeEvent<HTMLSelectElement>) => {
+    const id = Number(event.target.value)
+    if (id === NEW_WORKSPACE) {
+      create()
+    } else {
+      switchTo(id)
+    }
+  }
+
+  return (
+    <div className={styles.root}>
+      <select
+        className={styles.select}
+        title="Workspace"
+        value={active?.id ?? ""}
+        onChange={handleOnChange}
+      >
+        {workspaces.map((workspace) => (
+          <option key={workspace.id} value={workspace.id}>{workspace.name}</option>
+        ))}
+        <option value={NEW_WORKSPACE}>New workspace…</option>
+      </select>
+      <button className={styles.button} title="Rename the workspace" onClick={rename}>✎</button>
+      <button
+        className={styles.button}
+        title="Delete the workspace"
+        onClick={remove}
+        disabled={workspaces.length < 2}
+      >
+        ✕
+      </button>
+    </div>
+  )
+}
+
+export default Workspaces
@@ -0,0 +1,2 @@
+export { default as Workspaces } from './Workspaces'
+export type { Workspace } from './Workspaces'
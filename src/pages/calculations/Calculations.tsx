@@ -2,6 +2,7 @@ import { ColorModeToggle } from '../../components/colour-mode/ColourMode'
 import MathInput, { CurrentOperationProvider } from '../../components/math-input'
 import { OperationHistory, OperationHistoryProvider } from '../../components/operation-history'
 import { Box } from '../../components/system/box/Box'
+import { Workspaces } from '../../components/workspaces'
 
 function CalculationsPage() {
 
@@ -23,6 +24,13 @@ function CalculationsPage() {
       >
         <ColorModeToggle />
       </Box>
+      <Box
+        position="fixed"
+        top="medium"
+        left="medium"
+      >
+        <Workspaces />
+      </Box>
       <OperationHistoryProvider>
         <CurrentOperationProvider>
           <Box